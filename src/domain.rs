@@ -3,11 +3,13 @@ use std::{error::Error, fmt};
 pub(crate) mod delivery_slip;
 pub(crate) mod dto;
 pub(crate) mod invoice;
+pub(crate) mod invoice_line;
 pub(crate) mod language;
 pub(crate) mod mapping_client;
 pub(crate) mod new_type;
 pub(crate) mod order;
 pub(crate) mod order_line;
+pub(crate) mod payment;
 pub(crate) mod product;
 pub(crate) mod vo;
 
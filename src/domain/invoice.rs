@@ -5,10 +5,11 @@ use super::{
     dto::date_dto::DateDTO,
     language::Language,
     vo::{
+        document_type::DocumentType,
         file_name::FileName,
         locale::Locale,
         localized_item::{LocalizedItem, LocalizedItemFactory},
-        price::Price,
+        price::{Currency, Price},
         Reference, Translation,
     },
     DomainEntity, DomainError,
@@ -26,8 +27,12 @@ pub struct Invoice {
     invoice_types: Vec<LocalizedItem>,
     total_tax_excl: Price,
     total_tax_incl: Price,
+    document_type: DocumentType,
 }
 
+// Rounding difference allowed between the two totals, in cents
+pub(crate) const TOTAL_TOLERANCE_IN_CENTS: i64 = 1;
+
 impl DomainEntity for Invoice {}
 
 impl Invoice {
@@ -70,6 +75,14 @@ impl Invoice {
     pub fn total_tax_incl(&self) -> Decimal {
         self.total_tax_incl.get_amount_as_decimal()
     }
+
+    pub fn currency(&self) -> Currency {
+        self.total_tax_excl.get_currency()
+    }
+
+    pub fn document_type(&self) -> DocumentType {
+        self.document_type
+    }
 }
 
 pub struct InvoiceDomainFactory {
@@ -81,12 +94,23 @@ pub struct InvoiceDomainFactory {
     pub date_dto: DateDTO,
     pub po_ref: Option<String>,
     pub invoice_types: Vec<LocalizedItem>,
-    pub total_tax_excl: String,
-    pub total_tax_incl: String,
+    pub total_tax_excl: Price,
+    pub total_tax_incl: Price,
+    // Inferred from the sign of the totals when the source has no document base type
+    pub document_type: Option<DocumentType>,
 }
 
 impl InvoiceDomainFactory {
     pub fn make(self) -> Result<Invoice, DomainError> {
+        if self.total_tax_excl.get_currency() != self.total_tax_incl.get_currency() {
+            return Err(DomainError::ValidationError(format!(
+                "Invoice {} totals are in {} and {}",
+                self.invoice_id,
+                self.total_tax_excl.get_currency(),
+                self.total_tax_incl.get_currency()
+            )));
+        }
+        let (document_type, total_tax_excl, total_tax_incl) = self.signed_totals()?;
         Ok(Invoice {
             invoice_id: self.invoice_id,
             client_id: self.client_id,
@@ -96,10 +120,54 @@ impl InvoiceDomainFactory {
             date: self.date_dto.unwrap()?,
             po_ref: self.po_ref,
             invoice_types: self.invoice_types,
-            total_tax_excl: Price::try_from(self.total_tax_excl)?,
-            total_tax_incl: Price::try_from(self.total_tax_incl)?,
+            total_tax_excl,
+            total_tax_incl,
+            document_type,
         })
     }
+
+    // Credit notes have negative totals, Idempiere exporting some of them as positive amounts.
+    // Both totals have the same sign and taxes cannot make the included total smaller.
+    fn signed_totals(&self) -> Result<(DocumentType, Price, Price), DomainError> {
+        let (excl, incl) = (
+            self.total_tax_excl.get_amount_in_cents(),
+            self.total_tax_incl.get_amount_in_cents(),
+        );
+        if excl.signum() * incl.signum() < 0 {
+            return Err(DomainError::ValidationError(format!(
+                "Invoice {} totals have opposite signs: {} and {}",
+                self.invoice_id, self.total_tax_excl, self.total_tax_incl
+            )));
+        }
+        if incl.abs() + TOTAL_TOLERANCE_IN_CENTS < excl.abs() {
+            return Err(DomainError::ValidationError(format!(
+                "Invoice {} total with taxes {} is below the total without taxes {}",
+                self.invoice_id, self.total_tax_incl, self.total_tax_excl
+            )));
+        }
+
+        let is_negative = excl < 0 || incl < 0;
+        match (self.document_type, is_negative) {
+            (Some(DocumentType::Invoice), true) => Err(DomainError::ValidationError(format!(
+                "Invoice {} has negative totals, expected a credit note",
+                self.invoice_id
+            ))),
+            (Some(DocumentType::CreditNote), false) => Ok((
+                DocumentType::CreditNote,
+                self.total_tax_excl.negated(),
+                self.total_tax_incl.negated(),
+            )),
+            (document_type, _) => Ok((
+                document_type.unwrap_or(if is_negative {
+                    DocumentType::CreditNote
+                } else {
+                    DocumentType::Invoice
+                }),
+                self.total_tax_excl,
+                self.total_tax_incl,
+            )),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -151,6 +219,7 @@ pub mod tests {
                 ],
                 total_tax_excl: Price::try_from("100.0".to_string()).unwrap(),
                 total_tax_incl: Price::try_from("120.00".to_string()).unwrap(),
+                document_type: DocumentType::Invoice,
             },
             Invoice {
                 invoice_id: 3,
@@ -162,8 +231,71 @@ pub mod tests {
                 po_ref: None,
                 invoice_types: vec![localized_item_fixtures()[2].clone()],
                 total_tax_excl: Price::try_from("-300.0".to_string()).unwrap(),
-                total_tax_incl: Price::try_from("360.0".to_string()).unwrap(),
+                total_tax_incl: Price::try_from("-360.0".to_string()).unwrap(),
+                document_type: DocumentType::CreditNote,
             },
         ]
     }
+
+    fn factory(
+        total_tax_excl: &str,
+        total_tax_incl: &str,
+        document_type: Option<DocumentType>,
+    ) -> InvoiceDomainFactory {
+        InvoiceDomainFactory {
+            invoice_id: 3,
+            client_id: 1,
+            client_name: None,
+            invoice_ref: "INV-3".to_string(),
+            file_name: None,
+            date_dto: DateDTO::from(NaiveDate::from_ymd_opt(2020, 1, 3).unwrap()),
+            po_ref: None,
+            invoice_types: Vec::new(),
+            total_tax_excl: Price::try_from(total_tax_excl.to_string()).unwrap(),
+            total_tax_incl: Price::try_from(total_tax_incl.to_string()).unwrap(),
+            document_type,
+        }
+    }
+
+    #[test]
+    fn test_make_infers_credit_notes() {
+        let credit_note = factory("-300.0", "-360.0", None).make().unwrap();
+        assert_eq!(credit_note.document_type(), DocumentType::CreditNote);
+
+        let invoice = factory("0", "0", None).make().unwrap();
+        assert_eq!(invoice.document_type(), DocumentType::Invoice);
+    }
+
+    #[test]
+    fn test_make_negates_positive_credit_notes() {
+        let credit_note = factory("300.0", "360.0", Some(DocumentType::CreditNote))
+            .make()
+            .unwrap();
+        assert_eq!(credit_note.total_tax_excl(), Decimal::new(-30000, 2));
+        assert_eq!(credit_note.total_tax_incl(), Decimal::new(-36000, 2));
+    }
+
+    #[test]
+    fn test_make_rejects_mismatched_currencies() {
+        let invoice = InvoiceDomainFactory {
+            total_tax_incl: Price::try_from("360.0".to_string())
+                .unwrap()
+                .with_currency(Currency::Chf),
+            ..factory("300.0", "360.0", None)
+        };
+        assert_eq!(
+            invoice.make().unwrap_err(),
+            DomainError::ValidationError("Invoice 3 totals are in EUR and CHF".to_string())
+        );
+    }
+
+    #[test]
+    fn test_make_rejects_inconsistent_totals() {
+        assert!(factory("-300.0", "360.0", None).make().is_err());
+        assert!(factory("300.0", "299.98", None).make().is_err());
+        assert!(factory("300.0", "299.99", None).make().is_ok());
+        assert!(factory("-300.0", "-360.0", Some(DocumentType::Invoice))
+            .make()
+            .is_err());
+    }
 }
@@ -0,0 +1,226 @@
+use rust_decimal::Decimal;
+
+use super::{
+    invoice::TOTAL_TOLERANCE_IN_CENTS,
+    vo::{
+        document_type::DocumentType,
+        price::{Currency, Price},
+        Reference,
+    },
+    DomainEntity, DomainError,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvoiceLine {
+    invoiceline_id: u32,
+    invoice_id: u32,
+    product_ref: Reference,
+    // Negative for returned items, e.g. on credit notes
+    quantity: Decimal,
+    unit_price: Price,
+    // Percentage, e.g. 20 for 20%
+    tax_rate: Decimal,
+    total_tax_excl: Price,
+    total_tax_incl: Price,
+}
+
+impl DomainEntity for InvoiceLine {}
+
+impl InvoiceLine {
+    pub fn invoiceline_id(&self) -> u32 {
+        self.invoiceline_id
+    }
+
+    pub fn invoice_id(&self) -> u32 {
+        self.invoice_id
+    }
+
+    pub fn product_ref(&self) -> &str {
+        self.product_ref.as_str()
+    }
+
+    pub fn quantity(&self) -> Decimal {
+        self.quantity
+    }
+
+    pub fn unit_price(&self) -> Decimal {
+        self.unit_price.get_amount_as_decimal()
+    }
+
+    pub fn tax_rate(&self) -> Decimal {
+        self.tax_rate
+    }
+
+    pub fn total_tax_excl(&self) -> Decimal {
+        self.total_tax_excl.get_amount_as_decimal()
+    }
+
+    pub fn total_tax_incl(&self) -> Decimal {
+        self.total_tax_incl.get_amount_as_decimal()
+    }
+}
+
+/// Lines of an invoice, given by their totals without taxes, must add up to its own
+pub fn check_lines_total(
+    invoice_id: u32,
+    line_totals: &[Decimal],
+    total_tax_excl: Decimal,
+) -> Result<(), DomainError> {
+    let sum: Decimal = line_totals.iter().sum();
+    if (sum - total_tax_excl).abs() > Decimal::new(TOTAL_TOLERANCE_IN_CENTS, 2) {
+        return Err(DomainError::ValidationError(format!(
+            "Invoice {} lines add up to {}, expected its total without taxes {}",
+            invoice_id, sum, total_tax_excl
+        )));
+    }
+    Ok(())
+}
+
+pub struct InvoiceLineDomainFactory {
+    pub invoiceline_id: u32,
+    pub invoice_id: u32,
+    pub product_ref: String,
+    pub quantity: Decimal,
+    pub unit_price: Price,
+    pub tax_rate: Decimal,
+    pub total_tax_excl: Price,
+    pub total_tax_incl: Price,
+}
+
+impl InvoiceLineDomainFactory {
+    /// Line of an invoice in the given currency. Lines of credit notes have negative amounts,
+    /// Idempiere exporting them as positive ones.
+    pub fn make(
+        self,
+        currency: Currency,
+        document_type: DocumentType,
+    ) -> Result<InvoiceLine, DomainError> {
+        if self.tax_rate < Decimal::ZERO || self.tax_rate > Decimal::ONE_HUNDRED {
+            return Err(DomainError::ValidationError(format!(
+                "Invoice line {} tax rate {} is not a percentage",
+                self.invoiceline_id, self.tax_rate
+            )));
+        }
+        let (excl, incl) = (
+            self.total_tax_excl.get_amount_in_cents(),
+            self.total_tax_incl.get_amount_in_cents(),
+        );
+        if excl.signum() * incl.signum() < 0 || incl.abs() + TOTAL_TOLERANCE_IN_CENTS < excl.abs() {
+            return Err(DomainError::ValidationError(format!(
+                "Invoice line {} totals {} and {} are inconsistent",
+                self.invoiceline_id, self.total_tax_excl, self.total_tax_incl
+            )));
+        }
+
+        let sign = |price: Price| {
+            let price = price.with_currency(currency);
+            if document_type == DocumentType::CreditNote && excl > 0 {
+                price.negated()
+            } else {
+                price
+            }
+        };
+        Ok(InvoiceLine {
+            invoiceline_id: self.invoiceline_id,
+            invoice_id: self.invoice_id,
+            product_ref: Reference::new(self.product_ref)?,
+            quantity: self.quantity,
+            unit_price: sign(self.unit_price),
+            tax_rate: self.tax_rate,
+            total_tax_excl: sign(self.total_tax_excl),
+            total_tax_incl: sign(self.total_tax_incl),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn invoice_line_fixtures() -> [InvoiceLine; 3] {
+        let price = |value: &str| Price::try_from(value.to_string()).unwrap();
+        [
+            InvoiceLine {
+                invoiceline_id: 1,
+                invoice_id: 1,
+                product_ref: Reference::new("P-1".to_string()).unwrap(),
+                quantity: Decimal::from(2),
+                unit_price: price("30.00"),
+                tax_rate: Decimal::new(20, 0),
+                total_tax_excl: price("60.00"),
+                total_tax_incl: price("72.00"),
+            },
+            InvoiceLine {
+                invoiceline_id: 2,
+                invoice_id: 1,
+                product_ref: Reference::new("P-2".to_string()).unwrap(),
+                quantity: Decimal::ONE,
+                unit_price: price("40.0"),
+                tax_rate: Decimal::new(200, 1),
+                total_tax_excl: price("40.0"),
+                total_tax_incl: price("48.0"),
+            },
+            InvoiceLine {
+                invoiceline_id: 3,
+                invoice_id: 3,
+                product_ref: Reference::new("P-3".to_string()).unwrap(),
+                quantity: Decimal::from(3),
+                unit_price: price("-100"),
+                tax_rate: Decimal::new(20, 0),
+                total_tax_excl: price("-300"),
+                total_tax_incl: price("-360"),
+            },
+        ]
+    }
+
+    fn factory(total_tax_excl: &str, total_tax_incl: &str) -> InvoiceLineDomainFactory {
+        let price = |value: &str| Price::try_from(value.to_string()).unwrap();
+        InvoiceLineDomainFactory {
+            invoiceline_id: 3,
+            invoice_id: 3,
+            product_ref: "P-3".to_string(),
+            quantity: Decimal::from(3),
+            unit_price: price("100"),
+            tax_rate: Decimal::new(20, 0),
+            total_tax_excl: price(total_tax_excl),
+            total_tax_incl: price(total_tax_incl),
+        }
+    }
+
+    #[test]
+    fn test_make_credit_note_line() {
+        let line = factory("300", "360")
+            .make(Currency::Eur, DocumentType::CreditNote)
+            .unwrap();
+        assert_eq!(line, invoice_line_fixtures()[2]);
+
+        let line = factory("-300", "-360")
+            .make(Currency::Eur, DocumentType::CreditNote)
+            .unwrap();
+        assert_eq!(line, invoice_line_fixtures()[2]);
+    }
+
+    #[test]
+    fn test_make_rejects_inconsistent_totals() {
+        assert!(factory("300", "-360")
+            .make(Currency::Eur, DocumentType::Invoice)
+            .is_err());
+        assert!(factory("300", "200")
+            .make(Currency::Eur, DocumentType::Invoice)
+            .is_err());
+        let line = InvoiceLineDomainFactory {
+            tax_rate: Decimal::new(120, 0),
+            ..factory("300", "360")
+        };
+        assert!(line.make(Currency::Eur, DocumentType::Invoice).is_err());
+    }
+
+    #[test]
+    fn test_check_lines_total() {
+        let [first, second, _] = invoice_line_fixtures();
+        let (first, second) = (first.total_tax_excl(), second.total_tax_excl());
+        assert!(check_lines_total(1, &[first, second], Decimal::new(10000, 2)).is_ok());
+        assert!(check_lines_total(1, &[first, second], Decimal::new(10001, 2)).is_ok());
+        assert!(check_lines_total(1, &[first], Decimal::new(10000, 2)).is_err());
+    }
+}
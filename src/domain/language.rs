@@ -38,6 +38,7 @@ impl LanguageDomainFactory {
     }
 }
 
+#[cfg(test)]
 pub mod tests {
     use super::*;
     pub fn language_fixtures() -> [Language; 2] {
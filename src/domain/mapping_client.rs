@@ -1,6 +1,6 @@
 use super::{DomainEntity, DomainError};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MappingClient {
     id_customer: u32,
     idp_id_client: u32,
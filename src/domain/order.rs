@@ -88,6 +88,7 @@ impl OrderDomainFactory {
     }
 }
 
+#[cfg(test)]
 pub mod tests {
     use super::*;
     pub fn order_fixtures() -> [Order; 3] {
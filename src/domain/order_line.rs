@@ -1,4 +1,7 @@
+use std::str::FromStr;
+
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
 
 use super::{
     language::Language,
@@ -6,6 +9,7 @@ use super::{
     vo::{
         locale::Locale,
         localized_item::{LocalizedItem, LocalizedItemFactory},
+        unit_of_measure::UnitOfMeasure,
         Reference, Translation,
     },
     DomainEntity, DomainError,
@@ -17,9 +21,10 @@ pub struct OrderLine {
     orderline_id: u32,
     item_ref: Reference,
     item_names: Vec<LocalizedItem>,
-    qty_ordered: u32,
-    qty_reserved: u32,
-    qty_delivered: u32,
+    qty_ordered: Decimal,
+    qty_reserved: Decimal,
+    qty_delivered: Decimal,
+    uom: Option<UnitOfMeasure>,
     due_date: Option<NaiveDate>,
 }
 
@@ -40,18 +45,23 @@ impl OrderLine {
         &self.item_names
     }
 
-    pub fn qty_ordered(&self) -> u32 {
+    /// Negative for returns, as are the reserved and delivered quantities
+    pub fn qty_ordered(&self) -> Decimal {
         self.qty_ordered
     }
 
-    pub fn qty_reserved(&self) -> u32 {
+    pub fn qty_reserved(&self) -> Decimal {
         self.qty_reserved
     }
 
-    pub fn qty_delivered(&self) -> u32 {
+    pub fn qty_delivered(&self) -> Decimal {
         self.qty_delivered
     }
 
+    pub fn uom(&self) -> Option<&UnitOfMeasure> {
+        self.uom.as_ref()
+    }
+
     pub fn due_date(&self) -> Option<NaiveDate> {
         self.due_date
     }
@@ -59,20 +69,78 @@ impl OrderLine {
 
 impl DomainEntity for OrderLine {}
 
+/// What a broken order line rule does: an error rejects the line, a warning keeps it
+/// and allow accepts it silently
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum RuleSeverity {
+    #[default]
+    Error,
+    Warning,
+    Allow,
+}
+
+impl FromStr for RuleSeverity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "error" => Ok(RuleSeverity::Error),
+            "warning" => Ok(RuleSeverity::Warning),
+            "allow" => Ok(RuleSeverity::Allow),
+            _ => Err(format!(
+                "Invalid rule severity {}, expected error, warning or allow",
+                value
+            )),
+        }
+    }
+}
+
+/// Consistency rules of the quantities and due date of an order line
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct OrderLineRules {
+    /// Delivered above ordered
+    pub over_delivery: RuleSeverity,
+    /// Reserved plus delivered above ordered
+    pub over_reservation: RuleSeverity,
+    /// Due date before the order date
+    pub early_due_date: RuleSeverity,
+}
+
 pub struct OrderLineDomainFactory {
     pub order: Order,
     pub orderline_id: u32,
     pub item_ref: String,
     pub item_names: Vec<LocalizedItem>,
-    pub qty_ordered: u32,
-    pub qty_reserved: u32,
-    pub qty_delivered: u32,
+    pub qty_ordered: Decimal,
+    pub qty_reserved: Decimal,
+    pub qty_delivered: Decimal,
+    pub uom: Option<UnitOfMeasure>,
     pub due_date: Option<NaiveDate>,
+    pub rules: OrderLineRules,
 }
 
 impl OrderLineDomainFactory {
-    pub fn make(self) -> Result<OrderLine, DomainError> {
-        Ok(OrderLine {
+    /// Order line along with the broken rules configured as warnings.
+    /// Broken rules configured as errors are all reported in the error.
+    pub fn make_with_warnings(self) -> Result<(OrderLine, Vec<String>), DomainError> {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        for (severity, problem) in self.broken_rules() {
+            match severity {
+                RuleSeverity::Error => errors.push(problem),
+                RuleSeverity::Warning => warnings.push(problem),
+                RuleSeverity::Allow => {}
+            }
+        }
+        if !errors.is_empty() {
+            return Err(DomainError::ValidationError(format!(
+                "Order line {}: {}",
+                self.orderline_id,
+                errors.join("; ")
+            )));
+        }
+
+        let order_line = OrderLine {
             order: self.order,
             orderline_id: self.orderline_id,
             item_ref: Reference::new(self.item_ref)?,
@@ -80,9 +148,72 @@ impl OrderLineDomainFactory {
             qty_ordered: self.qty_ordered,
             qty_reserved: self.qty_reserved,
             qty_delivered: self.qty_delivered,
+            uom: self.uom,
             due_date: self.due_date,
-        })
+        };
+        Ok((order_line, warnings))
+    }
+
+    fn broken_rules(&self) -> Vec<(RuleSeverity, String)> {
+        let mut broken = Vec::new();
+        // Quantities of returns are negative, the rules compare their opposites
+        let sign = if self.qty_ordered.is_sign_negative() {
+            Decimal::NEGATIVE_ONE
+        } else {
+            Decimal::ONE
+        };
+        let ordered = self.qty_ordered * sign;
+        let reserved = self.qty_reserved * sign;
+        let delivered = self.qty_delivered * sign;
+        for (name, quantity, signed) in [
+            ("reserved", reserved, self.qty_reserved),
+            ("delivered", delivered, self.qty_delivered),
+        ] {
+            if quantity.is_sign_negative() && !quantity.is_zero() {
+                broken.push((
+                    RuleSeverity::Error,
+                    format!(
+                        "{} {} and ordered {} have opposite signs",
+                        name, signed, self.qty_ordered
+                    ),
+                ));
+            }
+        }
+        if delivered > ordered {
+            broken.push((
+                self.rules.over_delivery,
+                format!(
+                    "delivered {} above ordered {}",
+                    self.qty_delivered, self.qty_ordered
+                ),
+            ));
+        }
+        // An over-delivered line is only flagged when something is still reserved
+        if reserved > (ordered - delivered).max(Decimal::ZERO) {
+            broken.push((
+                self.rules.over_reservation,
+                format!(
+                    "reserved {} plus delivered {} above ordered {}",
+                    self.qty_reserved, self.qty_delivered, self.qty_ordered
+                ),
+            ));
+        }
+        if let Some(due_date) = self
+            .due_date
+            .filter(|due_date| *due_date < self.order.date())
+        {
+            broken.push((
+                self.rules.early_due_date,
+                format!(
+                    "due date {} before order date {}",
+                    due_date,
+                    self.order.date()
+                ),
+            ));
+        }
+        broken
     }
+
     pub fn new_from_order(order: Order, fields: &OrderLinePrimaryFields) -> Self {
         Self {
             order,
@@ -92,7 +223,9 @@ impl OrderLineDomainFactory {
             qty_ordered: fields.qty_ordered,
             qty_reserved: fields.qty_reserved,
             qty_delivered: fields.qty_delivered,
+            uom: fields.uom.clone(),
             due_date: fields.due_date,
+            rules: OrderLineRules::default(),
         }
     }
 }
@@ -102,9 +235,10 @@ pub struct OrderLinePrimaryFields {
     pub order_id: u32,
     pub orderline_id: u32,
     pub item_ref: String,
-    pub qty_ordered: u32,
-    pub qty_reserved: u32,
-    pub qty_delivered: u32,
+    pub qty_ordered: Decimal,
+    pub qty_reserved: Decimal,
+    pub qty_delivered: Decimal,
+    pub uom: Option<UnitOfMeasure>,
     pub due_date: Option<NaiveDate>,
 }
 
@@ -137,13 +271,13 @@ impl LocalizedItemFactory for OrderLineLocalizedItemFactory {
     }
 }
 
+#[cfg(test)]
 pub mod tests {
     use crate::domain::{
         order::tests::order_fixtures, vo::localized_item::tests::localized_item_fixtures,
     };
 
     use super::*;
-    #[allow(dead_code)] //use in other modules
     pub fn order_line_fixtures() -> [OrderLine; 3] {
         [
             OrderLine {
@@ -154,9 +288,10 @@ pub mod tests {
                     localized_item_fixtures()[0].clone(),
                     localized_item_fixtures()[1].clone(),
                 ],
-                qty_ordered: 10,
-                qty_reserved: 5,
-                qty_delivered: 5,
+                qty_ordered: Decimal::from(10),
+                qty_reserved: Decimal::from(5),
+                qty_delivered: Decimal::from(5),
+                uom: Some(UnitOfMeasure::try_from("PCE").unwrap()),
                 due_date: Some(NaiveDate::from_ymd_opt(2023, 8, 1).unwrap()),
             },
             OrderLine {
@@ -164,9 +299,10 @@ pub mod tests {
                 orderline_id: 2,
                 item_ref: Reference::new("ItemRef2".to_string()).unwrap(),
                 item_names: vec![localized_item_fixtures()[2].clone()],
-                qty_ordered: 20,
-                qty_reserved: 10,
-                qty_delivered: 10,
+                qty_ordered: Decimal::from(20),
+                qty_reserved: Decimal::from(10),
+                qty_delivered: Decimal::from(10),
+                uom: Some(UnitOfMeasure::try_from("PCE").unwrap()),
                 due_date: Some(NaiveDate::from_ymd_opt(2023, 8, 2).unwrap()),
             },
             OrderLine {
@@ -174,11 +310,125 @@ pub mod tests {
                 orderline_id: 3,
                 item_ref: Reference::new("ItemRef3".to_string()).unwrap(),
                 item_names: Vec::new(),
-                qty_ordered: 30,
-                qty_reserved: 15,
-                qty_delivered: 15,
+                qty_ordered: Decimal::from(30),
+                qty_reserved: Decimal::from(15),
+                qty_delivered: Decimal::from(15),
+                uom: None,
                 due_date: None,
             },
         ]
     }
+
+    fn factory(qty_ordered: i64, qty_reserved: i64, qty_delivered: i64) -> OrderLineDomainFactory {
+        OrderLineDomainFactory {
+            order: order_fixtures()[0].clone(),
+            orderline_id: 1,
+            item_ref: "ItemRef1".to_string(),
+            item_names: Vec::new(),
+            qty_ordered: Decimal::from(qty_ordered),
+            qty_reserved: Decimal::from(qty_reserved),
+            qty_delivered: Decimal::from(qty_delivered),
+            uom: None,
+            due_date: Some(NaiveDate::from_ymd_opt(2023, 8, 1).unwrap()),
+            rules: OrderLineRules::default(),
+        }
+    }
+
+    #[test]
+    fn test_make_rejects_impossible_quantities() {
+        assert!(factory(10, 5, 5).make_with_warnings().is_ok());
+        assert_eq!(
+            factory(10, 0, 50).make_with_warnings().unwrap_err(),
+            DomainError::ValidationError("Order line 1: delivered 50 above ordered 10".to_string())
+        );
+        assert_eq!(
+            factory(10, 6, 5).make_with_warnings().unwrap_err(),
+            DomainError::ValidationError(
+                "Order line 1: reserved 6 plus delivered 5 above ordered 10".to_string()
+            )
+        );
+        let early = OrderLineDomainFactory {
+            due_date: Some(NaiveDate::from_ymd_opt(2023, 7, 31).unwrap()),
+            ..factory(10, 5, 5)
+        };
+        assert!(matches!(
+            early.make_with_warnings(),
+            Err(DomainError::ValidationError(message)) if message.contains("due date 2023-07-31")
+        ));
+    }
+
+    #[test]
+    fn test_make_with_fractional_and_return_quantities() {
+        let (order_line, _) = OrderLineDomainFactory {
+            qty_ordered: Decimal::new(25, 1),
+            qty_reserved: Decimal::ZERO,
+            qty_delivered: Decimal::new(25, 1),
+            uom: Some(UnitOfMeasure::try_from("MTR").unwrap()),
+            ..factory(0, 0, 0)
+        }
+        .make_with_warnings()
+        .unwrap();
+        assert_eq!(order_line.qty_ordered(), Decimal::new(25, 1));
+        assert_eq!(order_line.uom().unwrap().as_str(), "MTR");
+
+        assert!(factory(-10, -5, -5).make_with_warnings().is_ok());
+        assert_eq!(
+            factory(-10, 0, -12).make_with_warnings().unwrap_err(),
+            DomainError::ValidationError(
+                "Order line 1: delivered -12 above ordered -10".to_string()
+            )
+        );
+        assert_eq!(
+            factory(-10, 0, 5).make_with_warnings().unwrap_err(),
+            DomainError::ValidationError(
+                "Order line 1: delivered 5 and ordered -10 have opposite signs".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_make_with_warnings() {
+        let rules = OrderLineRules {
+            over_delivery: RuleSeverity::Warning,
+            ..OrderLineRules::default()
+        };
+        let (order_line, warnings) = OrderLineDomainFactory {
+            rules,
+            ..factory(10, 0, 50)
+        }
+        .make_with_warnings()
+        .unwrap();
+        assert_eq!(order_line.qty_delivered(), Decimal::from(50));
+        assert_eq!(warnings, vec!["delivered 50 above ordered 10".to_string()]);
+
+        // Still rejected by the reservation rule
+        assert!(OrderLineDomainFactory {
+            rules,
+            ..factory(10, 1, 50)
+        }
+        .make_with_warnings()
+        .is_err());
+    }
+
+    #[test]
+    fn test_make_allows_over_delivery() {
+        let rules = OrderLineRules {
+            over_delivery: RuleSeverity::Allow,
+            ..OrderLineRules::default()
+        };
+        let (_, warnings) = OrderLineDomainFactory {
+            rules,
+            ..factory(10, 0, 12)
+        }
+        .make_with_warnings()
+        .unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_rule_severity_from_str() {
+        assert_eq!("Warning".parse::<RuleSeverity>(), Ok(RuleSeverity::Warning));
+        assert_eq!("allow".parse::<RuleSeverity>(), Ok(RuleSeverity::Allow));
+        assert!("ignore".parse::<RuleSeverity>().is_err());
+    }
 }
@@ -0,0 +1,328 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use super::{
+    dto::date_dto::DateDTO,
+    invoice::TOTAL_TOLERANCE_IN_CENTS,
+    vo::{
+        price::{Currency, Price},
+        Reference,
+    },
+    DomainEntity, DomainError,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payment {
+    payment_id: u32,
+    payment_ref: Reference,
+    date: NaiveDate,
+    amount: Price,
+    allocations: Vec<PaymentAllocation>,
+}
+
+impl DomainEntity for Payment {}
+
+impl Payment {
+    pub fn payment_id(&self) -> u32 {
+        self.payment_id
+    }
+
+    pub fn payment_ref(&self) -> &str {
+        self.payment_ref.as_str()
+    }
+
+    pub fn date(&self) -> &NaiveDate {
+        &self.date
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount.get_amount_as_decimal()
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.amount.get_currency()
+    }
+
+    pub fn allocations(&self) -> &[PaymentAllocation] {
+        &self.allocations
+    }
+}
+
+/// Part of a payment settling an invoice, as in Idempiere C_AllocationLine
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentAllocation {
+    allocationline_id: u32,
+    payment_id: u32,
+    invoice_id: u32,
+    amount: Price,
+    discount: Price,
+    write_off: Price,
+}
+
+impl PaymentAllocation {
+    pub fn allocationline_id(&self) -> u32 {
+        self.allocationline_id
+    }
+
+    pub fn payment_id(&self) -> u32 {
+        self.payment_id
+    }
+
+    pub fn invoice_id(&self) -> u32 {
+        self.invoice_id
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount.get_amount_as_decimal()
+    }
+
+    pub fn discount(&self) -> Decimal {
+        self.discount.get_amount_as_decimal()
+    }
+
+    pub fn write_off(&self) -> Decimal {
+        self.write_off.get_amount_as_decimal()
+    }
+}
+
+pub struct PaymentDomainFactory {
+    pub payment_id: u32,
+    pub payment_ref: String,
+    pub date_dto: DateDTO,
+    pub amount: Price,
+}
+
+impl PaymentDomainFactory {
+    pub fn currency(&self) -> Currency {
+        self.amount.get_currency()
+    }
+
+    /// Payment with its allocations, which cannot settle more than the amount paid
+    pub fn make(self, allocations: Vec<PaymentAllocation>) -> Result<Payment, DomainError> {
+        let allocated: i64 = allocations
+            .iter()
+            .map(|allocation| allocation.amount.get_amount_in_cents())
+            .sum();
+        if allocated.abs() > self.amount.get_amount_in_cents().abs() + TOTAL_TOLERANCE_IN_CENTS {
+            return Err(DomainError::ValidationError(format!(
+                "Payment {} allocates {}, more than its amount {}",
+                self.payment_id,
+                Decimal::new(allocated, 2),
+                self.amount
+            )));
+        }
+
+        Ok(Payment {
+            payment_id: self.payment_id,
+            payment_ref: Reference::new(self.payment_ref)?,
+            date: self.date_dto.unwrap()?,
+            amount: self.amount,
+            allocations,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PaymentAllocationFactory {
+    pub allocationline_id: u32,
+    pub payment_id: u32,
+    pub invoice_id: u32,
+    pub amount: Price,
+    pub discount: Price,
+    pub write_off: Price,
+}
+
+impl PaymentAllocationFactory {
+    /// Allocation of a payment to an invoice, both being in the same currency
+    pub fn make(
+        self,
+        payment_currency: Currency,
+        invoice_currency: Currency,
+    ) -> Result<PaymentAllocation, DomainError> {
+        if payment_currency != invoice_currency {
+            return Err(DomainError::ValidationError(format!(
+                "Allocation {} of payment {} in {} settles invoice {} in {}",
+                self.allocationline_id,
+                self.payment_id,
+                payment_currency,
+                self.invoice_id,
+                invoice_currency
+            )));
+        }
+
+        Ok(PaymentAllocation {
+            allocationline_id: self.allocationline_id,
+            payment_id: self.payment_id,
+            invoice_id: self.invoice_id,
+            amount: self.amount.with_currency(payment_currency),
+            discount: self.discount.with_currency(payment_currency),
+            write_off: self.write_off.with_currency(payment_currency),
+        })
+    }
+}
+
+/// Part of an invoice total with taxes left to pay
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvoiceOpenAmount {
+    invoice_id: u32,
+    open_amount: Price,
+    is_paid: bool,
+}
+
+impl DomainEntity for InvoiceOpenAmount {}
+
+impl InvoiceOpenAmount {
+    /// Settled amounts, discounts and write-offs included, are deducted from the total.
+    /// Credit notes being negative, so are their allocations.
+    pub fn compute(invoice_id: u32, total_tax_incl: Price, settled: &[Price]) -> Self {
+        let settled_in_cents: i64 = settled.iter().map(Price::get_amount_in_cents).sum();
+        let total = total_tax_incl.get_amount_in_cents();
+        let open = total - settled_in_cents;
+        Self {
+            invoice_id,
+            open_amount: Price::new(open, total_tax_incl.get_currency()),
+            // Overpaid invoices are paid too
+            is_paid: open == 0 || open.signum() == -total.signum(),
+        }
+    }
+
+    pub fn invoice_id(&self) -> u32 {
+        self.invoice_id
+    }
+
+    pub fn open_amount(&self) -> Decimal {
+        self.open_amount.get_amount_as_decimal()
+    }
+
+    pub fn is_paid(&self) -> bool {
+        self.is_paid
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn price(value: &str) -> Price {
+        Price::try_from(value.to_string()).unwrap()
+    }
+
+    pub fn payment_allocation_fixtures() -> [PaymentAllocation; 3] {
+        [
+            PaymentAllocation {
+                allocationline_id: 1,
+                payment_id: 1,
+                invoice_id: 1,
+                amount: price("100.00"),
+                discount: price("0"),
+                write_off: price("0"),
+            },
+            PaymentAllocation {
+                allocationline_id: 2,
+                payment_id: 2,
+                invoice_id: 1,
+                amount: price("19.50"),
+                discount: price("0.50"),
+                write_off: price("0"),
+            },
+            PaymentAllocation {
+                allocationline_id: 3,
+                payment_id: 3,
+                invoice_id: 3,
+                amount: price("-200.00"),
+                discount: price("0"),
+                write_off: price("0"),
+            },
+        ]
+    }
+
+    pub fn payment_fixtures() -> [Payment; 3] {
+        let [first, second, third] = payment_allocation_fixtures();
+        [
+            Payment {
+                payment_id: 1,
+                payment_ref: Reference::new("PAY-1".to_string()).unwrap(),
+                date: NaiveDate::from_ymd_opt(2020, 2, 1).unwrap(),
+                amount: price("100.00"),
+                allocations: vec![first],
+            },
+            Payment {
+                payment_id: 2,
+                payment_ref: Reference::new("PAY-2".to_string()).unwrap(),
+                date: NaiveDate::from_ymd_opt(2020, 2, 2).unwrap(),
+                amount: price("19.50"),
+                allocations: vec![second],
+            },
+            Payment {
+                payment_id: 3,
+                payment_ref: Reference::new("PAY-3".to_string()).unwrap(),
+                date: NaiveDate::from_ymd_opt(2020, 2, 3).unwrap(),
+                amount: price("-200.00"),
+                allocations: vec![third],
+            },
+        ]
+    }
+
+    fn factory(amount: &str) -> PaymentDomainFactory {
+        PaymentDomainFactory {
+            payment_id: 1,
+            payment_ref: "PAY-1".to_string(),
+            date_dto: DateDTO::from(NaiveDate::from_ymd_opt(2020, 2, 1).unwrap()),
+            amount: price(amount),
+        }
+    }
+
+    #[test]
+    fn test_make_payment() {
+        let [first, _, _] = payment_allocation_fixtures();
+        assert_eq!(
+            factory("100").make(vec![first]).unwrap(),
+            payment_fixtures()[0]
+        );
+    }
+
+    #[test]
+    fn test_make_rejects_allocations_above_amount() {
+        let [first, _, _] = payment_allocation_fixtures();
+        assert!(factory("99.98").make(vec![first]).is_err());
+    }
+
+    #[test]
+    fn test_make_allocation_rejects_other_currency() {
+        let allocation = PaymentAllocationFactory {
+            allocationline_id: 1,
+            payment_id: 1,
+            invoice_id: 1,
+            amount: price("100"),
+            discount: price("0"),
+            write_off: price("0"),
+        };
+        assert!(matches!(
+            allocation.make(Currency::Eur, Currency::Chf),
+            Err(DomainError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_compute_open_amount() {
+        let partly_paid =
+            InvoiceOpenAmount::compute(1, price("120.00"), &[price("100.00"), price("19.50")]);
+        assert_eq!(partly_paid.open_amount(), Decimal::new(50, 2));
+        assert!(!partly_paid.is_paid());
+
+        let paid = InvoiceOpenAmount::compute(1, price("120.00"), &[price("120")]);
+        assert_eq!(paid.open_amount(), Decimal::new(0, 2));
+        assert!(paid.is_paid());
+
+        let overpaid = InvoiceOpenAmount::compute(1, price("120.00"), &[price("121")]);
+        assert!(overpaid.is_paid());
+
+        let credit_note = InvoiceOpenAmount::compute(3, price("-360"), &[price("-200")]);
+        assert_eq!(credit_note.open_amount(), Decimal::new(-16000, 2));
+        assert!(!credit_note.is_paid());
+
+        let unpaid = InvoiceOpenAmount::compute(3, price("-360"), &[]);
+        assert_eq!(unpaid.open_amount(), Decimal::new(-36000, 2));
+        assert!(!unpaid.is_paid());
+    }
+}
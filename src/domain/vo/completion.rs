@@ -1,3 +1,5 @@
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
 use crate::domain::DomainError;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -7,6 +9,27 @@ impl Completion {
     pub fn as_u32(&self) -> u32 {
         self.0
     }
+
+    /// Percentage of the ordered quantities delivered, none when nothing is ordered.
+    /// Returns count by their opposite, over-deliveries as complete lines.
+    pub fn from_quantities(ordered_and_delivered: &[(Decimal, Decimal)]) -> Option<Self> {
+        let (ordered, delivered) = ordered_and_delivered.iter().fold(
+            (Decimal::ZERO, Decimal::ZERO),
+            |(ordered, delivered), (qty_ordered, qty_delivered)| {
+                (
+                    ordered + qty_ordered.abs(),
+                    delivered + qty_delivered.abs().min(qty_ordered.abs()),
+                )
+            },
+        );
+        if ordered.is_zero() {
+            return None;
+        }
+        (delivered * Decimal::ONE_HUNDRED / ordered)
+            .round()
+            .to_u32()
+            .map(Self)
+    }
 }
 
 impl TryFrom<String> for Completion {
@@ -54,6 +77,22 @@ mod tests {
         assert!(completion.is_err());
     }
 
+    #[test]
+    fn test_completion_from_quantities() {
+        let completion = Completion::from_quantities(&[
+            (Decimal::from(10), Decimal::from(5)),
+            (Decimal::new(25, 1), Decimal::new(25, 1)),
+            (Decimal::from(-10), Decimal::from(-3)),
+            (Decimal::new(25, 1), Decimal::from(4)),
+        ]);
+        assert_eq!(completion, Some(Completion(52)));
+        assert_eq!(
+            Completion::from_quantities(&[(Decimal::ZERO, Decimal::ZERO)]),
+            None
+        );
+        assert_eq!(Completion::from_quantities(&[]), None);
+    }
+
     #[test]
     fn test_completion_from() {
         let completion = Completion::from(50);
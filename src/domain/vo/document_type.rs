@@ -0,0 +1,53 @@
+use crate::domain::DomainError;
+
+/// Invoice or credit note, from the Idempiere document base type
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DocumentType {
+    Invoice,
+    CreditNote,
+}
+
+impl TryFrom<&str> for DocumentType {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_uppercase().as_str() {
+            "ARI" | "INVOICE" => Ok(DocumentType::Invoice),
+            "ARC" | "CREDIT_NOTE" => Ok(DocumentType::CreditNote),
+            _ => Err(DomainError::ValidationError(format!(
+                "Unsupported document base type {}, expected ARI or ARC",
+                value
+            ))),
+        }
+    }
+}
+
+impl DocumentType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DocumentType::Invoice => "invoice",
+            DocumentType::CreditNote => "credit_note",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(DocumentType::try_from("ARC"), Ok(DocumentType::CreditNote));
+        assert_eq!(DocumentType::try_from("ari"), Ok(DocumentType::Invoice));
+        assert_eq!(
+            DocumentType::try_from("credit_note"),
+            Ok(DocumentType::CreditNote)
+        );
+        assert!(DocumentType::try_from("API").is_err());
+    }
+
+    #[test]
+    fn test_as_str() {
+        assert_eq!(DocumentType::CreditNote.as_str(), "credit_note");
+    }
+}
@@ -28,6 +28,7 @@ pub trait LocalizedItemFactory {
     fn get_entity_id(&self) -> u32;
 }
 
+#[cfg(test)]
 pub mod tests {
     use crate::domain::language::tests::language_fixtures;
 
@@ -1,6 +1,7 @@
 use super::new_type::filled_string::FilledString;
 
 pub(crate) mod completion;
+pub(crate) mod document_type;
 pub(crate) mod file_name;
 pub(crate) mod locale;
 pub(crate) mod localized_item;
@@ -8,6 +9,7 @@ pub(crate) mod origin;
 pub(crate) mod price;
 pub(crate) mod status;
 pub(crate) mod tracking_link;
+pub(crate) mod unit_of_measure;
 
 pub(crate) type Reference = FilledString;
 pub(crate) type Translation = FilledString;
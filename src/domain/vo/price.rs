@@ -1,6 +1,7 @@
 use std::fmt;
+use std::str::FromStr;
 
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 
 use crate::domain::DomainError;
 
@@ -13,10 +14,108 @@ pub struct Price {
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Currency {
     Eur,
+    Chf,
+    Gbp,
+}
+
+impl Currency {
+    /// ISO 4217 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Chf => "CHF",
+            Currency::Gbp => "GBP",
+        }
+    }
+}
+
+impl TryFrom<&str> for Currency {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        [Currency::Eur, Currency::Chf, Currency::Gbp]
+            .into_iter()
+            .find(|currency| currency.code().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| {
+                DomainError::ValidationError(format!(
+                    "Unsupported currency {}, expected EUR, CHF or GBP",
+                    value
+                ))
+            })
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// How amounts with more than two decimals are brought to cents
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum RoundingPolicy {
+    #[default]
+    Reject,
+    HalfUp,
+    HalfEven,
+    Truncate,
+}
+
+impl FromStr for RoundingPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "reject" => Ok(RoundingPolicy::Reject),
+            "half_up" => Ok(RoundingPolicy::HalfUp),
+            "half_even" => Ok(RoundingPolicy::HalfEven),
+            "truncate" => Ok(RoundingPolicy::Truncate),
+            _ => Err(format!(
+                "Invalid rounding policy {}, expected reject, half_up, half_even or truncate",
+                value
+            )),
+        }
+    }
+}
+
+impl RoundingPolicy {
+    fn strategy(&self) -> Option<RoundingStrategy> {
+        match self {
+            RoundingPolicy::Reject => None,
+            // Half away from zero, -0.125 becoming -0.13
+            RoundingPolicy::HalfUp => Some(RoundingStrategy::MidpointAwayFromZero),
+            RoundingPolicy::HalfEven => Some(RoundingStrategy::MidpointNearestEven),
+            RoundingPolicy::Truncate => Some(RoundingStrategy::ToZero),
+        }
+    }
 }
 
 impl Price {
-    #[allow(dead_code)]
+    /// Parses an amount in euros, rounding extra decimals with the policy.
+    /// Also tells whether the amount was changed by the rounding.
+    pub fn parse_rounded(value: &str, policy: RoundingPolicy) -> Result<(Self, bool), DomainError> {
+        let error = || DomainError::ParsingError(format!("Invalid price: {}", value));
+        let exact = Price::try_from(value.to_string());
+        let Some(strategy) = policy.strategy() else {
+            return exact.map(|price| (price, false));
+        };
+        if let Ok(price) = exact {
+            return Ok((price, false));
+        }
+
+        let amount = Decimal::from_str_exact(value).map_err(|_| error())?;
+        let mut rounded = amount.round_dp_with_strategy(2, strategy);
+        rounded.rescale(2);
+        let amount_in_cents = i64::try_from(rounded.mantissa()).map_err(|_| error())?;
+        Ok((
+            Price {
+                amount_in_cents,
+                currency: Currency::Eur,
+            },
+            rounded != amount,
+        ))
+    }
+
     pub fn new(amount_in_cents: i64, currency: Currency) -> Self {
         Price {
             amount_in_cents,
@@ -24,23 +123,34 @@ impl Price {
         }
     }
 
-    // pub fn get_amount_in_cents(&self) -> i64 {
-    //     self.amount_in_cents
-    // }
+    pub fn get_amount_in_cents(&self) -> i64 {
+        self.amount_in_cents
+    }
+
+    pub fn negated(self) -> Self {
+        Price {
+            amount_in_cents: -self.amount_in_cents,
+            ..self
+        }
+    }
 
     pub fn get_amount_as_decimal(&self) -> Decimal {
         Decimal::new(self.amount_in_cents, 2)
     }
 
-    // pub fn get_currency(&self) -> Currency {
-    //     self.currency
-    // }
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Same amount in the given currency, parsed prices being in euros
+    pub fn with_currency(self, currency: Currency) -> Self {
+        Price { currency, ..self }
+    }
 }
 
 impl TryFrom<String> for Price {
     type Error = DomainError;
 
-    #[allow(clippy::unnecessary_unwrap)]
     fn try_from(value: String) -> Result<Self, Self::Error> {
         let parts: Vec<&str> = value.split('.').collect();
         let is_negative = parts[0].starts_with('-');
@@ -49,39 +159,38 @@ impl TryFrom<String> for Price {
             integer_part.remove(0);
         }
 
-        let decimal_part = parts.get(1);
-        let mut amount_in_cents: i64;
-
-        if decimal_part.is_some()
-            && !integer_part.is_empty()
-            && (!decimal_part.unwrap().is_empty() && decimal_part.unwrap().len() <= 2)
-            && integer_part.parse::<u64>().is_ok()
-            && decimal_part.unwrap().parse::<u64>().is_ok()
-        {
-            let decimal_multiplier: i64 = match decimal_part.unwrap().len() {
-                1 => 10,
-                _ => 1,
-            };
-            amount_in_cents = integer_part.parse::<i64>().unwrap() * 100
-                + decimal_part.unwrap().parse::<i64>().unwrap() * decimal_multiplier;
-        } else if decimal_part.is_none()
-            && !integer_part.is_empty()
-            && integer_part.parse::<u64>().is_ok()
-        {
-            amount_in_cents = integer_part.parse::<i64>().unwrap() * 100;
-        } else {
-            return Err(DomainError::ParsingError(format!(
-                "Invalid price: {}",
-                value
-            )));
-        }
-
-        if is_negative {
-            amount_in_cents *= -1;
+        // Unsigned digits only, an empty part is invalid
+        let parse_digits = |digits: &str| {
+            digits
+                .parse::<u64>()
+                .ok()
+                .and_then(|digits| i64::try_from(digits).ok())
+        };
+        let amount_in_cents = match parts.get(1) {
+            Some(decimal_part) if (1..=2).contains(&decimal_part.len()) => {
+                let decimal_multiplier: i64 = match decimal_part.len() {
+                    1 => 10,
+                    _ => 1,
+                };
+                parse_digits(&integer_part)
+                    .zip(parse_digits(decimal_part))
+                    .and_then(|(units, cents)| {
+                        units
+                            .checked_mul(100)?
+                            .checked_add(cents * decimal_multiplier)
+                    })
+            }
+            Some(_) => None,
+            None => parse_digits(&integer_part).and_then(|units| units.checked_mul(100)),
         }
+        .ok_or_else(|| DomainError::ParsingError(format!("Invalid price: {}", value)))?;
 
         Ok(Price {
-            amount_in_cents,
+            amount_in_cents: if is_negative {
+                -amount_in_cents
+            } else {
+                amount_in_cents
+            },
             currency: Currency::Eur,
         })
     }
@@ -104,11 +213,12 @@ mod tests {
         assert_eq!(price.currency, Currency::Eur);
     }
 
-    // #[test]
-    // fn test_get_amount_in_cents() {
-    //     let price = Price::new(100, Currency::EUR);
-    //     assert_eq!(price.get_amount_in_cents(), 100);
-    // }
+    #[test]
+    fn test_get_amount_in_cents() {
+        let price = Price::new(100, Currency::Eur);
+        assert_eq!(price.get_amount_in_cents(), 100);
+        assert_eq!(price.negated().get_amount_in_cents(), -100);
+    }
 
     #[test]
     fn test_get_amount_as_decimal() {
@@ -116,11 +226,20 @@ mod tests {
         assert_eq!(price.get_amount_as_decimal().to_string(), "12.34");
     }
 
-    // #[test]
-    // fn test_get_currency() {
-    //     let price = Price::new(100, Currency::EUR);
-    //     assert_eq!(price.get_currency(), Currency::EUR);
-    // }
+    #[test]
+    fn test_get_currency() {
+        let price = Price::new(100, Currency::Eur).with_currency(Currency::Chf);
+        assert_eq!(price.get_currency(), Currency::Chf);
+        assert_eq!(price.get_amount_as_decimal().to_string(), "1.00");
+    }
+
+    #[test]
+    fn test_currency_try_from() {
+        assert_eq!(Currency::try_from("GBP"), Ok(Currency::Gbp));
+        assert_eq!(Currency::try_from("chf"), Ok(Currency::Chf));
+        assert!(Currency::try_from("USD").is_err());
+        assert_eq!(Currency::Eur.to_string(), "EUR");
+    }
     #[test]
     fn test_try_from_valid_string_with_two_decimal_places() {
         let price = Price::try_from(String::from("10.99")).unwrap();
@@ -184,6 +303,35 @@ mod tests {
             DomainError::ParsingError(String::from("Invalid price: 10.999"))
         );
     }
+    #[test]
+    fn test_parse_rounded() {
+        let cents = |value: &str, policy: RoundingPolicy| {
+            Price::parse_rounded(value, policy)
+                .map(|(price, rounded)| (price.amount_in_cents, rounded))
+        };
+
+        assert_eq!(cents("12.345", RoundingPolicy::HalfUp), Ok((1235, true)));
+        assert_eq!(cents("-12.345", RoundingPolicy::HalfUp), Ok((-1235, true)));
+        assert_eq!(cents("12.345", RoundingPolicy::HalfEven), Ok((1234, true)));
+        assert_eq!(cents("12.355", RoundingPolicy::HalfEven), Ok((1236, true)));
+        assert_eq!(cents("12.3459", RoundingPolicy::Truncate), Ok((1234, true)));
+        assert_eq!(cents("12.3400", RoundingPolicy::HalfUp), Ok((1234, false)));
+        assert_eq!(cents("12.34", RoundingPolicy::Reject), Ok((1234, false)));
+        assert_eq!(
+            cents("12.345", RoundingPolicy::Reject),
+            Err(DomainError::ParsingError(String::from(
+                "Invalid price: 12.345"
+            )))
+        );
+        assert!(cents("12.3.4", RoundingPolicy::HalfUp).is_err());
+    }
+
+    #[test]
+    fn test_rounding_policy_from_str() {
+        assert_eq!("HALF_EVEN".parse(), Ok(RoundingPolicy::HalfEven));
+        assert!("bankers".parse::<RoundingPolicy>().is_err());
+    }
+
     #[test]
     fn test_display() {
         let price = Price::new(100, Currency::Eur);
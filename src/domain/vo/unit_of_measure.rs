@@ -0,0 +1,44 @@
+use crate::domain::DomainError;
+
+const MAX_LENGTH: usize = 16;
+
+/// Idempiere unit of measure code, e.g. PCE or MTR
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnitOfMeasure(String);
+
+impl TryFrom<&str> for UnitOfMeasure {
+    type Error = DomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let code = value.trim().to_uppercase();
+        if code.is_empty()
+            || code.len() > MAX_LENGTH
+            || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(DomainError::ValidationError(format!(
+                "Invalid unit of measure {:?}, expected up to {} letters or digits",
+                value, MAX_LENGTH
+            )));
+        }
+        Ok(UnitOfMeasure(code))
+    }
+}
+
+impl UnitOfMeasure {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(UnitOfMeasure::try_from(" mtr").unwrap().as_str(), "MTR");
+        assert!(UnitOfMeasure::try_from("").is_err());
+        assert!(UnitOfMeasure::try_from("m²").is_err());
+        assert!(UnitOfMeasure::try_from("ABCDEFGHIJKLMNOPQ").is_err());
+    }
+}
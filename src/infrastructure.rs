@@ -1,20 +1,38 @@
 use std::{error::Error, fmt};
 
+pub(crate) mod archive;
+pub(crate) mod as_of;
+pub(crate) mod config;
 pub(crate) mod csv_reader;
 pub(crate) mod data_source;
 pub mod database;
+pub(crate) mod fingerprint;
 pub(crate) mod logger;
+pub(crate) mod profile;
+pub(crate) mod report;
 pub(crate) mod repository;
+pub(crate) mod schedule;
+pub(crate) mod shutdown;
+pub(crate) mod watch;
 
 #[derive(Debug)]
 pub enum InfrastructureError {
     CsvError(csv_reader::CsvError),
     CSVFileNotFound(String),
     EnvVarError(std::env::VarError),
+    InvalidPathTemplate(String),
+    ConfigError(String),
+    IOError(std::io::Error),
     DatabaseError(diesel::result::Error),
+    // Message of the diesel connection error, without the database password
+    ConnectionError(String),
     InconsistentDataError(String),
     NotImplementedError(String),
     LookupError(String),
+    // Failure of a named target profile
+    Target(String, Box<dyn Error>),
+    // Stopped by SIGINT or SIGTERM before all rows were written
+    Interrupted,
 }
 
 impl fmt::Display for InfrastructureError {
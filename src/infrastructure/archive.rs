@@ -0,0 +1,216 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::Local;
+use flate2::{write::GzEncoder, Compression};
+
+use super::{config::ArchiveConfig, InfrastructureError};
+
+const FAILED_DIRECTORY: &str = "failed";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveMode {
+    Move,
+    Copy,
+}
+
+impl FromStr for ArchiveMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "move" => Ok(ArchiveMode::Move),
+            "copy" => Ok(ArchiveMode::Copy),
+            _ => Err(format!(
+                "Unknown archive mode {}, expected move or copy",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveOutcome {
+    Imported,
+    Failed,
+}
+
+/// Moves or copies imported source files into ARCHIVE_DIRECTORY,
+/// files of a failed import, rejected rows included, go to its failed/ subdirectory
+#[derive(Debug)]
+pub(crate) struct Archiver {
+    directory: PathBuf,
+    mode: ArchiveMode,
+    gzip: bool,
+    run_id: String,
+}
+
+impl Archiver {
+    pub fn new(directory: PathBuf, mode: ArchiveMode, gzip: bool, run_id: &str) -> Self {
+        Self {
+            directory,
+            mode,
+            gzip,
+            run_id: run_id.to_string(),
+        }
+    }
+
+    /// Archiving is disabled when archive.directory is not set
+    pub fn from_config(
+        config: &ArchiveConfig,
+        run_id: &str,
+    ) -> Result<Option<Self>, InfrastructureError> {
+        let Some(directory) = &config.directory else {
+            return Ok(None);
+        };
+        let mode = match &config.mode {
+            Some(mode) => mode
+                .parse()
+                .map_err(InfrastructureError::InconsistentDataError)?,
+            // Source files are only removed when asked for
+            None => ArchiveMode::Copy,
+        };
+        Ok(Some(Self::new(
+            PathBuf::from(directory),
+            mode,
+            config.gzip.unwrap_or(false),
+            run_id,
+        )))
+    }
+
+    /// Returns the path of the archived file
+    pub fn archive(
+        &self,
+        path: &Path,
+        outcome: ArchiveOutcome,
+    ) -> Result<PathBuf, InfrastructureError> {
+        let destination = self.destination(
+            path,
+            outcome,
+            &Local::now().format("%Y%m%d%H%M%S").to_string(),
+        );
+        if let Some(directory) = destination.parent() {
+            fs::create_dir_all(directory).map_err(InfrastructureError::IOError)?;
+        }
+
+        match (self.gzip, self.mode) {
+            (true, _) => Self::compress(path, &destination),
+            (false, ArchiveMode::Copy) => fs::copy(path, &destination).map(|_| ()),
+            // Renaming fails across file systems
+            (false, ArchiveMode::Move) => {
+                fs::rename(path, &destination).or_else(|_| fs::copy(path, &destination).map(|_| ()))
+            }
+        }
+        .map_err(InfrastructureError::IOError)?;
+
+        if self.mode == ArchiveMode::Move && path.exists() {
+            fs::remove_file(path).map_err(InfrastructureError::IOError)?;
+        }
+        Ok(destination)
+    }
+
+    // orders.csv becomes orders_20231106101500_<run id>.csv, with a .gz extension when compressed
+    fn destination(&self, path: &Path, outcome: ArchiveOutcome, timestamp: &str) -> PathBuf {
+        let directory = match outcome {
+            ArchiveOutcome::Imported => self.directory.clone(),
+            ArchiveOutcome::Failed => self.directory.join(FAILED_DIRECTORY),
+        };
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let mut file_name = format!("{}_{}_{}", stem, timestamp, self.run_id);
+        if let Some(extension) = path.extension() {
+            file_name.push('.');
+            file_name.push_str(&extension.to_string_lossy());
+        }
+        if self.gzip {
+            file_name.push_str(".gz");
+        }
+        directory.join(file_name)
+    }
+
+    fn compress(path: &Path, destination: &Path) -> Result<(), io::Error> {
+        let mut source = File::open(path)?;
+        let mut encoder = GzEncoder::new(File::create(destination)?, Compression::default());
+        io::copy(&mut source, &mut encoder)?;
+        encoder.finish().map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_archive_mode_from_str() {
+        assert_eq!(ArchiveMode::from_str("move"), Ok(ArchiveMode::Move));
+        assert_eq!(ArchiveMode::from_str("copy"), Ok(ArchiveMode::Copy));
+        assert!(ArchiveMode::from_str("delete").is_err());
+    }
+
+    #[test]
+    fn test_destination() {
+        let archiver = Archiver::new(PathBuf::from("archive"), ArchiveMode::Move, false, "ab12");
+        let gzip_archiver =
+            Archiver::new(PathBuf::from("archive"), ArchiveMode::Move, true, "ab12");
+        let path = Path::new("drop/orders.csv");
+
+        assert_eq!(
+            archiver.destination(path, ArchiveOutcome::Imported, "20231106101500"),
+            PathBuf::from("archive/orders_20231106101500_ab12.csv")
+        );
+        assert_eq!(
+            gzip_archiver.destination(path, ArchiveOutcome::Failed, "20231106101500"),
+            PathBuf::from("archive/failed/orders_20231106101500_ab12.csv.gz")
+        );
+    }
+
+    #[test]
+    fn test_move_file() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("orders.csv");
+        fs::write(&path, "c_order_id\n1\n").unwrap();
+        let archiver = Archiver::new(
+            directory.path().join("archive"),
+            ArchiveMode::Move,
+            false,
+            "ab12",
+        );
+
+        let archived = archiver.archive(&path, ArchiveOutcome::Imported).unwrap();
+
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(archived).unwrap(), "c_order_id\n1\n");
+    }
+
+    #[test]
+    fn test_copy_and_compress_file() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("orders.csv");
+        fs::write(&path, "c_order_id\n1\n").unwrap();
+        let archiver = Archiver::new(
+            directory.path().join("archive"),
+            ArchiveMode::Copy,
+            true,
+            "ab12",
+        );
+
+        let archived = archiver.archive(&path, ArchiveOutcome::Failed).unwrap();
+
+        assert!(path.exists());
+        assert!(archived.starts_with(directory.path().join("archive").join(FAILED_DIRECTORY)));
+        let mut content = String::new();
+        GzDecoder::new(File::open(archived).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "c_order_id\n1\n");
+    }
+}
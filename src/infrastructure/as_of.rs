@@ -0,0 +1,137 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    Duration, NaiveDate, NaiveTime,
+};
+
+pub const AS_OF_DATE: &str = "AS_OF_DATE";
+
+/// Date used to fill the strftime placeholders of source paths: today, yesterday or YYYY-MM-DD
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsOf {
+    Today,
+    Yesterday,
+    Date(NaiveDate),
+}
+
+impl FromStr for AsOf {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "today" => Ok(AsOf::Today),
+            "yesterday" => Ok(AsOf::Yesterday),
+            _ => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(AsOf::Date)
+                .map_err(|_| {
+                    format!(
+                        "Invalid date {}, expected today, yesterday or YYYY-MM-DD",
+                        value
+                    )
+                }),
+        }
+    }
+}
+
+impl fmt::Display for AsOf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsOf::Today => write!(f, "today"),
+            AsOf::Yesterday => write!(f, "yesterday"),
+            AsOf::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+impl AsOf {
+    pub fn date(&self, today: NaiveDate) -> NaiveDate {
+        match *self {
+            AsOf::Today => today,
+            AsOf::Yesterday => today - Duration::days(1),
+            AsOf::Date(date) => date,
+        }
+    }
+}
+
+/// Replaces strftime placeholders such as %Y%m%d with the as-of date.
+/// `%%` is a literal `%` and unknown `%` sequences are kept as they are.
+pub fn render_path(template: &str, date: NaiveDate) -> String {
+    // Time placeholders are rendered as midnight
+    let midnight = date.and_time(NaiveTime::MIN);
+    let mut path = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue;
+        }
+        let mut placeholder = String::from('%');
+        if let Some(padding) = chars.next_if(|c| matches!(c, '-' | '_' | '0')) {
+            placeholder.push(padding);
+        }
+        let Some(specifier) = chars.next() else {
+            path.push_str(&placeholder);
+            break;
+        };
+        placeholder.push(specifier);
+        if placeholder == "%%" {
+            path.push('%');
+            continue;
+        }
+        let items: Vec<Item> = StrftimeItems::new(&placeholder).collect();
+        if items.iter().any(|item| matches!(item, Item::Error)) {
+            path.push_str(&placeholder);
+        } else {
+            write!(path, "{}", midnight.format_with_items(items.into_iter()))
+                .expect("Writing into a String cannot fail");
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_as_of_from_str() {
+        assert_eq!("today".parse(), Ok(AsOf::Today));
+        assert_eq!("yesterday".parse(), Ok(AsOf::Yesterday));
+        assert_eq!(
+            "2026-10-18".parse(),
+            Ok(AsOf::Date(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()))
+        );
+        assert!("18/10/2026".parse::<AsOf>().is_err());
+    }
+
+    #[test]
+    fn test_as_of_date() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+
+        assert_eq!(AsOf::Today.date(today), today);
+        assert_eq!(
+            AsOf::Yesterday.date(today),
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_path() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+
+        assert_eq!(
+            render_path("exports/invoices_%Y%m%d.csv", date),
+            "exports/invoices_20261018.csv"
+        );
+        assert_eq!(
+            render_path("exports/%Y/%-m/orders_*.csv", date),
+            "exports/2026/10/orders_*.csv"
+        );
+        assert_eq!(
+            render_path("exports/100%%_%Q_%d.csv%", date),
+            "exports/100%_%Q_18.csv%"
+        );
+    }
+}
@@ -1,92 +1,266 @@
+use chrono::{Local, NaiveDate};
 use csv::ReaderBuilder;
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
 use serde::Deserialize;
-use std::env::{self, VarError};
-use std::fs::File;
+use std::collections::HashMap;
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use super::InfrastructureError;
+use super::as_of::render_path;
+use super::{report, InfrastructureError};
+use dialect::CsvDialect;
 
 pub(crate) mod delivery_slip;
+pub(crate) mod dialect;
 pub(crate) mod invoice;
+pub(crate) mod invoice_line;
 pub(crate) mod order;
 pub(crate) mod order_line;
+pub(crate) mod payment;
 pub(crate) mod product;
 
 #[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum CsvType {
     DeliverySlip,
     Invoice,
     InvoiceDocumentType,
+    InvoiceLine,
     Order,
     OrderLine,
     OrderLineItem,
+    Payment,
+    PaymentAllocation,
     ProductSubstitute,
     Test(PathBuf),
 }
 
 impl CsvType {
-    fn get_path(&self) -> Result<String, VarError> {
+    /// Every source read from the configured paths
+    pub fn all() -> [CsvType; 10] {
+        [
+            CsvType::DeliverySlip,
+            CsvType::Invoice,
+            CsvType::InvoiceDocumentType,
+            CsvType::InvoiceLine,
+            CsvType::Order,
+            CsvType::OrderLine,
+            CsvType::OrderLineItem,
+            CsvType::Payment,
+            CsvType::PaymentAllocation,
+            CsvType::ProductSubstitute,
+        ]
+    }
+
+    /// Name of the env variable holding the path
+    pub fn env_var(&self) -> Option<&'static str> {
         match self {
-            CsvType::DeliverySlip => env::var("DELIVERY_SLIPS_CSV_PATH"),
-            CsvType::Invoice => env::var("INVOICES_CSV_PATH"),
-            CsvType::InvoiceDocumentType => env::var("INVOICE_DOCUMENT_TYPES_CSV_PATH"),
-            CsvType::Order => env::var("ORDERS_CSV_PATH"),
-            CsvType::OrderLine => env::var("ORDER_LINES_CSV_PATH"),
-            CsvType::OrderLineItem => env::var("ORDER_LINE_ITEMS_CSV_PATH"),
-            CsvType::ProductSubstitute => env::var("PRODUCT_SUBSTITUTES_CSV_PATH"),
-            CsvType::Test(path) => Ok(path
-                .to_str()
-                .expect("CsvType::Test cannot be cast into &str")
-                .to_string()),
+            CsvType::DeliverySlip => Some("DELIVERY_SLIPS_CSV_PATH"),
+            CsvType::Invoice => Some("INVOICES_CSV_PATH"),
+            CsvType::InvoiceDocumentType => Some("INVOICE_DOCUMENT_TYPES_CSV_PATH"),
+            CsvType::InvoiceLine => Some("INVOICE_LINES_CSV_PATH"),
+            CsvType::Order => Some("ORDERS_CSV_PATH"),
+            CsvType::OrderLine => Some("ORDER_LINES_CSV_PATH"),
+            CsvType::OrderLineItem => Some("ORDER_LINE_ITEMS_CSV_PATH"),
+            CsvType::Payment => Some("PAYMENTS_CSV_PATH"),
+            CsvType::PaymentAllocation => Some("PAYMENT_ALLOCATIONS_CSV_PATH"),
+            CsvType::ProductSubstitute => Some("PRODUCT_SUBSTITUTES_CSV_PATH"),
+            CsvType::Test(_) => None,
+        }
+    }
+
+    /// Per-source override of a CSV_<SETTING> variable, e.g. INVOICES_CSV_DECIMAL_SEPARATOR
+    pub fn setting_var(&self, setting: &str) -> Option<String> {
+        self.env_var()
+            .and_then(|path_var| path_var.strip_suffix("PATH"))
+            .map(|prefix| format!("{}{}", prefix, setting))
+    }
+
+    /// Lookup sources read by several imports, left in place by the archiving
+    pub fn is_lookup(&self) -> bool {
+        matches!(self, CsvType::InvoiceDocumentType | CsvType::OrderLineItem)
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            CsvType::DeliverySlip => "delivery_slips",
+            CsvType::Invoice => "invoices",
+            CsvType::InvoiceDocumentType => "invoice_document_types",
+            CsvType::InvoiceLine => "invoice_lines",
+            CsvType::Order => "orders",
+            CsvType::OrderLine => "order_lines",
+            CsvType::OrderLineItem => "order_line_items",
+            CsvType::Payment => "payments",
+            CsvType::PaymentAllocation => "payment_allocations",
+            CsvType::ProductSubstitute => "product_substitutes",
+            CsvType::Test(_) => "test",
         }
     }
 }
 
+impl FromStr for CsvType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        CsvType::all()
+            .into_iter()
+            .find(|csv_type| csv_type.name() == value)
+            .ok_or_else(|| format!("Unknown CSV source {}", value))
+    }
+}
+
 #[derive(Debug)]
 pub enum CsvError {
     IOError(std::io::Error),
-    CsvParseError(csv::Error),
+    // File the malformed row comes from
+    CsvParseError(PathBuf, csv::Error),
+    InvalidPattern(glob::PatternError),
 }
 
 pub trait CsvDTO {}
 
+/// Paths and dialects of the sources of a run, the as-of date being resolved once
+#[derive(Debug, Clone)]
+pub struct CsvSources {
+    // Path templates by source name
+    templates: HashMap<String, String>,
+    as_of: NaiveDate,
+    // Files imported on their own by the watch mode, by source name
+    files: HashMap<String, PathBuf>,
+    // Empty when no date format is configured
+    dialects: HashMap<String, CsvDialect>,
+}
+
+impl Default for CsvSources {
+    fn default() -> Self {
+        Self::new(HashMap::new(), Local::now().date_naive(), HashMap::new())
+    }
+}
+
+impl CsvSources {
+    pub fn new(
+        templates: HashMap<String, String>,
+        as_of: NaiveDate,
+        dialects: HashMap<String, CsvDialect>,
+    ) -> Self {
+        Self {
+            templates,
+            as_of,
+            files: HashMap::new(),
+            dialects,
+        }
+    }
+
+    /// Same sources, the given one being read from a single file
+    pub fn with_file(&self, csv_type: &CsvType, file: PathBuf) -> Self {
+        let mut sources = self.clone();
+        sources.files.insert(csv_type.name().to_string(), file);
+        sources
+    }
+
+    // Date placeholders of the path template are filled with the as-of date
+    fn path(&self, csv_type: &CsvType) -> Result<String, InfrastructureError> {
+        if let CsvType::Test(path) = csv_type {
+            return Ok(path
+                .to_str()
+                .expect("CsvType::Test cannot be cast into &str")
+                .to_string());
+        }
+        let template = self.templates.get(csv_type.name()).ok_or_else(|| {
+            InfrastructureError::ConfigError(format!(
+                "sources.{} ({}) is not configured",
+                csv_type.name(),
+                csv_type.env_var().unwrap_or_default()
+            ))
+        })?;
+        Ok(render_path(template, self.as_of))
+    }
+
+    /// Files currently matched by the source path, in reading order
+    pub fn files(&self, csv_type: &CsvType) -> Result<Vec<PathBuf>, InfrastructureError> {
+        // A single file is read as is, its name may contain glob characters
+        if let Some(file) = self.files.get(csv_type.name()) {
+            if !file.is_file() {
+                return Err(InfrastructureError::CSVFileNotFound(
+                    file.display().to_string(),
+                ));
+            }
+            return Ok(vec![file.clone()]);
+        }
+        let source_path = self.path(csv_type)?;
+
+        let file_paths = resolve_source_files(&source_path)?;
+        if file_paths.is_empty() {
+            return Err(InfrastructureError::CSVFileNotFound(source_path));
+        }
+        Ok(file_paths)
+    }
+
+    /// Dialect of the source, test files following the default one
+    pub fn dialect(&self, csv_type: &CsvType) -> Result<CsvDialect, InfrastructureError> {
+        if let CsvType::Test(_) = csv_type {
+            return Ok(CsvDialect::default());
+        }
+        self.dialects.get(csv_type.name()).cloned().ok_or_else(|| {
+            InfrastructureError::ConfigError(
+                "dialect.date_format (CSV_DATE_FORMAT) is not configured".to_string(),
+            )
+        })
+    }
+}
+
+/// Reads every file of a source as one logical CSV file
 #[derive(Debug)]
 pub struct CsvFileReader {
-    file_path: PathBuf,
+    file_paths: Vec<PathBuf>,
     delimiter: u8,
 }
 
 impl Default for CsvFileReader {
     fn default() -> Self {
         Self {
-            file_path: Default::default(),
+            file_paths: Default::default(),
             delimiter: b';',
         }
     }
 }
 
 impl CsvFileReader {
-    fn new(file_path: PathBuf, delimiter: u8) -> Self {
+    fn new(file_paths: Vec<PathBuf>, delimiter: u8) -> Self {
         CsvFileReader {
-            file_path,
+            file_paths,
             delimiter,
         }
     }
 
+    pub fn file_paths(&self) -> &[PathBuf] {
+        &self.file_paths
+    }
+
     pub fn read<T>(&self) -> Result<Vec<T>, CsvError>
     where
         T: CsvDTO + for<'a> Deserialize<'a>,
     {
         let mut csv_dtos = Vec::new();
 
-        let file = File::open(self.file_path.as_path()).map_err(CsvError::IOError)?;
-        let mut rdr = ReaderBuilder::new()
-            .delimiter(self.delimiter)
-            .from_reader(file);
+        for file_path in self.file_paths() {
+            let file = File::open(file_path).map_err(CsvError::IOError)?;
+            let mut rdr = ReaderBuilder::new()
+                .delimiter(self.delimiter)
+                .from_reader(file);
 
-        for result in rdr.deserialize::<T>() {
-            let csv_dto = result.map_err(CsvError::CsvParseError)?;
-            csv_dtos.push(csv_dto);
+            let previous_len = csv_dtos.len();
+            for result in rdr.deserialize::<T>() {
+                let csv_dto = result.map_err(|e| CsvError::CsvParseError(file_path.clone(), e))?;
+                csv_dtos.push(csv_dto);
+            }
+
+            let rows = csv_dtos.len() - previous_len;
+            debug!("Read {} rows from {}", rows, file_path.display());
+            report::record_file(file_path, rows);
         }
 
         Ok(csv_dtos)
@@ -94,16 +268,70 @@ impl CsvFileReader {
 }
 
 pub fn make_csv_file_reader(
+    sources: &CsvSources,
     csv_type: CsvType,
     delimiter: u8,
 ) -> Result<CsvFileReader, InfrastructureError> {
-    let file_path = csv_type
-        .get_path()
-        .map_err(InfrastructureError::EnvVarError)?;
+    let file_paths = sources.files(&csv_type)?;
 
-    if !Path::new(&file_path).exists() {
-        return Err(InfrastructureError::CSVFileNotFound(file_path));
-    }
+    Ok(CsvFileReader::new(file_paths, delimiter))
+}
+
+lazy_static! {
+    // A YYYYMMDD date, its parts possibly separated by -, _ or ., not part of a longer number
+    static ref FILE_NAME_DATE: Regex =
+        Regex::new(r"(?:^|\D)(\d{4})[-_.]?(\d{2})[-_.]?(\d{2})(?:\D|$)").unwrap();
+}
+
+// First valid date found in the file name, e.g. 2023-08-01 in orders_2023-08-01.csv
+fn file_name_date(file: &Path) -> Option<NaiveDate> {
+    let name = file.file_name()?.to_str()?;
+    FILE_NAME_DATE.captures_iter(name).find_map(|captures| {
+        NaiveDate::from_ymd_opt(
+            captures[1].parse().ok()?,
+            captures[2].parse().ok()?,
+            captures[3].parse().ok()?,
+        )
+    })
+}
+
+// A source path is a single file, a directory of .csv files or a glob pattern.
+// Files are sorted by the date in their name, then by name, so the most recent rows are
+// upserted last. Files without a date come first. Modification dates are not used, copies
+// and restores change them.
+fn resolve_source_files(source_path: &str) -> Result<Vec<PathBuf>, InfrastructureError> {
+    let path = Path::new(source_path);
+    let io_error = |e| InfrastructureError::CsvError(CsvError::IOError(e));
+
+    let mut file_paths: Vec<PathBuf> = if path.is_dir() {
+        fs::read_dir(path)
+            .map_err(io_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<PathBuf>, std::io::Error>>()
+            .map_err(io_error)?
+            .into_iter()
+            .filter(|file| {
+                file.is_file()
+                    && file
+                        .extension()
+                        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+            })
+            .collect()
+    } else if source_path.contains(['*', '?', '[']) {
+        glob::glob(source_path)
+            .map_err(|e| InfrastructureError::CsvError(CsvError::InvalidPattern(e)))?
+            .map(|entry| entry.map_err(std::io::Error::from))
+            .collect::<Result<Vec<PathBuf>, std::io::Error>>()
+            .map_err(io_error)?
+            .into_iter()
+            .filter(|file| file.is_file())
+            .collect()
+    } else if path.exists() {
+        vec![path.to_path_buf()]
+    } else {
+        vec![]
+    };
 
-    Ok(CsvFileReader::new(PathBuf::from(file_path), delimiter))
+    file_paths.sort_by_cached_key(|file| (file_name_date(file), file.clone()));
+    Ok(file_paths)
 }
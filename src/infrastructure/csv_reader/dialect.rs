@@ -0,0 +1,153 @@
+use std::fmt;
+
+use crate::domain::vo::price::RoundingPolicy;
+
+/// How the dates and numbers of a CSV source are written, built once per run from the configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvDialect {
+    pub date_format: String,
+    pub number_format: NumberFormat,
+    pub rounding: RoundingPolicy,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            date_format: "%Y-%m-%d".to_string(),
+            number_format: NumberFormat::default(),
+            rounding: RoundingPolicy::default(),
+        }
+    }
+}
+
+/// Decimal and thousands separators of the numbers of a CSV source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    decimal_separator: char,
+    thousands_separator: Option<char>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: None,
+        }
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "decimal separator '{}'", self.decimal_separator)?;
+        match self.thousands_separator {
+            Some(separator) => write!(f, ", thousands separator '{}'", separator),
+            None => write!(f, ", no thousands separator"),
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Separators are single characters, "space" standing for ' '
+    pub fn new(decimal_separator: &str, thousands_separator: Option<&str>) -> Result<Self, String> {
+        let decimal_separator = parse_separator(decimal_separator)?;
+        let thousands_separator = thousands_separator
+            .filter(|separator| !separator.is_empty())
+            .map(parse_separator)
+            .transpose()?;
+        if thousands_separator == Some(decimal_separator) {
+            return Err(format!(
+                "Decimal and thousands separators are both '{}'",
+                decimal_separator
+            ));
+        }
+        Ok(Self {
+            decimal_separator,
+            thousands_separator,
+        })
+    }
+
+    /// Number with a '.' decimal point and no grouping, e.g. 1 234,50 => 1234.50,
+    /// None when the value does not follow the format
+    pub fn normalize(&self, value: &str) -> Option<String> {
+        let trimmed = value.trim();
+        let (sign, unsigned) = match trimmed.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", trimmed),
+        };
+        let (integer, fraction) = match unsigned.split_once(self.decimal_separator) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        };
+        let integer = self.ungroup(integer)?;
+        if !is_digits(&integer) || fraction.is_some_and(|fraction| !is_digits(fraction)) {
+            return None;
+        }
+        Some(match fraction {
+            Some(fraction) => format!("{}{}.{}", sign, integer, fraction),
+            None => format!("{}{}", sign, integer),
+        })
+    }
+
+    // Integer part without its thousands separators, every group but the first having 3 digits
+    fn ungroup(&self, integer: &str) -> Option<String> {
+        let Some(separator) = self.thousands_separator else {
+            return Some(integer.to_string());
+        };
+        // Spreadsheets group French numbers with non-breaking spaces
+        let groups: Vec<&str> = integer
+            .split(|c| c == separator || (separator == ' ' && matches!(c, '\u{a0}' | '\u{202f}')))
+            .collect();
+        if groups.len() > 1
+            && (groups[0].is_empty()
+                || groups[0].len() > 3
+                || groups[1..].iter().any(|group| group.len() != 3))
+        {
+            return None;
+        }
+        Some(groups.concat())
+    }
+}
+
+fn parse_separator(value: &str) -> Result<char, String> {
+    let separator = if value == "space" { " " } else { value };
+    let mut chars = separator.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_ascii_digit() && c != '-' => Ok(c),
+        _ => Err(format!("Invalid number separator {:?}", value)),
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn french() -> NumberFormat {
+        NumberFormat::new(",", Some("space")).unwrap()
+    }
+
+    #[test]
+    fn test_number_format_new() {
+        assert_eq!(
+            NumberFormat::new(".", None).unwrap(),
+            NumberFormat::default()
+        );
+        assert!(NumberFormat::new(",", Some(",")).is_err());
+        assert!(NumberFormat::new(",,", None).is_err());
+        assert!(NumberFormat::new("0", None).is_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(french().normalize("18,54").unwrap(), "18.54");
+        assert_eq!(french().normalize("1 234,50").unwrap(), "1234.50");
+        assert_eq!(french().normalize("-1\u{202f}234,5").unwrap(), "-1234.5");
+        assert_eq!(NumberFormat::default().normalize("10.99").unwrap(), "10.99");
+        for invalid in ["18.54", "12 34,5", "1,2,3", ",5", "12,", ""] {
+            assert!(french().normalize(invalid).is_none(), "{}", invalid);
+        }
+    }
+}
@@ -13,6 +13,12 @@ pub struct CsvInvoiceDTO {
     pub po_ref: String,
     pub total_tax_excl: String,
     pub total_tax_incl: String,
+    // ISO 4217 code of the totals not suffixed with their own, EUR when missing or empty
+    #[serde(default)]
+    pub currency: String,
+    // ARI for invoices, ARC for credit notes, inferred from the totals when missing or empty
+    #[serde(default)]
+    pub doc_base_type: String,
 }
 
 impl CsvDTO for CsvInvoiceDTO {}
@@ -40,6 +46,8 @@ pub mod tests {
                 po_ref: "PO-1".to_string(),
                 total_tax_excl: "100.00".to_string(),
                 total_tax_incl: "120.0".to_string(),
+                currency: "EUR".to_string(),
+                doc_base_type: "ARI".to_string(),
             },
             CsvInvoiceDTO {
                 c_invoice_id: "2".to_string(),
@@ -51,6 +59,8 @@ pub mod tests {
                 po_ref: "PO-2".to_string(),
                 total_tax_excl: "200.0".to_string(),
                 total_tax_incl: "240.00".to_string(),
+                currency: "CHF".to_string(),
+                doc_base_type: String::new(),
             },
             CsvInvoiceDTO {
                 c_invoice_id: "3".to_string(),
@@ -61,7 +71,9 @@ pub mod tests {
                 date: "2020-01-03".to_string(),
                 po_ref: String::new(),
                 total_tax_excl: "-300.0".to_string(),
-                total_tax_incl: "360.00".to_string(),
+                total_tax_incl: "-360.00".to_string(),
+                currency: String::new(),
+                doc_base_type: String::new(),
             },
         ]
     }
@@ -0,0 +1,56 @@
+use serde::Deserialize;
+
+use super::*;
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct CsvInvoiceLineDTO {
+    pub c_invoiceline_id: String,
+    pub c_invoice_id: String,
+    pub product_ref: String,
+    pub qty_invoiced: String,
+    pub price_actual: String,
+    pub tax_rate: String,
+    pub line_total_tax_excl: String,
+    pub line_total_tax_incl: String,
+}
+
+impl CsvDTO for CsvInvoiceLineDTO {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    pub fn csv_invoice_line_dto_fixtures() -> [CsvInvoiceLineDTO; 3] {
+        [
+            CsvInvoiceLineDTO {
+                c_invoiceline_id: "1".to_string(),
+                c_invoice_id: "1".to_string(),
+                product_ref: "P-1".to_string(),
+                qty_invoiced: "2".to_string(),
+                price_actual: "30.00".to_string(),
+                tax_rate: "20".to_string(),
+                line_total_tax_excl: "60.00".to_string(),
+                line_total_tax_incl: "72.00".to_string(),
+            },
+            CsvInvoiceLineDTO {
+                c_invoiceline_id: "2".to_string(),
+                c_invoice_id: "1".to_string(),
+                product_ref: "P-2".to_string(),
+                qty_invoiced: "1".to_string(),
+                price_actual: "40.0".to_string(),
+                tax_rate: "20.0".to_string(),
+                line_total_tax_excl: "40.0".to_string(),
+                line_total_tax_incl: "48.0".to_string(),
+            },
+            CsvInvoiceLineDTO {
+                c_invoiceline_id: "3".to_string(),
+                c_invoice_id: "3".to_string(),
+                product_ref: "P-3".to_string(),
+                qty_invoiced: "3".to_string(),
+                price_actual: "100".to_string(),
+                tax_rate: "20".to_string(),
+                line_total_tax_excl: "300".to_string(),
+                line_total_tax_incl: "360".to_string(),
+            },
+        ]
+    }
+}
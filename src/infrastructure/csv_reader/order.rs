@@ -20,8 +20,12 @@ impl CsvDTO for CsvOrderDTO {}
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::infrastructure::report;
+    use std::fs::File;
     use std::io::Write;
-    use tempfile::NamedTempFile;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+    use tempfile::{tempdir, NamedTempFile};
 
     pub const DELIMITER: u8 = b';';
 
@@ -80,9 +84,12 @@ pub mod tests {
     #[test]
     fn test_read_flawless_csv() {
         let temp_csv = create_temp_csv(ORDER_FLAWLESS_CSV);
-        let csv_reader =
-            make_csv_file_reader(CsvType::Test(temp_csv.path().to_path_buf()), DELIMITER)
-                .expect("Failed to create csv_reader");
+        let csv_reader = make_csv_file_reader(
+            &CsvSources::default(),
+            CsvType::Test(temp_csv.path().to_path_buf()),
+            DELIMITER,
+        )
+        .expect("Failed to create csv_reader");
         let order_fixture = csv_order_dto_fixtures();
 
         // Act
@@ -99,9 +106,12 @@ pub mod tests {
     #[test]
     fn test_read_csv_with_different_nb_of_field_that_struct() {
         let temp_csv = create_temp_csv(ORDER_WITH_MISSING_DATA_CSV);
-        let csv_reader =
-            make_csv_file_reader(CsvType::Test(temp_csv.path().to_path_buf()), DELIMITER)
-                .expect("Failed to create csv_reader");
+        let csv_reader = make_csv_file_reader(
+            &CsvSources::default(),
+            CsvType::Test(temp_csv.path().to_path_buf()),
+            DELIMITER,
+        )
+        .expect("Failed to create csv_reader");
 
         // Act
         let result: Result<Vec<CsvOrderDTO>, CsvError> = csv_reader.read();
@@ -109,7 +119,8 @@ pub mod tests {
         //Assert
         assert!(
             result.is_err_and(|err| match err {
-                CsvError::CsvParseError(err) => {
+                CsvError::CsvParseError(file, err) => {
+                    assert_eq!(file, temp_csv.path());
                     match err.kind() {
                         csv::ErrorKind::UnequalLengths {
                             pos: _,
@@ -135,9 +146,12 @@ pub mod tests {
     #[test]
     fn test_read_csv_with_empty_field() {
         let temp_csv = create_temp_csv(ORDER_WITH_EMPTY_FIELD_CSV);
-        let csv_reader =
-            make_csv_file_reader(CsvType::Test(temp_csv.path().to_path_buf()), DELIMITER)
-                .expect("Failed to create csv_reader");
+        let csv_reader = make_csv_file_reader(
+            &CsvSources::default(),
+            CsvType::Test(temp_csv.path().to_path_buf()),
+            DELIMITER,
+        )
+        .expect("Failed to create csv_reader");
         let order_fixture = csv_order_dto_fixtures();
 
         // Act
@@ -150,14 +164,136 @@ pub mod tests {
         assert_eq!(order_dtos[1], order_fixture[2]);
     }
 
+    // Writes a CSV file into the directory with the given modification date
+    fn write_dated_csv(directory: &Path, name: &str, content: &str, seconds: u64) -> PathBuf {
+        let path = directory.join(name);
+        std::fs::write(&path, content).expect("Failed to write CSV file");
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| {
+                file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+            })
+            .expect("Failed to set CSV file modification date");
+        path
+    }
+
+    #[test]
+    fn test_read_directory_sorted_by_file_name_date() {
+        let directory = tempdir().unwrap();
+        // Modification dates are ignored, the file names are sorted by their date then name
+        let newest = write_dated_csv(
+            directory.path(),
+            "orders_2023-08-02.csv",
+            ORDER_WITH_EMPTY_FIELD_CSV,
+            1_000,
+        );
+        let oldest = write_dated_csv(
+            directory.path(),
+            "orders_20230801.csv",
+            ORDER_FLAWLESS_CSV,
+            2_000,
+        );
+        write_dated_csv(directory.path(), "notes.txt", "not a csv", 500);
+        let csv_reader = make_csv_file_reader(
+            &CsvSources::default(),
+            CsvType::Test(directory.path().to_path_buf()),
+            DELIMITER,
+        )
+        .expect("Failed to create csv_reader");
+        let order_fixture = csv_order_dto_fixtures();
+
+        // Act
+        let result: Result<Vec<CsvOrderDTO>, CsvError> = csv_reader.read();
+
+        // Assert
+        assert_eq!(csv_reader.file_paths(), &[oldest.clone(), newest.clone()]);
+        let order_dtos = result.expect("Expected successful read_orders");
+        assert_eq!(order_dtos.len(), 4);
+        assert_eq!(order_dtos[0], order_fixture[0]);
+        assert_eq!(order_dtos[3], order_fixture[2]);
+        assert_eq!(report::file_report(&oldest), Some(2));
+        assert_eq!(report::file_report(&newest), Some(2));
+    }
+
+    #[test]
+    fn test_file_name_date() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+        assert_eq!(
+            file_name_date(Path::new("/in/orders_2023-08-01.csv")),
+            date(2023, 8, 1)
+        );
+        assert_eq!(
+            file_name_date(Path::new("orders.20230801.csv")),
+            date(2023, 8, 1)
+        );
+        assert_eq!(
+            file_name_date(Path::new("2023_12_31_orders.csv")),
+            date(2023, 12, 31)
+        );
+        // Not a date, or part of a longer number
+        assert_eq!(file_name_date(Path::new("orders_20231301.csv")), None);
+        assert_eq!(file_name_date(Path::new("orders_1202308011.csv")), None);
+        assert_eq!(file_name_date(Path::new("orders.csv")), None);
+    }
+
+    #[test]
+    fn test_read_glob_pattern() {
+        let directory = tempdir().unwrap();
+        let matching = write_dated_csv(
+            directory.path(),
+            "orders_2023.csv",
+            ORDER_FLAWLESS_CSV,
+            1_000,
+        );
+        write_dated_csv(
+            directory.path(),
+            "invoices_2023.csv",
+            ORDER_FLAWLESS_CSV,
+            1_000,
+        );
+        let pattern = directory.path().join("orders_*.csv");
+
+        // Act
+        let csv_reader =
+            make_csv_file_reader(&CsvSources::default(), CsvType::Test(pattern), DELIMITER)
+                .expect("Failed to create csv_reader");
+
+        // Assert
+        assert_eq!(csv_reader.file_paths(), &[matching]);
+    }
+
+    #[test]
+    fn test_read_glob_pattern_without_match() {
+        let directory = tempdir().unwrap();
+        let pattern = directory.path().join("orders_*.csv");
+
+        // Act
+        let result = make_csv_file_reader(
+            &CsvSources::default(),
+            CsvType::Test(pattern.clone()),
+            DELIMITER,
+        );
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(InfrastructureError::CSVFileNotFound(file)) if file == pattern.to_str().unwrap()
+        ));
+    }
+
     #[test]
     fn test_read_csv_with_invalid_path() {
         // Arrange: Use non-existent paths
         let invalid_file_path = "/invalid/orders.csv";
 
         // Act
-        let result =
-            make_csv_file_reader(CsvType::Test(PathBuf::from(invalid_file_path)), DELIMITER);
+        let result = make_csv_file_reader(
+            &CsvSources::default(),
+            CsvType::Test(PathBuf::from(invalid_file_path)),
+            DELIMITER,
+        );
 
         // Assert
         let error_variant = result.unwrap_err();
@@ -11,6 +11,9 @@ pub struct CsvOrderLineDTO {
     pub qty_reserved: String,
     pub qty_delivered: String,
     pub due_date: String,
+    // Unit of measure of the quantities, e.g. PCE or MTR, none when missing or empty
+    #[serde(default)]
+    pub uom: String,
 }
 
 impl CsvDTO for CsvOrderLineDTO {}
@@ -37,6 +40,7 @@ pub mod tests {
                 qty_reserved: "5".to_string(),
                 qty_delivered: "5".to_string(),
                 due_date: "2023-08-01".to_string(),
+                uom: "PCE".to_string(),
             },
             CsvOrderLineDTO {
                 c_orderline_id: 2.to_string(),
@@ -46,6 +50,7 @@ pub mod tests {
                 qty_reserved: "10".to_string(),
                 qty_delivered: "10".to_string(),
                 due_date: "2023-08-02".to_string(),
+                uom: "PCE".to_string(),
             },
             CsvOrderLineDTO {
                 c_orderline_id: 3.to_string(),
@@ -55,6 +60,7 @@ pub mod tests {
                 qty_reserved: "15".to_string(),
                 qty_delivered: "15".to_string(),
                 due_date: String::new(),
+                uom: String::new(),
             },
         ]
     }
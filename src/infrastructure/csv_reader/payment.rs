@@ -0,0 +1,90 @@
+use serde::Deserialize;
+
+use super::*;
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct CsvPaymentDTO {
+    pub c_payment_id: String,
+    pub documentno: String,
+    pub date_trx: String,
+    pub pay_amt: String,
+    // ISO 4217 code of the amount, EUR when the column is missing or empty
+    #[serde(default)]
+    pub currency: String,
+}
+
+impl CsvDTO for CsvPaymentDTO {}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct CsvPaymentAllocationDTO {
+    pub c_allocationline_id: String,
+    pub c_payment_id: String,
+    pub c_invoice_id: String,
+    pub amount: String,
+    // Zero when the column is missing or empty
+    #[serde(default)]
+    pub discount_amt: String,
+    #[serde(default)]
+    pub write_off_amt: String,
+}
+
+impl CsvDTO for CsvPaymentAllocationDTO {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    pub fn csv_payment_dto_fixtures() -> [CsvPaymentDTO; 3] {
+        [
+            CsvPaymentDTO {
+                c_payment_id: "1".to_string(),
+                documentno: "PAY-1".to_string(),
+                date_trx: "2020-02-01".to_string(),
+                pay_amt: "100.00".to_string(),
+                currency: "EUR".to_string(),
+            },
+            CsvPaymentDTO {
+                c_payment_id: "2".to_string(),
+                documentno: "PAY-2".to_string(),
+                date_trx: "2020-02-02".to_string(),
+                pay_amt: "19.50".to_string(),
+                currency: "".to_string(),
+            },
+            CsvPaymentDTO {
+                c_payment_id: "3".to_string(),
+                documentno: "PAY-3".to_string(),
+                date_trx: "2020-02-03".to_string(),
+                pay_amt: "-200".to_string(),
+                currency: "EUR".to_string(),
+            },
+        ]
+    }
+
+    pub fn csv_payment_allocation_dto_fixtures() -> [CsvPaymentAllocationDTO; 3] {
+        [
+            CsvPaymentAllocationDTO {
+                c_allocationline_id: "1".to_string(),
+                c_payment_id: "1".to_string(),
+                c_invoice_id: "1".to_string(),
+                amount: "100.00".to_string(),
+                discount_amt: "".to_string(),
+                write_off_amt: "".to_string(),
+            },
+            CsvPaymentAllocationDTO {
+                c_allocationline_id: "2".to_string(),
+                c_payment_id: "2".to_string(),
+                c_invoice_id: "1".to_string(),
+                amount: "19.50".to_string(),
+                discount_amt: "0.50".to_string(),
+                write_off_amt: "0".to_string(),
+            },
+            CsvPaymentAllocationDTO {
+                c_allocationline_id: "3".to_string(),
+                c_payment_id: "3".to_string(),
+                c_invoice_id: "3".to_string(),
+                amount: "-200".to_string(),
+                discount_amt: "".to_string(),
+                write_off_amt: "".to_string(),
+            },
+        ]
+    }
+}
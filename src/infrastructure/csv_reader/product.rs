@@ -12,11 +12,19 @@ pub struct CsvProductSubstituteDTO {
 
 impl CsvDTO for CsvProductSubstituteDTO {}
 
-pub struct ProductCsvDataSourceReader;
+pub struct ProductCsvDataSourceReader {
+    sources: CsvSources,
+}
+
+impl ProductCsvDataSourceReader {
+    pub fn new(sources: CsvSources) -> Self {
+        Self { sources }
+    }
+}
 
 impl CanReadCSVDataSource<CsvProductSubstituteDTO> for ProductCsvDataSourceReader {
     fn find_all(&self) -> Result<Vec<CsvProductSubstituteDTO>, InfrastructureError> {
-        self.read(CsvType::ProductSubstitute)
+        self.read(&self.sources, CsvType::ProductSubstitute)
     }
 }
 
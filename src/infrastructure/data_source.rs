@@ -1,7 +1,7 @@
 use serde::Deserialize;
 
 use super::{
-    csv_reader::{make_csv_file_reader, CsvDTO, CsvType},
+    csv_reader::{make_csv_file_reader, CsvDTO, CsvSources, CsvType},
     database::{connection::HasConnection, models::CanSelectAllModel},
     InfrastructureError,
 };
@@ -10,8 +10,8 @@ pub(crate) trait CanReadCSVDataSource<T>
 where
     T: CsvDTO + for<'a> Deserialize<'a>,
 {
-    fn read(&self, csv_type: CsvType) -> Result<Vec<T>, InfrastructureError> {
-        let csv_reader = make_csv_file_reader(csv_type, b';')?;
+    fn read(&self, sources: &CsvSources, csv_type: CsvType) -> Result<Vec<T>, InfrastructureError> {
+        let csv_reader = make_csv_file_reader(sources, csv_type, b';')?;
 
         let csv_data: Vec<T> = csv_reader.read().map_err(InfrastructureError::CsvError)?;
         Ok(csv_data)
@@ -2,3 +2,5 @@ pub(crate) mod batch;
 pub mod connection;
 pub mod models;
 mod schema;
+pub(crate) mod schema_check;
+pub(crate) mod tls;
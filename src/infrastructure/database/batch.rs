@@ -1,4 +1,5 @@
 use super::connection::{DbConnection, HasConnection};
+use crate::infrastructure::shutdown;
 use diesel::result::Error as DieselError;
 use std::cell::RefCell;
 
@@ -48,6 +49,10 @@ impl<'a, M> Batch<'a, M> {
         let iter = self.models.chunks(self.config.max_batch_size);
 
         for chunk in iter {
+            // The current chunk always completes, the next ones are skipped on shutdown
+            if shutdown::is_requested() {
+                break;
+            }
             let result = (self.cb)(chunk, &mut self.connection.borrow_mut());
             if let Err(e) = result {
                 errors.push(e);
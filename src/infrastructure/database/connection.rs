@@ -1,40 +1,149 @@
-use std::env;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
 
 use diesel::prelude::*;
 use lazy_static::lazy_static;
+use percent_encoding::percent_decode_str;
 
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 
+use super::tls::TlsOptions;
+use crate::infrastructure::{profile, InfrastructureError};
+
 pub type DbConnection = PooledConnection<ConnectionManager<MysqlConnection>>;
 
 pub type DbPool = Pool<ConnectionManager<MysqlConnection>>;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum Database {
     Target,
     LegacyStaging,
 }
 
+impl Database {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Database::Target => "target",
+            Database::LegacyStaging => "legacy_staging",
+        }
+    }
+
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            Database::Target => "TARGET_DATABASE_URL",
+            Database::LegacyStaging => "LEGACY_STAGING_DATABASE_URL",
+        }
+    }
+
+    /// URL of the active target profile or of the configuration, with the TLS settings of the database
+    pub fn url(&self) -> Result<String, InfrastructureError> {
+        let settings = DATABASE_SETTINGS.lock().unwrap_or_else(|e| e.into_inner());
+        let (url, tls) = match self {
+            Database::Target => (&settings.target_url, &settings.target_tls),
+            Database::LegacyStaging => (&settings.legacy_staging_url, &settings.legacy_staging_tls),
+        };
+        let url = match (self, profile::active_target_url()) {
+            (Database::Target, Some(profile_url)) => profile_url,
+            _ => url.clone().ok_or_else(|| {
+                InfrastructureError::ConfigError(format!(
+                    "databases.{}_url ({}) is not configured",
+                    self.name(),
+                    self.env_var()
+                ))
+            })?,
+        };
+        tls.apply(&url)
+    }
+
+    /// Dedicated connection outside of the pools, which panic when the database is unreachable
+    pub fn establish_connection(&self) -> Result<MysqlConnection, InfrastructureError> {
+        let database_url = self.url()?;
+        MysqlConnection::establish(&database_url).map_err(|e| {
+            InfrastructureError::ConnectionError(redact(&e.to_string(), &database_url))
+        })
+    }
+}
+
+/// Database URLs and TLS settings of the configuration, the URLs holding passwords
+#[derive(Default, Clone)]
+pub struct DatabaseSettings {
+    pub target_url: Option<String>,
+    pub legacy_staging_url: Option<String>,
+    pub target_tls: TlsOptions,
+    pub legacy_staging_tls: TlsOptions,
+}
+
 lazy_static! {
-    static ref TARGET_CONNECTION_POOL: DbPool = {
-        // Load environment variables from .env file
-        // TODO: Must be done in main.rs
-        dotenvy::dotenv().ok();
+    static ref DATABASE_SETTINGS: Mutex<DatabaseSettings> = Mutex::new(DatabaseSettings::default());
+}
+
+/// Settings the pools and connections are created with, to be set before the first connection
+pub fn configure(settings: DatabaseSettings) {
+    *DATABASE_SETTINGS.lock().unwrap_or_else(|e| e.into_inner()) = settings;
+}
+
+/// Database URL with its password masked, safe to log
+pub struct RedactedUrl<'a>(pub &'a str);
+
+impl fmt::Display for RedactedUrl<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match url::Url::parse(self.0) {
+            Ok(mut url) if url.password().is_some() => {
+                let _ = url.set_password(Some("***"));
+                write!(f, "{}", url)
+            }
+            Ok(_) => write!(f, "{}", self.0),
+            Err(_) => write!(f, "<invalid URL>"),
+        }
+    }
+}
+
+impl fmt::Debug for RedactedUrl<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+/// Masks the password of the database URL, and the URL itself, in a message.
+/// The password is masked both as written in the URL and percent-decoded, whatever its length,
+/// so only error texts are given, not whole log lines.
+pub fn redact(message: &str, database_url: &str) -> String {
+    let mut message = message.replace(database_url, &RedactedUrl(database_url).to_string());
+    let Some(encoded) = url::Url::parse(database_url)
+        .ok()
+        .and_then(|url| url.password().map(str::to_string))
+    else {
+        return message;
+    };
+    let decoded = percent_decode_str(&encoded).decode_utf8_lossy().to_string();
+
+    for password in [encoded, decoded] {
+        if !password.is_empty() {
+            message = message.replace(&password, "***");
+        }
+    }
+    message
+}
 
-        let target_database_url = env::var("TARGET_DATABASE_URL")
-            .expect("TARGET_DATABASE_URL must be set in the .env file");
+lazy_static! {
+    static ref TARGET_CONNECTION_POOL: DbPool = {
+        let target_database_url = Database::Target.url().unwrap_or_else(|e| panic!("{}", e));
 
         establish_connection_pool(&target_database_url)
     };
 }
 
 lazy_static! {
-    static ref LEGACT_STAGING_CONNECTION_POOL: DbPool = {
-        // Load environment variables from .env file
-        // TODO: Must be done in main.rs
-        dotenvy::dotenv().ok();
+    // Target databases of the profiles, by profile URL
+    static ref PROFILE_CONNECTION_POOLS: Mutex<HashMap<String, DbPool>> = Mutex::new(HashMap::new());
+}
 
-        let legacy_staging_database_url = env::var("LEGACY_STAGING_DATABASE_URL")
-            .expect("LEGACY_STAGING_DATABASE_URL must be set in the .env file");
+lazy_static! {
+    static ref LEGACT_STAGING_CONNECTION_POOL: DbPool = {
+        let legacy_staging_database_url = Database::LegacyStaging
+            .url()
+            .unwrap_or_else(|e| panic!("{}", e));
 
         establish_connection_pool(&legacy_staging_database_url)
     };
@@ -43,9 +152,12 @@ lazy_static! {
 // Set up a connection pool for the specified database URL
 pub fn establish_connection_pool(target_database_url: &str) -> DbPool {
     let manager = ConnectionManager::<MysqlConnection>::new(target_database_url);
-    Pool::builder()
-        .build(manager)
-        .expect("Failed to create connection pool")
+    Pool::builder().build(manager).unwrap_or_else(|e| {
+        panic!(
+            "Failed to create connection pool: {}",
+            redact(&e.to_string(), target_database_url)
+        )
+    })
 }
 
 // Function to get a reference to the connection pool
@@ -57,7 +169,26 @@ fn get_legacy_staging_pooled_connection() -> &'static DbPool {
     &LEGACT_STAGING_CONNECTION_POOL
 }
 
+// Pools are created once per profile URL, with the target TLS settings
+fn get_profile_pooled_connection(profile_url: String) -> DbPool {
+    let mut pools = PROFILE_CONNECTION_POOLS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    pools
+        .entry(profile_url)
+        .or_insert_with(|| {
+            let target_database_url = Database::Target.url().unwrap_or_else(|e| panic!("{}", e));
+            establish_connection_pool(&target_database_url)
+        })
+        .clone()
+}
+
 pub(crate) fn get_pooled_connection(db: Database) -> DbConnection {
+    if let (Database::Target, Some(profile_url)) = (db, profile::active_target_url()) {
+        return get_profile_pooled_connection(profile_url)
+            .get()
+            .expect("Failed to get connection");
+    }
     let result = match db {
         Database::Target => get_target_pooled_connection(),
         Database::LegacyStaging => get_legacy_staging_pooled_connection(),
@@ -86,6 +217,8 @@ impl HasConnection for HasLegacyStagingConnection {
 #[cfg(test)]
 pub(crate) mod tests {
     use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+    use serial_test::serial;
+    use std::env;
     pub const TEST_MIGRATIONS: EmbeddedMigrations = embed_migrations!("tests/migrations");
     use super::*;
     lazy_static! {
@@ -138,4 +271,65 @@ pub(crate) mod tests {
         teardown_test_database(connection);
         setup_test_database(connection);
     }
+
+    #[test]
+    #[serial]
+    fn test_url_from_settings() {
+        configure(DatabaseSettings {
+            legacy_staging_url: Some("mysql://etl:s3cret@db:3306/etl".to_string()),
+            legacy_staging_tls: TlsOptions::from_values(Some("required"), None, None, None)
+                .unwrap(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            Database::LegacyStaging.url().unwrap(),
+            "mysql://etl:s3cret@db:3306/etl?ssl_mode=required"
+        );
+        assert!(Database::Target.url().is_err());
+
+        configure(DatabaseSettings::default());
+    }
+
+    #[test]
+    fn test_redact() {
+        let url = "mysql://etl:s3cret@db:3306/etl";
+
+        assert_eq!(RedactedUrl(url).to_string(), "mysql://etl:***@db:3306/etl");
+        assert_eq!(
+            redact(
+                &format!("Unable to connect to {}: s3cret refused", url),
+                url
+            ),
+            "Unable to connect to mysql://etl:***@db:3306/etl: *** refused"
+        );
+    }
+
+    #[test]
+    fn test_redact_percent_encoded_password() {
+        let url = "mysql://etl:p%40ss%3Aw0rd@db:3306/etl";
+
+        assert_eq!(RedactedUrl(url).to_string(), "mysql://etl:***@db:3306/etl");
+        assert_eq!(
+            redact(
+                "Access to mysql://etl:p@ss:w0rd@db denied for p@ss:w0rd",
+                url
+            ),
+            "Access to mysql://etl:***@db denied for ***"
+        );
+    }
+
+    #[test]
+    fn test_redact_short_password() {
+        let url = "mysql://etl:pw1@db:3306/etl";
+
+        assert_eq!(
+            redact("Unable to connect to mysql://etl:pw1@db as etl", url),
+            "Unable to connect to mysql://etl:***@db as etl"
+        );
+        assert_eq!(
+            redact("Access denied for etl using pw1", url),
+            "Access denied for etl using ***"
+        );
+    }
 }
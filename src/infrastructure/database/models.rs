@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use diesel::associations::HasTable;
 use diesel::insertable::CanInsertInSingleQuery;
 use diesel::internal::derives::multiconnection::DieselReserveSpecialization;
@@ -5,17 +6,21 @@ use diesel::query_builder::{AsQuery, IntoUpdateTarget, QueryFragment, QueryId, U
 use diesel::query_dsl::methods::ExecuteDsl;
 use diesel::result::Error as DieselError;
 use diesel::{AsChangeset, Connection, Insertable, RunQueryDsl, Table};
+use sha2::{Digest, Sha256};
 
 use super::connection::DbConnection;
 
 pub(crate) mod delivery_slip;
 pub(crate) mod invoice;
+pub(crate) mod invoice_line;
 pub(crate) mod language;
 pub(crate) mod mapping_client;
 pub(crate) mod order;
 pub(crate) mod order_line;
+pub(crate) mod payment;
 pub(crate) mod product;
 pub(crate) mod product_substitute;
+pub(crate) mod row_hash;
 
 pub use order::OrderModel;
 
@@ -25,6 +30,63 @@ pub trait CanUpsertModel: Model {
     fn upsert(&self, connection: &mut DbConnection) -> Result<(), DieselError>;
 }
 
+/// Identifies a model row and fingerprints its content for the incremental mode
+pub trait CanHashModel: Model {
+    /// Entity name under which row hashes are stored
+    const ENTITY: &'static str;
+
+    fn row_key(&self) -> String;
+
+    /// Values of the written columns, always in the same order, None for NULL
+    fn column_values(&self) -> Vec<Option<String>>;
+
+    fn row_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for value in self.column_values() {
+            match value {
+                // Length prefixed so that adjacent values cannot run into each other
+                Some(value) => {
+                    hasher.update([1]);
+                    hasher.update((value.len() as u64).to_le_bytes());
+                    hasher.update(value);
+                }
+                None => hasher.update([0]),
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Deletes rows along with the rows depending on them, returns the number of deleted rows
+pub trait CanDeleteByIdsModel: Model {
+    fn delete_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError>;
+}
+
+/// Target rows which can be deleted or flagged once they disappear from a full extract.
+/// Flagged rows have is_flagged set, an upsert of the row clears it.
+pub trait CanSyncDeletesModel: CanDeleteByIdsModel {
+    fn select_ids(
+        connection: &mut DbConnection,
+        include_flagged: bool,
+    ) -> Result<Vec<u32>, DieselError>;
+
+    fn flag_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError>;
+}
+
+/// Target rows which can be purged once they are older than the retention period
+pub trait CanPurgeModel: CanDeleteByIdsModel {
+    fn count_older_than(
+        connection: &mut DbConnection,
+        cutoff: NaiveDate,
+    ) -> Result<i64, DieselError>;
+
+    fn select_ids_older_than(
+        connection: &mut DbConnection,
+        cutoff: NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<u32>, DieselError>;
+}
+
 pub trait CanSelectAllModel: Model
 where
     Self: Sized,
@@ -4,9 +4,11 @@ use chrono::NaiveDate;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 
-use super::{CanUpsertModel, Model};
+use super::{
+    CanDeleteByIdsModel, CanHashModel, CanPurgeModel, CanSyncDeletesModel, CanUpsertModel, Model,
+};
 
-#[derive(Queryable, Identifiable, Insertable, AsChangeset, PartialEq, Debug, Clone)]
+#[derive(Queryable, Selectable, Identifiable, Insertable, AsChangeset, PartialEq, Debug, Clone)]
 #[diesel(table_name = schema::target::delivery_slip)]
 #[diesel(primary_key(id_delivery_slip))]
 pub struct DeliverySlipModel {
@@ -28,6 +30,86 @@ impl CanUpsertModel for DeliverySlipModel {
     }
 }
 
+impl CanHashModel for DeliverySlipModel {
+    const ENTITY: &'static str = "delivery_slip";
+
+    fn row_key(&self) -> String {
+        self.id_delivery_slip.to_string()
+    }
+
+    fn column_values(&self) -> Vec<Option<String>> {
+        vec![
+            Some(self.id_delivery_slip.to_string()),
+            Some(self.id_client.to_string()),
+            Some(self.reference.clone()),
+            self.shipping_date
+                .map(|shipping_date| shipping_date.to_string()),
+            self.po_ref.clone(),
+            self.carrier_name.clone(),
+            self.status.clone(),
+            self.tracking_number.clone(),
+            self.tracking_link.clone(),
+        ]
+    }
+}
+
+impl CanSyncDeletesModel for DeliverySlipModel {
+    fn select_ids(
+        connection: &mut DbConnection,
+        include_flagged: bool,
+    ) -> Result<Vec<u32>, DieselError> {
+        use self::schema::target::delivery_slip::dsl::*;
+        let query = delivery_slip.select(id_delivery_slip).into_boxed();
+        if include_flagged {
+            query.load(connection)
+        } else {
+            query.filter(is_flagged.eq(false)).load(connection)
+        }
+    }
+
+    fn flag_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError> {
+        use self::schema::target::delivery_slip::dsl::*;
+        diesel::update(delivery_slip.filter(id_delivery_slip.eq_any(ids)))
+            .set(is_flagged.eq(true))
+            .execute(connection)
+    }
+}
+
+impl CanDeleteByIdsModel for DeliverySlipModel {
+    fn delete_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError> {
+        use self::schema::target::delivery_slip::dsl::*;
+        diesel::delete(delivery_slip.filter(id_delivery_slip.eq_any(ids))).execute(connection)
+    }
+}
+
+// Delivery slips without shipping date are never purged
+impl CanPurgeModel for DeliverySlipModel {
+    fn count_older_than(
+        connection: &mut DbConnection,
+        cutoff: NaiveDate,
+    ) -> Result<i64, DieselError> {
+        use self::schema::target::delivery_slip::dsl::*;
+        delivery_slip
+            .filter(shipping_date.lt(cutoff))
+            .count()
+            .get_result(connection)
+    }
+
+    fn select_ids_older_than(
+        connection: &mut DbConnection,
+        cutoff: NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<u32>, DieselError> {
+        use self::schema::target::delivery_slip::dsl::*;
+        delivery_slip
+            .filter(shipping_date.lt(cutoff))
+            .select(id_delivery_slip)
+            .order(id_delivery_slip)
+            .limit(limit)
+            .load(connection)
+    }
+}
+
 pub fn batch_upsert(
     models: &[DeliverySlipModel],
     connection: &mut DbConnection,
@@ -103,7 +185,8 @@ pub mod tests {
 
     pub fn read_delivery_slips(connection: &mut DbConnection) -> Vec<DeliverySlipModel> {
         schema::target::delivery_slip::dsl::delivery_slip
-            .load::<DeliverySlipModel>(connection)
+            .select(DeliverySlipModel::as_select())
+            .load(connection)
             .expect("Error loading updated DeliverySlipModel")
     }
 
@@ -121,7 +204,8 @@ pub mod tests {
                 schema::target::delivery_slip::id_delivery_slip
                     .eq(&delivery_slip_model_fixtures()[0].id_delivery_slip),
             )
-            .load::<DeliverySlipModel>(&mut connection)
+            .select(DeliverySlipModel::as_select())
+            .load(&mut connection)
             .expect("Error loading inserted DeloverySlipModel");
 
         assert_eq!(result.len(), 1);
@@ -146,7 +230,8 @@ pub mod tests {
 
         let result = schema::target::delivery_slip::dsl::delivery_slip
             .filter(schema::target::delivery_slip::id_delivery_slip.eq(1))
-            .load::<DeliverySlipModel>(&mut connection)
+            .select(DeliverySlipModel::as_select())
+            .load(&mut connection)
             .expect("Error loading inserted/upserted DeloverySlipModel");
 
         assert_eq!(result.len(), 1);
@@ -1,4 +1,9 @@
-use super::{CanUpsertModel, Model};
+use super::{
+    invoice_line::InvoiceLineModel,
+    payment::{PaymentAllocationModel, PaymentModel},
+    row_hash::RowHashModel,
+    CanDeleteByIdsModel, CanHashModel, CanPurgeModel, CanUpsertModel, Model,
+};
 use crate::infrastructure::database::connection::DbConnection;
 use crate::infrastructure::database::schema;
 use chrono::NaiveDate;
@@ -6,6 +11,9 @@ use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 use rust_decimal::Decimal;
 
+// Keep IN clauses to a reasonable size
+const SELECT_CHUNK_SIZE: usize = 1000;
+
 #[derive(
     Queryable,
     Selectable,
@@ -39,6 +47,17 @@ pub struct InvoiceModel {
     pub po_ref: Option<String>,
     pub total_tax_excl: Decimal,
     pub total_tax_incl: Decimal,
+    pub currency: String,
+    pub document_type: String,
+}
+
+#[derive(Queryable, Identifiable, Insertable, AsChangeset, PartialEq, Debug, Clone)]
+#[diesel(table_name = schema::target::invoice_open_amount)]
+#[diesel(primary_key(id_invoice))]
+pub struct InvoiceOpenAmountModel {
+    pub id_invoice: u32,
+    pub open_amount: Decimal,
+    pub is_paid: bool,
 }
 
 impl Model for InvoiceModel {}
@@ -58,6 +77,158 @@ impl CanUpsertModel for (InvoiceModel, Vec<InvoiceLangModel>) {
     }
 }
 
+impl CanHashModel for (InvoiceModel, Vec<InvoiceLangModel>) {
+    const ENTITY: &'static str = "invoice";
+
+    fn row_key(&self) -> String {
+        self.0.id_invoice.to_string()
+    }
+
+    fn column_values(&self) -> Vec<Option<String>> {
+        let (invoice, invoice_langs) = self;
+        let mut values = vec![
+            Some(invoice.id_invoice.to_string()),
+            Some(invoice.id_client.to_string()),
+            invoice.client_name.clone(),
+            Some(invoice.invoice_ref.clone()),
+            Some(invoice.date.to_string()),
+            invoice.file_name.clone(),
+            invoice.po_ref.clone(),
+            Some(invoice.total_tax_excl.to_string()),
+            Some(invoice.total_tax_incl.to_string()),
+            Some(invoice.currency.clone()),
+            Some(invoice.document_type.clone()),
+        ];
+        for invoice_lang in invoice_langs {
+            values.push(Some(invoice_lang.id_lang.to_string()));
+            values.push(Some(invoice_lang.type_name.clone()));
+        }
+        values
+    }
+}
+
+impl CanDeleteByIdsModel for (InvoiceModel, Vec<InvoiceLangModel>) {
+    // Lines and allocations are removed along with the row hashes of the lines and payments, so
+    // they are written again when exported again
+    fn delete_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError> {
+        use self::schema::target::{
+            invoice, invoice_lang, invoice_line, invoice_open_amount, payment_allocation,
+        };
+        diesel::delete(invoice_lang::table.filter(invoice_lang::id_invoice.eq_any(ids)))
+            .execute(connection)?;
+        let invoice_line_keys: Vec<String> = invoice_line::table
+            .filter(invoice_line::id_invoice.eq_any(ids))
+            .select(invoice_line::id_invoice_line)
+            .load::<u32>(connection)?
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        if !invoice_line_keys.is_empty() {
+            diesel::delete(invoice_line::table.filter(invoice_line::id_invoice.eq_any(ids)))
+                .execute(connection)?;
+            RowHashModel::delete_by_keys(connection, InvoiceLineModel::ENTITY, &invoice_line_keys)?;
+        }
+        diesel::delete(
+            invoice_open_amount::table.filter(invoice_open_amount::id_invoice.eq_any(ids)),
+        )
+        .execute(connection)?;
+        let payment_keys: Vec<String> = payment_allocation::table
+            .filter(payment_allocation::id_invoice.eq_any(ids))
+            .select(payment_allocation::id_payment)
+            .distinct()
+            .load::<u32>(connection)?
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        if !payment_keys.is_empty() {
+            diesel::delete(
+                payment_allocation::table.filter(payment_allocation::id_invoice.eq_any(ids)),
+            )
+            .execute(connection)?;
+            RowHashModel::delete_by_keys(
+                connection,
+                <(PaymentModel, Vec<PaymentAllocationModel>)>::ENTITY,
+                &payment_keys,
+            )?;
+        }
+        diesel::delete(invoice::table.filter(invoice::id_invoice.eq_any(ids))).execute(connection)
+    }
+}
+
+impl CanPurgeModel for (InvoiceModel, Vec<InvoiceLangModel>) {
+    fn count_older_than(
+        connection: &mut DbConnection,
+        cutoff: NaiveDate,
+    ) -> Result<i64, DieselError> {
+        use self::schema::target::invoice::dsl::*;
+        invoice
+            .filter(date.lt(cutoff))
+            .count()
+            .get_result(connection)
+    }
+
+    fn select_ids_older_than(
+        connection: &mut DbConnection,
+        cutoff: NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<u32>, DieselError> {
+        use self::schema::target::invoice::dsl::*;
+        invoice
+            .filter(date.lt(cutoff))
+            .select(id_invoice)
+            .order(id_invoice)
+            .limit(limit)
+            .load(connection)
+    }
+}
+
+impl InvoiceModel {
+    pub fn select_by_id(
+        connection: &mut DbConnection,
+        invoice_id: &u32,
+    ) -> Result<Self, DieselError> {
+        use self::schema::target::invoice::dsl::*;
+        invoice.filter(id_invoice.eq(invoice_id)).first(connection)
+    }
+
+    /// Total with taxes and currency of the given invoices
+    pub fn select_totals(
+        connection: &mut DbConnection,
+        invoice_ids: &[u32],
+    ) -> Result<Vec<(u32, Decimal, String)>, DieselError> {
+        use self::schema::target::invoice::dsl::*;
+        let mut totals = Vec::new();
+        for chunk in invoice_ids.chunks(SELECT_CHUNK_SIZE) {
+            totals.extend(
+                invoice
+                    .filter(id_invoice.eq_any(chunk))
+                    .select((id_invoice, total_tax_incl, currency))
+                    .order(id_invoice)
+                    .load::<(u32, Decimal, String)>(connection)?,
+            );
+        }
+        Ok(totals)
+    }
+}
+
+impl Model for InvoiceOpenAmountModel {}
+impl CanUpsertModel for InvoiceOpenAmountModel {
+    fn upsert(&self, connection: &mut DbConnection) -> Result<(), DieselError> {
+        super::upsert!(schema::target::invoice_open_amount::table, self, connection)
+    }
+}
+
+pub fn batch_upsert_open_amounts(
+    models: &[InvoiceOpenAmountModel],
+    connection: &mut DbConnection,
+) -> Result<(), DieselError> {
+    super::upsert!(
+        schema::target::invoice_open_amount::table,
+        models,
+        connection
+    )
+}
+
 pub fn batch_upsert(
     models: &[(InvoiceModel, Vec<InvoiceLangModel>)],
     connection: &mut DbConnection,
@@ -99,6 +270,8 @@ pub mod tests {
                 po_ref: Some("PO-1".to_string()),
                 total_tax_excl: Decimal::new(10000, 2),
                 total_tax_incl: Decimal::new(12000, 2),
+                currency: "EUR".to_string(),
+                document_type: "invoice".to_string(),
             },
             InvoiceModel {
                 id_invoice: 3,
@@ -109,7 +282,9 @@ pub mod tests {
                 date: NaiveDate::from_ymd_opt(2020, 1, 3).unwrap(),
                 po_ref: None,
                 total_tax_excl: Decimal::new(-30000, 2),
-                total_tax_incl: Decimal::new(36000, 2),
+                total_tax_incl: Decimal::new(-36000, 2),
+                currency: "EUR".to_string(),
+                document_type: "credit_note".to_string(),
             },
         ]
     }
@@ -160,6 +335,13 @@ pub mod tests {
             .expect("Error loading updated InvoiceModel")
     }
 
+    pub fn read_open_amounts(connection: &mut DbConnection) -> Vec<InvoiceOpenAmountModel> {
+        schema::target::invoice_open_amount::dsl::invoice_open_amount
+            .order(schema::target::invoice_open_amount::id_invoice)
+            .load::<InvoiceOpenAmountModel>(connection)
+            .expect("Error loading InvoiceOpenAmountModel")
+    }
+
     pub fn read_invoice_types(
         connection: &mut DbConnection,
         invoice: &InvoiceModel,
@@ -0,0 +1,170 @@
+use crate::infrastructure::database::connection::DbConnection;
+use crate::infrastructure::database::schema;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use rust_decimal::Decimal;
+
+use super::{CanDeleteByIdsModel, CanHashModel, CanUpsertModel, Model};
+
+// Keep IN clauses to a reasonable size
+const SELECT_CHUNK_SIZE: usize = 1000;
+
+#[derive(Queryable, Identifiable, Insertable, AsChangeset, PartialEq, Debug, Clone)]
+#[diesel(table_name = schema::target::invoice_line)]
+#[diesel(primary_key(id_invoice_line))]
+pub struct InvoiceLineModel {
+    pub id_invoice_line: u32,
+    pub id_invoice: u32,
+    pub product_ref: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub tax_rate: Decimal,
+    pub total_tax_excl: Decimal,
+    pub total_tax_incl: Decimal,
+}
+
+impl InvoiceLineModel {
+    /// Id, invoice and total without taxes of the stored lines of the given invoices
+    pub fn select_totals_by_invoice(
+        connection: &mut DbConnection,
+        invoice_ids: &[u32],
+    ) -> Result<Vec<(u32, u32, Decimal)>, DieselError> {
+        use self::schema::target::invoice_line::dsl::*;
+        let mut totals = Vec::new();
+        for chunk in invoice_ids.chunks(SELECT_CHUNK_SIZE) {
+            totals.extend(
+                invoice_line
+                    .filter(id_invoice.eq_any(chunk))
+                    .select((id_invoice_line, id_invoice, total_tax_excl))
+                    .load::<(u32, u32, Decimal)>(connection)?,
+            );
+        }
+        Ok(totals)
+    }
+}
+
+impl Model for InvoiceLineModel {}
+impl CanUpsertModel for InvoiceLineModel {
+    fn upsert(&self, connection: &mut DbConnection) -> Result<(), DieselError> {
+        super::upsert!(schema::target::invoice_line::table, self, connection)
+    }
+}
+
+impl CanHashModel for InvoiceLineModel {
+    const ENTITY: &'static str = "invoice_line";
+
+    fn row_key(&self) -> String {
+        self.id_invoice_line.to_string()
+    }
+
+    fn column_values(&self) -> Vec<Option<String>> {
+        vec![
+            Some(self.id_invoice_line.to_string()),
+            Some(self.id_invoice.to_string()),
+            Some(self.product_ref.clone()),
+            Some(self.quantity.to_string()),
+            Some(self.unit_price.to_string()),
+            Some(self.tax_rate.to_string()),
+            Some(self.total_tax_excl.to_string()),
+            Some(self.total_tax_incl.to_string()),
+        ]
+    }
+}
+
+impl CanDeleteByIdsModel for InvoiceLineModel {
+    fn delete_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError> {
+        use self::schema::target::invoice_line::dsl::*;
+        diesel::delete(invoice_line.filter(id_invoice_line.eq_any(ids))).execute(connection)
+    }
+}
+
+pub fn batch_upsert(
+    models: &[InvoiceLineModel],
+    connection: &mut DbConnection,
+) -> Result<(), DieselError> {
+    super::upsert!(schema::target::invoice_line::table, models, connection)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use serial_test::serial;
+
+    use crate::infrastructure::database::connection::tests::{
+        get_test_pooled_connection, reset_test_database,
+    };
+
+    use super::*;
+    pub fn invoice_line_model_fixtures() -> [InvoiceLineModel; 3] {
+        [
+            InvoiceLineModel {
+                id_invoice_line: 1,
+                id_invoice: 1,
+                product_ref: "P-1".to_string(),
+                quantity: Decimal::from(2),
+                unit_price: Decimal::new(3000, 2),
+                tax_rate: Decimal::new(20, 0),
+                total_tax_excl: Decimal::new(6000, 2),
+                total_tax_incl: Decimal::new(7200, 2),
+            },
+            InvoiceLineModel {
+                id_invoice_line: 2,
+                id_invoice: 1,
+                product_ref: "P-2".to_string(),
+                quantity: Decimal::from(1),
+                unit_price: Decimal::new(4000, 2),
+                tax_rate: Decimal::new(200, 1),
+                total_tax_excl: Decimal::new(4000, 2),
+                total_tax_incl: Decimal::new(4800, 2),
+            },
+            InvoiceLineModel {
+                id_invoice_line: 3,
+                id_invoice: 3,
+                product_ref: "P-3".to_string(),
+                quantity: Decimal::from(3),
+                unit_price: Decimal::new(-10000, 2),
+                tax_rate: Decimal::new(20, 0),
+                total_tax_excl: Decimal::new(-30000, 2),
+                total_tax_incl: Decimal::new(-36000, 2),
+            },
+        ]
+    }
+
+    pub fn read_invoice_lines(connection: &mut DbConnection) -> Vec<InvoiceLineModel> {
+        schema::target::invoice_line::dsl::invoice_line
+            .order(schema::target::invoice_line::id_invoice_line)
+            .load::<InvoiceLineModel>(connection)
+            .expect("Error loading InvoiceLineModel")
+    }
+
+    #[test]
+    #[serial]
+    fn test_upsert_invoice_line() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        let mut invoice_line = invoice_line_model_fixtures()[0].clone();
+        invoice_line.upsert(&mut connection).unwrap();
+        invoice_line.quantity = Decimal::new(-15, 1);
+        invoice_line.upsert(&mut connection).unwrap();
+
+        assert_eq!(read_invoice_lines(&mut connection), vec![invoice_line]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_batch_upsert_and_delete() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        batch_upsert(&invoice_line_model_fixtures(), &mut connection).unwrap();
+        assert_eq!(
+            InvoiceLineModel::delete_by_ids(&mut connection, &[1, 2]).unwrap(),
+            2
+        );
+
+        assert_eq!(
+            read_invoice_lines(&mut connection),
+            vec![invoice_line_model_fixtures()[2].clone()]
+        );
+    }
+}
@@ -3,9 +3,11 @@ use crate::infrastructure::database::schema;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 
-use super::{CanSelectAllModel, CanUpsertModel, Model, SingleRowInsertable, SingleRowUpdatable};
+use super::{
+    CanHashModel, CanSelectAllModel, CanUpsertModel, Model, SingleRowInsertable, SingleRowUpdatable,
+};
 
-#[derive(Queryable, Identifiable, Insertable, AsChangeset, PartialEq)]
+#[derive(Queryable, Identifiable, Insertable, AsChangeset, PartialEq, Debug)]
 #[diesel(table_name = schema::target::mapping_client_contact)]
 #[diesel(primary_key(id_customer))]
 pub struct MappingClientModel {
@@ -24,6 +26,21 @@ impl CanUpsertModel for MappingClientModel {
     }
 }
 
+impl CanHashModel for MappingClientModel {
+    const ENTITY: &'static str = "mapping_client";
+
+    fn row_key(&self) -> String {
+        self.id_customer.to_string()
+    }
+
+    fn column_values(&self) -> Vec<Option<String>> {
+        vec![
+            Some(self.id_customer.to_string()),
+            Some(self.idp_id_client.to_string()),
+        ]
+    }
+}
+
 impl SingleRowInsertable<schema::target::mapping_client_contact::table, DbConnection>
     for MappingClientModel
 {
@@ -1,11 +1,25 @@
+use std::collections::BTreeMap;
+
 use crate::infrastructure::database::connection::DbConnection;
 use crate::infrastructure::database::schema;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
 
-use super::{CanUpsertModel, Model, SingleRowInsertable, SingleRowUpdatable};
+use super::{
+    order_line::{OrderLineLangModel, OrderLineModel},
+    row_hash::RowHashModel,
+    CanDeleteByIdsModel, CanHashModel, CanPurgeModel, CanSyncDeletesModel, CanUpsertModel, Model,
+    SingleRowInsertable, SingleRowUpdatable,
+};
+
+// Keep IN clauses to a reasonable size
+const SELECT_CHUNK_SIZE: usize = 1000;
+const UPDATE_CHUNK_SIZE: usize = 1000;
 
-#[derive(Queryable, Identifiable, Insertable, AsChangeset, PartialEq, Debug, Clone)]
+/// Order id with its stored and CSV completions
+pub type OrderCompletion = (u32, Option<u32>, Option<u32>);
+
+#[derive(Queryable, Selectable, Identifiable, Insertable, AsChangeset, PartialEq, Debug, Clone)]
 #[diesel(table_name = schema::target::order)]
 #[diesel(primary_key(id_order))]
 pub struct OrderModel {
@@ -17,6 +31,8 @@ pub struct OrderModel {
     pub po_ref: Option<String>,
     pub origin: Option<String>,
     pub completion: Option<u32>,
+    // Completion of the CSV, kept when the completion is recomputed from the lines
+    pub csv_completion: Option<u32>,
     pub order_status: Option<String>,
 }
 
@@ -27,6 +43,107 @@ impl CanUpsertModel for OrderModel {
     }
 }
 
+impl CanHashModel for OrderModel {
+    const ENTITY: &'static str = "order";
+
+    fn row_key(&self) -> String {
+        self.id_order.to_string()
+    }
+
+    fn column_values(&self) -> Vec<Option<String>> {
+        vec![
+            Some(self.id_order.to_string()),
+            Some(self.id_client.to_string()),
+            self.client_name.clone(),
+            Some(self.order_ref.clone()),
+            Some(self.date.to_string()),
+            self.po_ref.clone(),
+            self.origin.clone(),
+            self.completion.map(|completion| completion.to_string()),
+            self.order_status.clone(),
+        ]
+    }
+}
+
+impl CanSyncDeletesModel for OrderModel {
+    fn select_ids(
+        connection: &mut DbConnection,
+        include_flagged: bool,
+    ) -> Result<Vec<u32>, DieselError> {
+        use self::schema::target::order::dsl::*;
+        let query = order.select(id_order).into_boxed();
+        if include_flagged {
+            query.load(connection)
+        } else {
+            query.filter(is_flagged.eq(false)).load(connection)
+        }
+    }
+
+    fn flag_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError> {
+        use self::schema::target::order::dsl::*;
+        diesel::update(order.filter(id_order.eq_any(ids)))
+            .set(is_flagged.eq(true))
+            .execute(connection)
+    }
+}
+
+impl CanDeleteByIdsModel for OrderModel {
+    // Order lines have no cascade on delete, they are removed along with their row hashes
+    fn delete_by_ids(connection: &mut DbConnection, ids: &[u32]) -> Result<usize, DieselError> {
+        use self::schema::target::{order, order_line, order_line_lang};
+        let order_line_ids: Vec<u32> = order_line::table
+            .filter(order_line::id_order.eq_any(ids))
+            .select(order_line::id_order_line)
+            .load(connection)?;
+        if !order_line_ids.is_empty() {
+            diesel::delete(
+                order_line_lang::table
+                    .filter(order_line_lang::id_order_line.eq_any(&order_line_ids)),
+            )
+            .execute(connection)?;
+            diesel::delete(
+                order_line::table.filter(order_line::id_order_line.eq_any(&order_line_ids)),
+            )
+            .execute(connection)?;
+            let order_line_keys: Vec<String> =
+                order_line_ids.iter().map(|id| id.to_string()).collect();
+            RowHashModel::delete_by_keys(
+                connection,
+                <(OrderLineModel, Vec<OrderLineLangModel>)>::ENTITY,
+                &order_line_keys,
+            )?;
+        }
+        diesel::delete(order::table.filter(order::id_order.eq_any(ids))).execute(connection)
+    }
+}
+
+impl CanPurgeModel for OrderModel {
+    fn count_older_than(
+        connection: &mut DbConnection,
+        cutoff: chrono::NaiveDate,
+    ) -> Result<i64, DieselError> {
+        use self::schema::target::order::dsl::*;
+        order
+            .filter(date.lt(cutoff.and_time(chrono::NaiveTime::MIN)))
+            .count()
+            .get_result(connection)
+    }
+
+    fn select_ids_older_than(
+        connection: &mut DbConnection,
+        cutoff: chrono::NaiveDate,
+        limit: i64,
+    ) -> Result<Vec<u32>, DieselError> {
+        use self::schema::target::order::dsl::*;
+        order
+            .filter(date.lt(cutoff.and_time(chrono::NaiveTime::MIN)))
+            .select(id_order)
+            .order(id_order)
+            .limit(limit)
+            .load(connection)
+    }
+}
+
 impl SingleRowInsertable<schema::target::order::table, DbConnection> for OrderModel {
     fn target_client_table(&self) -> schema::target::order::table {
         schema::target::order::table
@@ -45,7 +162,52 @@ impl OrderModel {
         order_id: &u32,
     ) -> Result<Self, DieselError> {
         use self::schema::target::order::dsl::*;
-        order.filter(id_order.eq(order_id)).first(connection)
+        order
+            .filter(id_order.eq(order_id))
+            .select(OrderModel::as_select())
+            .first(connection)
+    }
+
+    pub fn select_completions(
+        connection: &mut DbConnection,
+        order_ids: &[u32],
+    ) -> Result<Vec<OrderCompletion>, DieselError> {
+        use self::schema::target::order::dsl::*;
+        let mut completions = Vec::new();
+        for chunk in order_ids.chunks(SELECT_CHUNK_SIZE) {
+            completions.extend(
+                order
+                    .filter(id_order.eq_any(chunk))
+                    .select((id_order, completion, csv_completion))
+                    .order(id_order)
+                    .load::<OrderCompletion>(connection)?,
+            );
+        }
+        Ok(completions)
+    }
+
+    /// Sets the completion of orders, one statement per completion value and chunk
+    pub fn update_completions(
+        connection: &mut DbConnection,
+        completions: &[(u32, u32)],
+    ) -> Result<usize, DieselError> {
+        use self::schema::target::order::dsl::*;
+        let mut orders_by_completion: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for (order_id, value) in completions {
+            orders_by_completion
+                .entry(*value)
+                .or_default()
+                .push(*order_id);
+        }
+        let mut updated = 0;
+        for (value, order_ids) in orders_by_completion {
+            for chunk in order_ids.chunks(UPDATE_CHUNK_SIZE) {
+                updated += diesel::update(order.filter(id_order.eq_any(chunk)))
+                    .set(completion.eq(value))
+                    .execute(connection)?;
+            }
+        }
+        Ok(updated)
     }
 }
 
@@ -64,6 +226,7 @@ pub mod bench {
                 po_ref: Some("PoRef1".to_string()),
                 origin: Some("Web".to_string()),
                 completion: Some(30),
+                csv_completion: Some(30),
                 date: NaiveDateTime::new(
                     NaiveDate::from_ymd_opt(2023, 8, 1).unwrap(),
                     NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
@@ -78,6 +241,7 @@ pub mod bench {
                 po_ref: Some("PoRef2".to_string()),
                 origin: Some("EDI".to_string()),
                 completion: Some(20),
+                csv_completion: Some(20),
                 date: NaiveDateTime::new(
                     NaiveDate::from_ymd_opt(2023, 8, 2).unwrap(),
                     NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
@@ -92,6 +256,7 @@ pub mod bench {
                 po_ref: None,
                 origin: None,
                 completion: None,
+                csv_completion: None,
                 date: NaiveDateTime::new(
                     NaiveDate::from_ymd_opt(2023, 8, 3).unwrap(),
                     NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
@@ -125,10 +290,20 @@ pub mod tests {
 
     pub fn read_orders(connection: &mut DbConnection) -> Vec<OrderModel> {
         schema::target::order::dsl::order
-            .load::<OrderModel>(connection)
+            .select(OrderModel::as_select())
+            .load(connection)
             .expect("Error loading updated OrderModel")
     }
 
+    pub fn read_flagged_order_ids(connection: &mut DbConnection) -> Vec<u32> {
+        use self::schema::target::order::dsl::*;
+        order
+            .filter(is_flagged.eq(true))
+            .select(id_order)
+            .load(connection)
+            .expect("Error loading flagged orders")
+    }
+
     use super::*;
 
     #[test]
@@ -144,7 +319,8 @@ pub mod tests {
 
         let result = schema::target::order::dsl::order
             .filter(schema::target::order::id_order.eq(1))
-            .load::<OrderModel>(&mut connection)
+            .select(OrderModel::as_select())
+            .load(&mut connection)
             .expect("Error loading inserted OrderModel");
 
         assert_eq!(result.len(), 1);
@@ -163,7 +339,8 @@ pub mod tests {
 
         let mut fetched_orders = schema::target::order::dsl::order
             .filter(schema::target::order::id_order.eq(1))
-            .load::<OrderModel>(&mut connection)
+            .select(OrderModel::as_select())
+            .load(&mut connection)
             .expect("Error loading inserted OrderModel");
 
         fetched_orders[0].order_ref = "Updated Ref1".to_string();
@@ -175,7 +352,8 @@ pub mod tests {
         // Verify the order has been updated
         let retrieved_order = schema::target::order::dsl::order
             .filter(schema::target::order::id_order.eq(1))
-            .load::<OrderModel>(&mut connection)
+            .select(OrderModel::as_select())
+            .load(&mut connection)
             .expect("Error loading inserted OrderModel");
 
         assert_eq!(retrieved_order.len(), 1);
@@ -238,7 +416,8 @@ pub mod tests {
 
         let result = schema::target::order::dsl::order
             .filter(schema::target::order::id_order.eq(1))
-            .load::<OrderModel>(&mut connection)
+            .select(OrderModel::as_select())
+            .load(&mut connection)
             .expect("Error loading inserted OrderModel");
 
         assert_eq!(result.len(), 1);
@@ -259,10 +438,35 @@ pub mod tests {
 
         let result = schema::target::order::dsl::order
             .filter(schema::target::order::id_order.eq(1))
-            .load::<OrderModel>(&mut connection)
+            .select(OrderModel::as_select())
+            .load(&mut connection)
             .expect("Error loading inserted OrderModel");
 
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].id_order, 1);
     }
+
+    #[test]
+    #[serial]
+    fn test_update_completions() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        for order_model in &order_model_fixtures() {
+            insert_order(&mut connection, false, order_model).expect("Failed to insert order");
+        }
+
+        let updated = OrderModel::update_completions(&mut connection, &[(1, 50), (3, 50)])
+            .expect("Failed to update completions");
+
+        assert_eq!(updated, 2);
+        assert_eq!(
+            OrderModel::select_completions(&mut connection, &[1, 2, 3]).unwrap(),
+            vec![
+                (1, Some(50), Some(30)),
+                (2, Some(20), Some(20)),
+                (3, Some(50), None)
+            ]
+        );
+    }
 }
@@ -3,8 +3,12 @@ use crate::infrastructure::database::schema;
 use chrono::NaiveDate;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
+use rust_decimal::Decimal;
 
-use super::{CanUpsertModel, Model};
+use super::{CanHashModel, CanUpsertModel, Model};
+
+// Keep IN clauses to a reasonable size
+const SELECT_CHUNK_SIZE: usize = 1000;
 
 #[derive(
     Selectable,
@@ -33,12 +37,34 @@ pub struct OrderLineModel {
     pub id_order_line: u32,
     pub id_order: u32,
     pub product_ref: String,
-    pub qty_ordered: u32,
-    pub qty_reserved: u32,
-    pub qty_delivered: u32,
+    pub qty_ordered: Decimal,
+    pub qty_reserved: Decimal,
+    pub qty_delivered: Decimal,
+    pub uom: Option<String>,
     pub due_date: Option<NaiveDate>,
 }
 
+impl OrderLineModel {
+    /// Ordered and delivered quantities of the lines of the given orders, by order
+    pub fn select_quantities(
+        connection: &mut DbConnection,
+        order_ids: &[u32],
+    ) -> Result<Vec<(u32, Decimal, Decimal)>, DieselError> {
+        use self::schema::target::order_line::dsl::*;
+        let mut quantities = Vec::new();
+        for chunk in order_ids.chunks(SELECT_CHUNK_SIZE) {
+            quantities.extend(
+                order_line
+                    .filter(id_order.eq_any(chunk))
+                    .select((id_order, qty_ordered, qty_delivered))
+                    .order((id_order, id_order_line))
+                    .load::<(u32, Decimal, Decimal)>(connection)?,
+            );
+        }
+        Ok(quantities)
+    }
+}
+
 impl Model for OrderLineModel {}
 impl CanUpsertModel for OrderLineModel {
     fn upsert(&self, connection: &mut DbConnection) -> Result<(), DieselError> {
@@ -55,6 +81,33 @@ impl CanUpsertModel for (OrderLineModel, Vec<OrderLineLangModel>) {
     }
 }
 
+impl CanHashModel for (OrderLineModel, Vec<OrderLineLangModel>) {
+    const ENTITY: &'static str = "order_line";
+
+    fn row_key(&self) -> String {
+        self.0.id_order_line.to_string()
+    }
+
+    fn column_values(&self) -> Vec<Option<String>> {
+        let (order_line, order_line_langs) = self;
+        let mut values = vec![
+            Some(order_line.id_order_line.to_string()),
+            Some(order_line.id_order.to_string()),
+            Some(order_line.product_ref.clone()),
+            Some(order_line.qty_ordered.to_string()),
+            Some(order_line.qty_reserved.to_string()),
+            Some(order_line.qty_delivered.to_string()),
+            order_line.uom.clone(),
+            order_line.due_date.map(|due_date| due_date.to_string()),
+        ];
+        for order_line_lang in order_line_langs {
+            values.push(Some(order_line_lang.id_lang.to_string()));
+            values.push(Some(order_line_lang.product_name.clone()));
+        }
+        values
+    }
+}
+
 pub fn batch_upsert(
     models: &[(OrderLineModel, Vec<OrderLineLangModel>)],
     connection: &mut DbConnection,
@@ -91,27 +144,30 @@ pub mod tests {
                 id_order_line: 1,
                 id_order: 1,
                 product_ref: "ItemRef1".to_string(),
-                qty_ordered: 10,
-                qty_reserved: 5,
-                qty_delivered: 5,
+                qty_ordered: Decimal::from(10),
+                qty_reserved: Decimal::from(5),
+                qty_delivered: Decimal::from(5),
+                uom: Some("PCE".to_string()),
                 due_date: Some(NaiveDate::from_ymd_opt(2023, 8, 1).unwrap()),
             },
             OrderLineModel {
                 id_order_line: 2,
                 id_order: 1,
                 product_ref: "ItemRef2".to_string(),
-                qty_ordered: 20,
-                qty_reserved: 10,
-                qty_delivered: 10,
+                qty_ordered: Decimal::from(20),
+                qty_reserved: Decimal::from(10),
+                qty_delivered: Decimal::from(10),
+                uom: Some("PCE".to_string()),
                 due_date: Some(NaiveDate::from_ymd_opt(2023, 8, 2).unwrap()),
             },
             OrderLineModel {
                 id_order_line: 3,
                 id_order: 2,
                 product_ref: "ItemRef3".to_string(),
-                qty_ordered: 30,
-                qty_reserved: 15,
-                qty_delivered: 15,
+                qty_ordered: Decimal::from(30),
+                qty_reserved: Decimal::from(15),
+                qty_delivered: Decimal::from(15),
+                uom: None,
                 due_date: None,
             },
         ]
@@ -212,7 +268,7 @@ pub mod tests {
         insert_order_line(&mut connection, false, &order_line_models[0])
             .expect("Failed to insert order");
 
-        order_line_models[0].qty_delivered = 10;
+        order_line_models[0].qty_delivered = Decimal::new(75, 1);
 
         insert_order_line(&mut connection, true, &order_line_models[0])
             .expect("Failed to upsert order");
@@ -226,7 +282,7 @@ pub mod tests {
         assert_eq!(
             result[0],
             OrderLineModel {
-                qty_delivered: 10,
+                qty_delivered: Decimal::new(75, 1),
                 ..order_line_model_fixtures()[0].clone()
             }
         );
@@ -0,0 +1,376 @@
+use crate::infrastructure::database::connection::DbConnection;
+use crate::infrastructure::database::schema;
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use rust_decimal::Decimal;
+
+use super::{CanHashModel, CanUpsertModel, Model};
+
+// Keep IN clauses to a reasonable size
+const SELECT_CHUNK_SIZE: usize = 1000;
+const DELETE_CHUNK_SIZE: usize = 1000;
+
+#[derive(Queryable, Identifiable, Insertable, AsChangeset, PartialEq, Debug, Clone)]
+#[diesel(table_name = schema::target::payment)]
+#[diesel(primary_key(id_payment))]
+pub struct PaymentModel {
+    pub id_payment: u32,
+    pub payment_ref: String,
+    pub date: NaiveDate,
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+#[derive(
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    Associations,
+    AsChangeset,
+    PartialEq,
+    Debug,
+    Clone,
+)]
+#[diesel(table_name = schema::target::payment_allocation)]
+#[diesel(belongs_to(PaymentModel, foreign_key = id_payment))]
+#[diesel(primary_key(id_allocation_line))]
+pub struct PaymentAllocationModel {
+    pub id_allocation_line: u32,
+    pub id_payment: u32,
+    pub id_invoice: u32,
+    pub amount: Decimal,
+    pub discount: Decimal,
+    pub write_off: Decimal,
+}
+
+impl Model for (PaymentModel, Vec<PaymentAllocationModel>) {}
+impl CanUpsertModel for (PaymentModel, Vec<PaymentAllocationModel>) {
+    fn upsert(&self, connection: &mut DbConnection) -> Result<(), DieselError> {
+        connection.transaction(|connection| {
+            super::upsert!(schema::target::payment::table, &self.0, connection)?;
+            delete_allocations(connection, &[self.0.id_payment])?;
+            super::upsert!(
+                schema::target::payment_allocation::table,
+                &self.1,
+                connection
+            )
+        })
+    }
+}
+
+impl CanHashModel for (PaymentModel, Vec<PaymentAllocationModel>) {
+    const ENTITY: &'static str = "payment";
+
+    fn row_key(&self) -> String {
+        self.0.id_payment.to_string()
+    }
+
+    fn column_values(&self) -> Vec<Option<String>> {
+        let (payment, allocations) = self;
+        let mut values = vec![
+            Some(payment.id_payment.to_string()),
+            Some(payment.payment_ref.clone()),
+            Some(payment.date.to_string()),
+            Some(payment.amount.to_string()),
+            Some(payment.currency.clone()),
+        ];
+        for allocation in allocations {
+            values.push(Some(allocation.id_allocation_line.to_string()));
+            values.push(Some(allocation.id_invoice.to_string()));
+            values.push(Some(allocation.amount.to_string()));
+            values.push(Some(allocation.discount.to_string()));
+            values.push(Some(allocation.write_off.to_string()));
+        }
+        values
+    }
+}
+
+impl PaymentAllocationModel {
+    /// Amounts settled on the given invoices, discounts and write-offs included
+    pub fn select_settled_by_invoice(
+        connection: &mut DbConnection,
+        invoice_ids: &[u32],
+    ) -> Result<Vec<(u32, Decimal)>, DieselError> {
+        use self::schema::target::payment_allocation::dsl::*;
+        let mut allocations: Vec<PaymentAllocationModel> = Vec::new();
+        for chunk in invoice_ids.chunks(SELECT_CHUNK_SIZE) {
+            allocations.extend(
+                payment_allocation
+                    .filter(id_invoice.eq_any(chunk))
+                    .order(id_allocation_line)
+                    .select(PaymentAllocationModel::as_select())
+                    .load::<PaymentAllocationModel>(connection)?,
+            );
+        }
+        Ok(allocations
+            .into_iter()
+            .map(|allocation| {
+                (
+                    allocation.id_invoice,
+                    allocation.amount + allocation.discount + allocation.write_off,
+                )
+            })
+            .collect())
+    }
+
+    /// Invoices the stored allocations of the given payments point to
+    pub fn select_invoice_ids(
+        connection: &mut DbConnection,
+        payment_ids: &[u32],
+    ) -> Result<Vec<u32>, DieselError> {
+        use self::schema::target::payment_allocation::dsl::*;
+        let mut invoice_ids = Vec::new();
+        for chunk in payment_ids.chunks(SELECT_CHUNK_SIZE) {
+            invoice_ids.extend(
+                payment_allocation
+                    .filter(id_payment.eq_any(chunk))
+                    .select(id_invoice)
+                    .distinct()
+                    .load::<u32>(connection)?,
+            );
+        }
+        Ok(invoice_ids)
+    }
+}
+
+// Allocations missing from a payment exported again were reversed or moved in the ERP
+fn delete_allocations(
+    connection: &mut DbConnection,
+    payment_ids: &[u32],
+) -> Result<(), DieselError> {
+    use self::schema::target::payment_allocation::dsl::*;
+    for chunk in payment_ids.chunks(DELETE_CHUNK_SIZE) {
+        diesel::delete(payment_allocation.filter(id_payment.eq_any(chunk))).execute(connection)?;
+    }
+    Ok(())
+}
+
+pub fn batch_upsert(
+    models: &[(PaymentModel, Vec<PaymentAllocationModel>)],
+    connection: &mut DbConnection,
+) -> Result<(), DieselError> {
+    let payments: Vec<&PaymentModel> = models.iter().map(|tuple| &tuple.0).collect();
+    let allocations: Vec<&PaymentAllocationModel> =
+        models.iter().flat_map(|tuple| tuple.1.iter()).collect();
+    let payment_ids: Vec<u32> = payments.iter().map(|payment| payment.id_payment).collect();
+    connection.transaction(|connection| {
+        super::upsert!(schema::target::payment::table, payments, connection)?;
+        delete_allocations(connection, &payment_ids)?;
+        super::upsert!(
+            schema::target::payment_allocation::table,
+            allocations,
+            connection
+        )
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use serial_test::serial;
+
+    use crate::infrastructure::database::{
+        connection::tests::{get_test_pooled_connection, reset_test_database},
+        models::{
+            invoice::{InvoiceLangModel, InvoiceModel},
+            invoice_line::{self, tests::invoice_line_model_fixtures, InvoiceLineModel},
+            row_hash::{self, RowHashModel},
+            CanDeleteByIdsModel,
+        },
+    };
+
+    use super::*;
+    pub fn payment_model_fixtures() -> [PaymentModel; 3] {
+        [
+            PaymentModel {
+                id_payment: 1,
+                payment_ref: "PAY-1".to_string(),
+                date: NaiveDate::from_ymd_opt(2020, 2, 1).unwrap(),
+                amount: Decimal::new(10000, 2),
+                currency: "EUR".to_string(),
+            },
+            PaymentModel {
+                id_payment: 2,
+                payment_ref: "PAY-2".to_string(),
+                date: NaiveDate::from_ymd_opt(2020, 2, 2).unwrap(),
+                amount: Decimal::new(1950, 2),
+                currency: "EUR".to_string(),
+            },
+            PaymentModel {
+                id_payment: 3,
+                payment_ref: "PAY-3".to_string(),
+                date: NaiveDate::from_ymd_opt(2020, 2, 3).unwrap(),
+                amount: Decimal::new(-20000, 2),
+                currency: "EUR".to_string(),
+            },
+        ]
+    }
+
+    pub fn payment_allocation_model_fixtures() -> [PaymentAllocationModel; 3] {
+        [
+            PaymentAllocationModel {
+                id_allocation_line: 1,
+                id_payment: 1,
+                id_invoice: 1,
+                amount: Decimal::new(10000, 2),
+                discount: Decimal::new(0, 2),
+                write_off: Decimal::new(0, 2),
+            },
+            PaymentAllocationModel {
+                id_allocation_line: 2,
+                id_payment: 2,
+                id_invoice: 1,
+                amount: Decimal::new(1950, 2),
+                discount: Decimal::new(50, 2),
+                write_off: Decimal::new(0, 2),
+            },
+            PaymentAllocationModel {
+                id_allocation_line: 3,
+                id_payment: 3,
+                id_invoice: 3,
+                amount: Decimal::new(-20000, 2),
+                discount: Decimal::new(0, 2),
+                write_off: Decimal::new(0, 2),
+            },
+        ]
+    }
+
+    pub fn batch_tuple_fixtures() -> Vec<(PaymentModel, Vec<PaymentAllocationModel>)> {
+        payment_model_fixtures()
+            .into_iter()
+            .zip(payment_allocation_model_fixtures())
+            .map(|(payment, allocation)| (payment, vec![allocation]))
+            .collect()
+    }
+
+    pub fn read_payments(connection: &mut DbConnection) -> Vec<PaymentModel> {
+        schema::target::payment::dsl::payment
+            .order(schema::target::payment::id_payment)
+            .load::<PaymentModel>(connection)
+            .expect("Error loading PaymentModel")
+    }
+
+    pub fn read_payment_allocations(
+        connection: &mut DbConnection,
+        payment: &PaymentModel,
+    ) -> Vec<PaymentAllocationModel> {
+        PaymentAllocationModel::belonging_to(payment)
+            .select(PaymentAllocationModel::as_select())
+            .load(connection)
+            .expect("Error loading PaymentAllocationModel")
+    }
+
+    #[test]
+    #[serial]
+    fn test_upsert_payment() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        let mut payment = batch_tuple_fixtures()[1].clone();
+        payment.upsert(&mut connection).unwrap();
+        payment.1[0].discount = Decimal::new(0, 2);
+        payment.upsert(&mut connection).unwrap();
+
+        assert_eq!(read_payments(&mut connection), vec![payment.0.clone()]);
+        assert_eq!(
+            read_payment_allocations(&mut connection, &payment.0),
+            payment.1
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_upsert_payment_with_fewer_allocations() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        let mut payment = batch_tuple_fixtures()[1].clone();
+        let mut moved_allocation = payment.1[0].clone();
+        moved_allocation.id_allocation_line = 4;
+        moved_allocation.id_invoice = 3;
+        payment.1.push(moved_allocation);
+        payment.upsert(&mut connection).unwrap();
+        payment.1.pop();
+        payment.upsert(&mut connection).unwrap();
+
+        assert_eq!(
+            read_payment_allocations(&mut connection, &payment.0),
+            payment.1
+        );
+
+        payment.1.clear();
+        batch_upsert(&[payment.clone()], &mut connection).unwrap();
+
+        assert!(read_payment_allocations(&mut connection, &payment.0).is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn test_select_settled_by_invoice() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        batch_upsert(&batch_tuple_fixtures(), &mut connection).unwrap();
+
+        assert_eq!(
+            PaymentAllocationModel::select_settled_by_invoice(&mut connection, &[1, 3]).unwrap(),
+            vec![
+                (1, Decimal::new(10000, 2)),
+                (1, Decimal::new(2000, 2)),
+                (3, Decimal::new(-20000, 2)),
+            ]
+        );
+        let mut invoice_ids =
+            PaymentAllocationModel::select_invoice_ids(&mut connection, &[2, 3]).unwrap();
+        invoice_ids.sort();
+        assert_eq!(invoice_ids, vec![1, 3]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_invoice_delete_removes_its_allocations() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        batch_upsert(&batch_tuple_fixtures(), &mut connection).unwrap();
+        <(InvoiceModel, Vec<InvoiceLangModel>)>::delete_by_ids(&mut connection, &[1]).unwrap();
+
+        assert_eq!(
+            PaymentAllocationModel::select_settled_by_invoice(&mut connection, &[1, 3]).unwrap(),
+            vec![(3, Decimal::new(-20000, 2))]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_invoice_delete_removes_row_hashes() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        let payments = batch_tuple_fixtures();
+        let invoice_lines = invoice_line_model_fixtures();
+        batch_upsert(&payments, &mut connection).unwrap();
+        invoice_line::batch_upsert(&invoice_lines, &mut connection).unwrap();
+        let row_hashes: Vec<RowHashModel> = payments
+            .iter()
+            .map(RowHashModel::from_model)
+            .chain(invoice_lines.iter().map(RowHashModel::from_model))
+            .collect();
+        row_hash::batch_upsert(&row_hashes, &mut connection).unwrap();
+
+        <(InvoiceModel, Vec<InvoiceLangModel>)>::delete_by_ids(&mut connection, &[1]).unwrap();
+
+        // Lines and payments of invoice 1 are written again when exported again
+        for entity in [
+            <(PaymentModel, Vec<PaymentAllocationModel>)>::ENTITY,
+            InvoiceLineModel::ENTITY,
+        ] {
+            let keys: Vec<String> = RowHashModel::select_by_entity(&mut connection, entity)
+                .unwrap()
+                .into_keys()
+                .collect();
+            assert_eq!(keys, vec!["3".to_string()]);
+        }
+    }
+}
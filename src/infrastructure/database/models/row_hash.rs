@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::infrastructure::database::connection::DbConnection;
+use crate::infrastructure::database::schema;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use super::{CanHashModel, Model};
+
+// Keep the generated statement far below MySQL placeholders limit
+const UPSERT_CHUNK_SIZE: usize = 1000;
+
+#[derive(Queryable, Selectable, Insertable, PartialEq, Debug, Clone)]
+#[diesel(table_name = schema::target::fluetl_row_hash)]
+pub struct RowHashModel {
+    pub entity: String,
+    pub row_key: String,
+    pub row_hash: String,
+}
+
+impl Model for RowHashModel {}
+
+impl RowHashModel {
+    pub fn from_model<M: CanHashModel>(model: &M) -> Self {
+        Self {
+            entity: M::ENTITY.to_string(),
+            row_key: model.row_key(),
+            row_hash: model.row_hash(),
+        }
+    }
+
+    /// Row hashes stored by the previous runs, indexed by row key
+    pub fn select_by_entity(
+        connection: &mut DbConnection,
+        entity_name: &str,
+    ) -> Result<HashMap<String, String>, DieselError> {
+        use self::schema::target::fluetl_row_hash::dsl::*;
+        fluetl_row_hash
+            .filter(entity.eq(entity_name))
+            .select(RowHashModel::as_select())
+            .load(connection)
+            .map(|models| {
+                models
+                    .into_iter()
+                    .map(|model| (model.row_key, model.row_hash))
+                    .collect()
+            })
+    }
+
+    pub fn delete_by_keys(
+        connection: &mut DbConnection,
+        entity_name: &str,
+        keys: &[String],
+    ) -> Result<usize, DieselError> {
+        use self::schema::target::fluetl_row_hash::dsl::*;
+        diesel::delete(
+            fluetl_row_hash
+                .filter(entity.eq(entity_name))
+                .filter(row_key.eq_any(keys)),
+        )
+        .execute(connection)
+    }
+}
+
+pub fn batch_upsert(
+    models: &[RowHashModel],
+    connection: &mut DbConnection,
+) -> Result<(), DieselError> {
+    connection.transaction(|connection| {
+        for chunk in models.chunks(UPSERT_CHUNK_SIZE) {
+            super::upsert!(schema::target::fluetl_row_hash::table, chunk, connection)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::infrastructure::database::{
+        connection::tests::{get_test_pooled_connection, reset_test_database},
+        models::order::bench::order_model_fixtures,
+    };
+
+    #[test]
+    fn test_row_hash_follows_content() {
+        let mut order = order_model_fixtures()[0].clone();
+        let row_hash = RowHashModel::from_model(&order);
+
+        assert_eq!(row_hash.entity, "order");
+        assert_eq!(row_hash.row_key, "1");
+        assert_eq!(row_hash, RowHashModel::from_model(&order.clone()));
+
+        order.completion = Some(100);
+        assert_ne!(row_hash.row_hash, RowHashModel::from_model(&order).row_hash);
+    }
+
+    #[test]
+    fn test_row_hash_separates_column_values() {
+        let mut order = order_model_fixtures()[0].clone();
+        order.po_ref = None;
+        let without_po_ref = order.row_hash();
+        order.po_ref = Some(String::new());
+        assert_ne!(without_po_ref, order.row_hash());
+
+        order.po_ref = Some("ab".to_string());
+        order.origin = Some("c".to_string());
+        let row_hash = order.row_hash();
+        order.po_ref = Some("a".to_string());
+        order.origin = Some("bc".to_string());
+        assert_ne!(row_hash, order.row_hash());
+    }
+
+    #[test]
+    #[serial]
+    fn test_upsert_and_select_by_entity() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+        let mut row_hashes: Vec<RowHashModel> = order_model_fixtures()
+            .iter()
+            .map(RowHashModel::from_model)
+            .collect();
+
+        batch_upsert(&row_hashes, &mut connection).expect("Failed to upsert row hashes");
+        row_hashes[0].row_hash = "changed".to_string();
+        batch_upsert(&row_hashes[..1], &mut connection).expect("Failed to upsert row hashes");
+
+        let stored = RowHashModel::select_by_entity(&mut connection, "order")
+            .expect("Failed to select row hashes");
+        assert_eq!(stored.len(), 3);
+        assert_eq!(stored.get("1"), Some(&"changed".to_string()));
+        assert_eq!(stored.get("2"), Some(&row_hashes[1].row_hash));
+        assert!(RowHashModel::select_by_entity(&mut connection, "invoice")
+            .unwrap()
+            .is_empty());
+    }
+}
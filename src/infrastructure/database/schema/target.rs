@@ -17,6 +17,18 @@ diesel::table! {
         tracking_number -> Nullable<Varchar>,
         #[max_length = 255]
         tracking_link -> Nullable<Varchar>,
+        is_flagged -> Bool,
+    }
+}
+
+diesel::table! {
+    fluetl_row_hash (entity, row_key) {
+        #[max_length = 64]
+        entity -> Varchar,
+        #[max_length = 255]
+        row_key -> Varchar,
+        #[max_length = 64]
+        row_hash -> Char,
     }
 }
 
@@ -35,6 +47,32 @@ diesel::table! {
         po_ref -> Nullable<Varchar>,
         total_tax_excl -> Decimal,
         total_tax_incl -> Decimal,
+        #[max_length = 3]
+        currency -> Varchar,
+        #[max_length = 16]
+        document_type -> Varchar,
+    }
+}
+
+diesel::table! {
+    invoice_line (id_invoice_line) {
+        id_invoice_line -> Unsigned<Integer>,
+        id_invoice -> Unsigned<Integer>,
+        #[max_length = 64]
+        product_ref -> Varchar,
+        quantity -> Decimal,
+        unit_price -> Decimal,
+        tax_rate -> Decimal,
+        total_tax_excl -> Decimal,
+        total_tax_incl -> Decimal,
+    }
+}
+
+diesel::table! {
+    invoice_open_amount (id_invoice) {
+        id_invoice -> Unsigned<Integer>,
+        open_amount -> Decimal,
+        is_paid -> Bool,
     }
 }
 
@@ -68,8 +106,10 @@ diesel::table! {
         #[max_length = 255]
         origin -> Nullable<Varchar>,
         completion -> Nullable<Unsigned<Integer>>,
+        csv_completion -> Nullable<Unsigned<Integer>>,
         #[max_length = 2]
         order_status -> Nullable<Varchar>,
+        is_flagged -> Bool,
     }
 }
 
@@ -79,9 +119,11 @@ diesel::table! {
         id_order -> Unsigned<Integer>,
         #[max_length = 64]
         product_ref -> Varchar,
-        qty_ordered -> Unsigned<Integer>,
-        qty_reserved -> Unsigned<Integer>,
-        qty_delivered -> Unsigned<Integer>,
+        qty_ordered -> Decimal,
+        qty_reserved -> Decimal,
+        qty_delivered -> Decimal,
+        #[max_length = 16]
+        uom -> Nullable<Varchar>,
         due_date -> Nullable<Date>,
     }
 }
@@ -95,6 +137,29 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    payment (id_payment) {
+        id_payment -> Unsigned<Integer>,
+        #[max_length = 32]
+        payment_ref -> Varchar,
+        date -> Date,
+        amount -> Decimal,
+        #[max_length = 3]
+        currency -> Varchar,
+    }
+}
+
+diesel::table! {
+    payment_allocation (id_allocation_line) {
+        id_allocation_line -> Unsigned<Integer>,
+        id_payment -> Unsigned<Integer>,
+        id_invoice -> Unsigned<Integer>,
+        amount -> Decimal,
+        discount -> Decimal,
+        write_off -> Decimal,
+    }
+}
+
 diesel::table! {
     product_substitute (id_product, id_substitute) {
         id_product -> Unsigned<Integer>,
@@ -105,14 +170,20 @@ diesel::table! {
 diesel::joinable!(invoice_lang -> invoice (id_invoice));
 diesel::joinable!(order_line -> order (id_order));
 diesel::joinable!(order_line_lang -> order_line (id_order_line));
+diesel::joinable!(payment_allocation -> payment (id_payment));
 
 diesel::allow_tables_to_appear_in_same_query!(
     delivery_slip,
+    fluetl_row_hash,
     invoice,
     invoice_lang,
+    invoice_line,
+    invoice_open_amount,
     mapping_client_contact,
     order,
     order_line,
     order_line_lang,
+    payment,
+    payment_allocation,
     product_substitute,
 );
@@ -0,0 +1,220 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::infrastructure::InfrastructureError;
+
+/// ssl_mode of the MySQL client
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SslMode {
+    Disabled,
+    Preferred,
+    Required,
+    VerifyCa,
+    VerifyIdentity,
+}
+
+impl FromStr for SslMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "disabled" => Ok(SslMode::Disabled),
+            "preferred" => Ok(SslMode::Preferred),
+            "required" => Ok(SslMode::Required),
+            "verify_ca" => Ok(SslMode::VerifyCa),
+            "verify_identity" => Ok(SslMode::VerifyIdentity),
+            _ => Err(format!(
+                "Invalid ssl mode {}, expected disabled, preferred, required, verify_ca or verify_identity",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SslMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = match self {
+            SslMode::Disabled => "disabled",
+            SslMode::Preferred => "preferred",
+            SslMode::Required => "required",
+            SslMode::VerifyCa => "verify_ca",
+            SslMode::VerifyIdentity => "verify_identity",
+        };
+        write!(f, "{}", mode)
+    }
+}
+
+/// TLS settings of a database, from databases.target_tls or databases.legacy_staging_tls
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TlsOptions {
+    pub mode: Option<SslMode>,
+    pub ca: Option<PathBuf>,
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+impl TlsOptions {
+    pub fn from_values(
+        mode: Option<&str>,
+        ca: Option<&str>,
+        cert: Option<&str>,
+        key: Option<&str>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            mode: mode.map(str::parse).transpose()?,
+            ca: ca.map(PathBuf::from),
+            cert: cert.map(PathBuf::from),
+            key: key.map(PathBuf::from),
+        })
+    }
+
+    /// Missing or unreadable certificates and inconsistent settings
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if matches!(self.mode, Some(SslMode::VerifyCa | SslMode::VerifyIdentity))
+            && self.ca.is_none()
+        {
+            problems.push(format!(
+                "ssl mode {} needs a CA certificate",
+                self.mode.unwrap()
+            ));
+        }
+        if self.cert.is_some() != self.key.is_some() {
+            problems.push("client certificate and key must be given together".to_string());
+        }
+        for (name, path, marker) in [
+            ("CA certificate", &self.ca, "CERTIFICATE-----"),
+            ("client certificate", &self.cert, "CERTIFICATE-----"),
+            ("client key", &self.key, "PRIVATE KEY-----"),
+        ] {
+            let Some(path) = path else {
+                continue;
+            };
+            match fs::read_to_string(path) {
+                Ok(content) if content.contains("-----BEGIN") && content.contains(marker) => {}
+                Ok(_) => problems.push(format!("{} {} is not a PEM file", name, path.display())),
+                Err(e) => problems.push(format!(
+                    "{} {} is not readable: {}",
+                    name,
+                    path.display(),
+                    e
+                )),
+            }
+        }
+        problems
+    }
+
+    /// Adds the settings as the query parameters read by diesel, unless the URL has them already
+    pub fn apply(&self, database_url: &str) -> Result<String, InfrastructureError> {
+        let parameters: Vec<(&str, String)> = [
+            ("ssl_mode", self.mode.map(|mode| mode.to_string())),
+            ("ssl_ca", path_value(&self.ca)),
+            ("ssl_cert", path_value(&self.cert)),
+            ("ssl_key", path_value(&self.key)),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect();
+        if parameters.is_empty() {
+            return Ok(database_url.to_string());
+        }
+
+        let mut url = url::Url::parse(database_url)
+            .map_err(|_| InfrastructureError::ConfigError("Invalid database URL".to_string()))?;
+        let existing: Vec<String> = url
+            .query_pairs()
+            .map(|(name, _)| name.into_owned())
+            .collect();
+        for (name, value) in parameters {
+            if !existing.iter().any(|existing| existing == name) {
+                url.query_pairs_mut().append_pair(name, &value);
+            }
+        }
+        Ok(url.to_string())
+    }
+}
+
+fn path_value(path: &Option<PathBuf>) -> Option<String> {
+    path.as_ref().map(|path| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n";
+
+    #[test]
+    fn test_ssl_mode_from_str() {
+        assert_eq!("VERIFY_CA".parse::<SslMode>(), Ok(SslMode::VerifyCa));
+        assert_eq!(
+            "verify_identity".parse::<SslMode>(),
+            Ok(SslMode::VerifyIdentity)
+        );
+        assert!("strict".parse::<SslMode>().is_err());
+    }
+
+    #[test]
+    fn test_problems() {
+        let directory = tempfile::tempdir().unwrap();
+        let ca = directory.path().join("ca.pem");
+        fs::write(&ca, CERTIFICATE).unwrap();
+        let not_pem = directory.path().join("ca.der");
+        fs::write(&not_pem, [0x30, 0x82]).unwrap();
+        let ca = ca.to_str().unwrap();
+
+        assert!(
+            TlsOptions::from_values(Some("verify_ca"), Some(ca), None, None)
+                .unwrap()
+                .problems()
+                .is_empty()
+        );
+        assert_eq!(
+            TlsOptions::from_values(Some("verify_identity"), None, Some(ca), None)
+                .unwrap()
+                .problems()
+                .len(),
+            2
+        );
+        assert_eq!(
+            TlsOptions::from_values(None, not_pem.to_str(), None, None)
+                .unwrap()
+                .problems(),
+            vec![format!(
+                "CA certificate {} is not a PEM file",
+                not_pem.display()
+            )]
+        );
+        assert_eq!(
+            TlsOptions::from_values(None, Some("/missing/ca.pem"), None, None)
+                .unwrap()
+                .problems()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let options =
+            TlsOptions::from_values(Some("verify_ca"), Some("/etc/ssl/ca.pem"), None, None)
+                .unwrap();
+
+        assert_eq!(
+            options.apply("mysql://user:pass@db:3306/shop").unwrap(),
+            "mysql://user:pass@db:3306/shop?ssl_mode=verify_ca&ssl_ca=%2Fetc%2Fssl%2Fca.pem"
+        );
+        assert_eq!(
+            options
+                .apply("mysql://user:pass@db:3306/shop?ssl_mode=required")
+                .unwrap(),
+            "mysql://user:pass@db:3306/shop?ssl_mode=required&ssl_ca=%2Fetc%2Fssl%2Fca.pem"
+        );
+        assert_eq!(
+            TlsOptions::default().apply("mysql://db/shop").unwrap(),
+            "mysql://db/shop"
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use super::{config::FingerprintsConfig, csv_reader::CsvType, InfrastructureError};
+
+/// Hex encoded SHA-256 of a source file content
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fingerprint(String);
+
+impl Fingerprint {
+    pub fn from_file(path: &Path) -> Result<Self, io::Error> {
+        let mut file = File::open(path)?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher)?;
+        Ok(Self(format!("{:x}", hasher.finalize())))
+    }
+
+    /// A single file keeps its own fingerprint, several files are fingerprinted in order
+    pub fn from_files(paths: &[PathBuf]) -> Result<Self, io::Error> {
+        if let [path] = paths {
+            return Self::from_file(path);
+        }
+        let mut hasher = Sha256::new();
+        for path in paths {
+            hasher.update(Self::from_file(path)?.as_str());
+            hasher.update(b"\n");
+        }
+        Ok(Self(format!("{:x}", hasher.finalize())))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Keeps the fingerprint of the last successful import of each CsvType,
+/// one file per CsvType in FINGERPRINT_DIRECTORY
+pub(crate) struct FingerprintStore {
+    directory: PathBuf,
+}
+
+impl FingerprintStore {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// Fingerprinting is disabled when fingerprints.directory is not set
+    pub fn from_config(config: &FingerprintsConfig) -> Option<Self> {
+        config
+            .directory
+            .as_ref()
+            .map(|directory| Self::new(PathBuf::from(directory)))
+    }
+
+    pub fn load(&self, csv_type: &CsvType) -> Option<Fingerprint> {
+        fs::read_to_string(self.path(csv_type))
+            .ok()
+            .map(|content| Fingerprint(content.trim().to_string()))
+    }
+
+    pub fn save(
+        &self,
+        csv_type: &CsvType,
+        fingerprint: &Fingerprint,
+    ) -> Result<(), InfrastructureError> {
+        fs::create_dir_all(&self.directory).map_err(InfrastructureError::IOError)?;
+        fs::write(self.path(csv_type), fingerprint.as_str()).map_err(InfrastructureError::IOError)
+    }
+
+    fn path(&self, csv_type: &CsvType) -> PathBuf {
+        self.directory.join(format!("{}.sha256", csv_type.name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::{tempdir, NamedTempFile};
+
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_from_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"c_order_id;c_bpartner_id\n1;1\n").unwrap();
+
+        let fingerprint = Fingerprint::from_file(file.path()).unwrap();
+
+        assert_eq!(
+            fingerprint.as_str(),
+            "602363a9510d8ebb5bf6a3af0b535227e43f49498f668b2d43a7d461f14e229a"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_content() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"1;1\n").unwrap();
+        let before = Fingerprint::from_file(file.path()).unwrap();
+
+        file.write_all(b"2;2\n").unwrap();
+        let after = Fingerprint::from_file(file.path()).unwrap();
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_fingerprint_from_files() {
+        let mut first = NamedTempFile::new().unwrap();
+        first.write_all(b"1;1\n").unwrap();
+        let mut second = NamedTempFile::new().unwrap();
+        second.write_all(b"2;2\n").unwrap();
+        let first_path = first.path().to_path_buf();
+        let second_path = second.path().to_path_buf();
+
+        let single = Fingerprint::from_files(std::slice::from_ref(&first_path)).unwrap();
+        let both = Fingerprint::from_files(&[first_path.clone(), second_path.clone()]).unwrap();
+        let reversed = Fingerprint::from_files(&[second_path, first_path.clone()]).unwrap();
+
+        assert_eq!(single, Fingerprint::from_file(&first_path).unwrap());
+        assert_ne!(both, single);
+        assert_ne!(both, reversed);
+    }
+
+    #[test]
+    fn test_store_save_and_load() {
+        let directory = tempdir().unwrap();
+        let store = FingerprintStore::new(directory.path().join("fingerprints"));
+        let fingerprint = Fingerprint("abc".to_string());
+
+        assert!(store.load(&CsvType::Order).is_none());
+
+        store
+            .save(&CsvType::Order, &fingerprint)
+            .expect("Failed to save fingerprint");
+
+        assert_eq!(store.load(&CsvType::Order), Some(fingerprint));
+        assert!(store.load(&CsvType::Invoice).is_none());
+    }
+}
@@ -1,5 +1,3 @@
-use std::env;
-
 use log::LevelFilter;
 
 use log4rs::append::console::ConsoleAppender;
@@ -14,12 +12,16 @@ use log4rs::encode::pattern::PatternEncoder;
 use log4rs::config::{Appender, Root};
 use log4rs::Config;
 
-use super::InfrastructureError;
+use super::{config::LogConfig, InfrastructureError};
 
-fn make() -> Result<Config, InfrastructureError> {
-    let log_directory = env::var("LOG_DIRECTORY").map_err(InfrastructureError::EnvVarError)?;
+fn make(log: &LogConfig) -> Result<Config, InfrastructureError> {
+    let log_directory = log.directory.as_deref().ok_or_else(|| {
+        InfrastructureError::ConfigError(
+            "log.directory (LOG_DIRECTORY) is not configured".to_string(),
+        )
+    })?;
 
-    let log_level = env::var("LOG_LEVEL").unwrap_or("INFO".to_string());
+    let log_level = log.level.clone().unwrap_or("INFO".to_string());
 
     let log_line_pattern = "{d(%Y-%m-%d %H:%M:%S)} | {({l}):5.5} | {f}:{L} — {m}{n}";
 
@@ -59,8 +61,8 @@ fn make() -> Result<Config, InfrastructureError> {
     Ok(config)
 }
 
-pub fn init() {
-    let make_result = make();
+pub fn init(log: &LogConfig) {
+    let make_result = make(log);
     if make_result.is_err() {
         println!("Failed to initialize logger: {:?}", make_result);
         return;
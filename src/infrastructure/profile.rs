@@ -0,0 +1,230 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::{config::ProfileConfig, database::connection::RedactedUrl, InfrastructureError};
+
+/// Target database of a named profile, TARGET_DATABASE_URL_<NAME>
+pub fn target_url_var(name: &str) -> String {
+    format!("TARGET_DATABASE_URL_{}", env_suffix(name))
+}
+
+/// Language ids of a named profile by locale, TARGET_LANGUAGES_<NAME>, e.g. fr_FR=3,en_US=1
+pub fn languages_var(name: &str) -> String {
+    format!("TARGET_LANGUAGES_{}", env_suffix(name))
+}
+
+fn env_suffix(name: &str) -> String {
+    name.to_uppercase().replace('-', "_")
+}
+
+/// Storefront database loaded from the same extraction as the other profiles
+#[derive(Clone, PartialEq)]
+pub struct TargetProfile {
+    pub name: String,
+    pub target_url: String,
+    // Target language id by locale, the legacy staging id is kept for missing locales
+    pub languages: HashMap<String, u32>,
+}
+
+impl fmt::Debug for TargetProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TargetProfile")
+            .field("name", &self.name)
+            .field("target_url", &RedactedUrl(&self.target_url))
+            .field("languages", &self.languages)
+            .finish()
+    }
+}
+
+impl TargetProfile {
+    pub fn from_config(name: &str, config: &ProfileConfig) -> Result<Self, InfrastructureError> {
+        let target_url = config.target_url.clone().ok_or_else(|| {
+            InfrastructureError::ConfigError(format!(
+                "Profile {} has no target_url ({})",
+                name,
+                target_url_var(name)
+            ))
+        })?;
+        Ok(Self {
+            name: name.to_string(),
+            target_url,
+            languages: config.languages.clone().into_iter().collect(),
+        })
+    }
+}
+
+pub fn parse_languages(value: &str) -> Result<HashMap<String, u32>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(|language| {
+            let (locale, id) = language
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid language {}, expected LOCALE=ID", language))?;
+            let id = id
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid language id {}", id))?;
+            Ok((locale.trim().to_string(), id))
+        })
+        .collect()
+}
+
+// Per thread, scheduled imports of different profiles run concurrently
+thread_local! {
+    static SELECTED: RefCell<Vec<TargetProfile>> = const { RefCell::new(Vec::new()) };
+    static ACTIVE: RefCell<Option<TargetProfile>> = const { RefCell::new(None) };
+}
+
+/// Profiles the next imports are loaded into, the first one being active while rows are validated.
+/// No profile means the default target database.
+pub fn select(profiles: Vec<TargetProfile>) {
+    ACTIVE.with(|active| *active.borrow_mut() = profiles.first().cloned());
+    SELECTED.with(|selected| *selected.borrow_mut() = profiles);
+}
+
+pub fn is_selected() -> bool {
+    SELECTED.with(|selected| !selected.borrow().is_empty())
+}
+
+pub fn active_name() -> Option<String> {
+    ACTIVE.with(|active| active.borrow().as_ref().map(|profile| profile.name.clone()))
+}
+
+pub fn active_target_url() -> Option<String> {
+    ACTIVE.with(|active| {
+        active
+            .borrow()
+            .as_ref()
+            .map(|profile| profile.target_url.clone())
+    })
+}
+
+/// Language id of the active target for a locale, the given legacy staging id otherwise
+pub fn language_id(locale: &str, id: u32) -> u32 {
+    ACTIVE.with(|active| {
+        active
+            .borrow()
+            .as_ref()
+            .and_then(|profile| profile.languages.get(locale).copied())
+            .unwrap_or(id)
+    })
+}
+
+/// Runs the write once per selected profile, with the profile active,
+/// or once against the default target. Errors are tagged with their profile.
+pub fn for_each_target<E, F>(mut write: F) -> Vec<(Option<String>, E)>
+where
+    F: FnMut() -> Option<Vec<E>>,
+{
+    let profiles = SELECTED.with(|selected| selected.borrow().clone());
+    if profiles.is_empty() {
+        return write()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| (None, e))
+            .collect();
+    }
+
+    let previous = ACTIVE.with(|active| active.borrow().clone());
+    let mut errors = Vec::new();
+    for profile in profiles {
+        let name = profile.name.clone();
+        ACTIVE.with(|active| *active.borrow_mut() = Some(profile));
+        errors.extend(
+            write()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| (Some(name.clone()), e)),
+        );
+    }
+    ACTIVE.with(|active| *active.borrow_mut() = previous);
+    errors
+}
+
+/// Wraps the error of a profile so the failing target shows in the logs
+pub fn tag_error(target: Option<String>, error: Box<dyn Error>) -> Box<dyn Error> {
+    match target {
+        Some(name) => Box::new(InfrastructureError::Target(name, error)),
+        None => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, languages: &str) -> TargetProfile {
+        TargetProfile {
+            name: name.to_string(),
+            target_url: format!("mysql://localhost/{}", name),
+            languages: parse_languages(languages).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_languages() {
+        assert_eq!(
+            parse_languages("fr_FR=3, en_US=1").unwrap(),
+            HashMap::from([("fr_FR".to_string(), 3), ("en_US".to_string(), 1)])
+        );
+        assert!(parse_languages("fr_FR").is_err());
+        assert!(parse_languages("fr_FR=first").is_err());
+    }
+
+    #[test]
+    fn test_profile_from_config() {
+        let config = ProfileConfig {
+            target_url: Some("mysql://localhost/shop_be".to_string()),
+            languages: [("fr_FR".to_string(), 3)].into(),
+        };
+
+        assert_eq!(
+            TargetProfile::from_config("shop-be", &config).unwrap(),
+            TargetProfile {
+                name: "shop-be".to_string(),
+                target_url: "mysql://localhost/shop_be".to_string(),
+                languages: HashMap::from([("fr_FR".to_string(), 3)]),
+            }
+        );
+        assert!(TargetProfile::from_config("shop-nl", &ProfileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_for_each_target() {
+        assert_eq!(
+            for_each_target(|| Some(vec![language_id("fr_FR", 2)])),
+            vec![(None, 2)]
+        );
+
+        select(vec![profile("shop_fr", "fr_FR=3"), profile("shop_be", "")]);
+        assert_eq!(active_name().as_deref(), Some("shop_fr"));
+
+        let written = for_each_target(|| {
+            Some(vec![(
+                active_target_url().unwrap(),
+                language_id("fr_FR", 2),
+            )])
+        });
+
+        assert_eq!(
+            written,
+            vec![
+                (
+                    Some("shop_fr".to_string()),
+                    ("mysql://localhost/shop_fr".to_string(), 3)
+                ),
+                (
+                    Some("shop_be".to_string()),
+                    ("mysql://localhost/shop_be".to_string(), 2)
+                ),
+            ]
+        );
+        assert_eq!(active_name().as_deref(), Some("shop_fr"));
+        select(vec![]);
+        assert_eq!(active_name(), None);
+    }
+}
@@ -0,0 +1,216 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+
+use super::profile;
+
+/// Row counts of an entity for the current run
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EntityReport {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    // Rows written without change detection
+    pub written: usize,
+    pub deleted: usize,
+    pub flagged: usize,
+    pub purged: usize,
+    // Rows a purge dry run found older than the retention period
+    pub would_purge: usize,
+    // Prices changed by the rounding policy
+    pub rounded: usize,
+    // Rows kept despite broken rules configured as warnings
+    pub warned: usize,
+    // Completions recomputed from the order lines, differing from the CSV
+    pub recomputed: usize,
+}
+
+struct RunReport {
+    // Short identifier of the run, derived from its start time, process and thread
+    run_id: String,
+    entities: BTreeMap<String, EntityReport>,
+    // Source files in reading order with their number of rows
+    files: Vec<(PathBuf, usize)>,
+}
+
+impl Default for RunReport {
+    fn default() -> Self {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let digest = Sha256::digest(format!(
+            "{}-{}-{:?}",
+            started_at,
+            std::process::id(),
+            thread::current().id()
+        ));
+        Self {
+            run_id: format!("{:x}", digest)[..8].to_string(),
+            entities: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+}
+
+thread_local! {
+    // Each thread is a run of its own, e.g. a scheduled job
+    static RUN_REPORT: RefCell<RunReport> = RefCell::new(RunReport::default());
+}
+
+pub fn run_id() -> String {
+    RUN_REPORT.with(|report| report.borrow().run_id.clone())
+}
+
+/// Starts a new run with a new identifier and no counts, e.g. for each file a watcher imports
+pub fn start_run() {
+    RUN_REPORT.with(|report| *report.borrow_mut() = RunReport::default());
+}
+
+pub fn record<F>(entity: &str, update: F)
+where
+    F: FnOnce(&mut EntityReport),
+{
+    // Counts of a target profile are kept apart, e.g. order@shop_fr
+    let entity = match profile::active_name() {
+        Some(name) => format!("{}@{}", entity, name),
+        None => entity.to_string(),
+    };
+    RUN_REPORT.with(|report| update(report.borrow_mut().entities.entry(entity).or_default()));
+}
+
+/// A file read several times in the same run keeps its last count
+pub fn record_file(path: &Path, rows: usize) {
+    RUN_REPORT.with(|report| {
+        let files = &mut report.borrow_mut().files;
+        match files.iter_mut().find(|(file, _)| file == path) {
+            Some((_, count)) => *count = rows,
+            None => files.push((path.to_path_buf(), rows)),
+        }
+    });
+}
+
+#[cfg(test)]
+pub fn entity_report(entity: &str) -> Option<EntityReport> {
+    RUN_REPORT.with(|report| report.borrow().entities.get(entity).cloned())
+}
+
+#[cfg(test)]
+pub fn file_report(path: &Path) -> Option<usize> {
+    RUN_REPORT.with(|report| {
+        report
+            .borrow()
+            .files
+            .iter()
+            .find(|(file, _)| file == path)
+            .map(|(_, rows)| *rows)
+    })
+}
+
+pub fn log_summary() {
+    RUN_REPORT.with(|report| log_report(&report.borrow()));
+}
+
+fn log_report(report: &RunReport) {
+    for (file, rows) in report.files.iter() {
+        info!("{}: {} rows read", file.display(), rows);
+    }
+    for (entity, counts) in report.entities.iter() {
+        if counts.written > 0 {
+            info!("{}: {} rows written", entity, counts.written);
+        }
+        if counts.inserted + counts.updated + counts.unchanged > 0 {
+            info!(
+                "{}: {} inserted, {} updated, {} unchanged",
+                entity, counts.inserted, counts.updated, counts.unchanged
+            );
+        }
+        if counts.deleted + counts.flagged > 0 {
+            info!(
+                "{}: {} deleted, {} flagged as missing from the extract",
+                entity, counts.deleted, counts.flagged
+            );
+        }
+        if counts.purged > 0 {
+            info!("{}: {} purged", entity, counts.purged);
+        }
+        if counts.would_purge > 0 {
+            info!(
+                "{}: {} would be purged (dry run)",
+                entity, counts.would_purge
+            );
+        }
+        if counts.rounded > 0 {
+            warn!(
+                "{}: {} prices rounded, see PRICE_ROUNDING",
+                entity, counts.rounded
+            );
+        }
+        if counts.warned > 0 {
+            warn!("{}: {} rows imported with warnings", entity, counts.warned);
+        }
+        if counts.recomputed > 0 {
+            info!(
+                "{}: {} completions recomputed from the order lines",
+                entity, counts.recomputed
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_accumulates_counts() {
+        record("report_test", |r| r.inserted += 2);
+        record("report_test", |r| {
+            r.inserted += 1;
+            r.unchanged += 5;
+        });
+
+        let report = entity_report("report_test").unwrap();
+        assert_eq!(report.inserted, 3);
+        assert_eq!(report.updated, 0);
+        assert_eq!(report.unchanged, 5);
+        assert!(entity_report("report_missing").is_none());
+    }
+
+    #[test]
+    fn test_threads_keep_their_own_report() {
+        record("report_thread_test", |r| r.inserted += 1);
+        let (run_id, report) = thread::spawn(|| (run_id(), entity_report("report_thread_test")))
+            .join()
+            .unwrap();
+
+        assert!(report.is_none());
+        assert_ne!(run_id, super::run_id());
+        assert_eq!(entity_report("report_thread_test").unwrap().inserted, 1);
+    }
+
+    #[test]
+    fn test_start_run_resets_counts() {
+        record("report_run_test", |r| r.inserted += 1);
+        let previous_run_id = run_id();
+        start_run();
+
+        assert!(entity_report("report_run_test").is_none());
+        assert_ne!(run_id(), previous_run_id);
+    }
+
+    #[test]
+    fn test_record_file_keeps_last_count() {
+        let path = Path::new("report_test.csv");
+        record_file(path, 3);
+        record_file(path, 4);
+
+        assert_eq!(file_report(path), Some(4));
+        assert_eq!(file_report(Path::new("report_missing.csv")), None);
+    }
+}
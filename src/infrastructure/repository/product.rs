@@ -16,7 +16,7 @@ use crate::{
                 CanUpsertModel,
             },
         },
-        InfrastructureError,
+        shutdown, InfrastructureError,
     },
     interface_adapters::mappers::{
         parse_string_to_u32, product::transform_csv_to_product, MappingError,
@@ -199,12 +199,19 @@ where
         } else {
             let connection = &mut self.connection.borrow_mut();
             for model in models {
+                if shutdown::is_requested() {
+                    break;
+                }
                 if let Err(e) = model.upsert(connection) {
                     errors.push(Box::new(e));
                 }
             }
         }
 
+        if shutdown::is_requested() {
+            errors.push(Box::new(InfrastructureError::Interrupted));
+        }
+
         if errors.is_empty() {
             None
         } else {
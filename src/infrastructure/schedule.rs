@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use cron::Schedule;
+use serde::Deserialize;
+
+use super::InfrastructureError;
+
+#[derive(Debug, Deserialize)]
+struct ScheduleFile {
+    #[serde(rename = "job", default)]
+    jobs: Vec<JobConfig>,
+}
+
+/// Import of an entity, with the options of the import command
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobConfig {
+    /// Entity name as given to the import command, e.g. order or delivery-slip
+    pub entity: String,
+    /// Cron expression with seconds: sec min hour day_of_month month day_of_week [year]
+    pub cron: String,
+    #[serde(default)]
+    pub batch: bool,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub clear: bool,
+    /// Target profiles the import is loaded into, the default target when empty
+    #[serde(default)]
+    pub profiles: Vec<String>,
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+    pub config: JobConfig,
+    pub schedule: Schedule,
+}
+
+/// Reads the [[job]] tables of a schedule TOML file
+pub fn load_schedule(path: &Path) -> Result<Vec<ScheduledJob>, InfrastructureError> {
+    let content = fs::read_to_string(path).map_err(InfrastructureError::IOError)?;
+    parse_schedule(&content)
+}
+
+fn parse_schedule(content: &str) -> Result<Vec<ScheduledJob>, InfrastructureError> {
+    let file: ScheduleFile =
+        toml::from_str(content).map_err(|e| InfrastructureError::ConfigError(e.to_string()))?;
+    if file.jobs.is_empty() {
+        return Err(InfrastructureError::ConfigError(
+            "The schedule has no [[job]]".to_string(),
+        ));
+    }
+    file.jobs
+        .into_iter()
+        .map(|config| {
+            let schedule = Schedule::from_str(&config.cron).map_err(|e| {
+                InfrastructureError::ConfigError(format!(
+                    "Invalid cron expression {} for {}: {}",
+                    config.cron, config.entity, e
+                ))
+            })?;
+            Ok(ScheduledJob { config, schedule })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_schedule() {
+        let jobs = parse_schedule(
+            r#"
+            [[job]]
+            entity = "order"
+            cron = "0 0 2 * * *"
+
+            [[job]]
+            entity = "product"
+            cron = "0 30 3 * * Mon"
+            batch = true
+            batch_size = 500
+            clear = true
+            profiles = ["shop_fr", "shop_be"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(
+            jobs[0].config,
+            JobConfig {
+                entity: "order".to_string(),
+                cron: "0 0 2 * * *".to_string(),
+                batch: false,
+                batch_size: 100,
+                clear: false,
+                profiles: vec![],
+            }
+        );
+        assert!(jobs[1].config.batch && jobs[1].config.clear);
+        assert_eq!(jobs[1].config.batch_size, 500);
+        assert_eq!(jobs[1].config.profiles, vec!["shop_fr", "shop_be"]);
+    }
+
+    #[test]
+    fn test_parse_invalid_schedule() {
+        assert!(matches!(
+            parse_schedule("[[job]]\nentity = \"order\"\ncron = \"every night\"\n"),
+            Err(InfrastructureError::ConfigError(_))
+        ));
+        assert!(matches!(
+            parse_schedule("[[job]]\nentity = \"order\"\n"),
+            Err(InfrastructureError::ConfigError(_))
+        ));
+        assert!(matches!(
+            parse_schedule(""),
+            Err(InfrastructureError::ConfigError(_))
+        ));
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+/// Exit code of a run stopped by SIGINT or SIGTERM
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// The first SIGINT or SIGTERM only raises a flag checked between chunks,
+/// a second one stops the process at once
+pub fn install_handler() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        warn!("Shutdown requested, stopping after the current chunk");
+    })
+}
+
+pub fn is_requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Returns false when the sleep was cut short by a shutdown request
+pub fn sleep(duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+    while !is_requested() {
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(Duration::from_millis(200)));
+    }
+    false
+}
+
+#[cfg(test)]
+pub fn set_requested(requested: bool) {
+    REQUESTED.store(requested, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn test_sleep_is_cut_short_by_shutdown() {
+        assert!(sleep(Duration::from_millis(10)));
+
+        set_requested(true);
+        let started_at = Instant::now();
+        let completed = sleep(Duration::from_secs(60));
+        set_requested(false);
+
+        assert!(!completed);
+        assert!(started_at.elapsed() < Duration::from_secs(1));
+    }
+}
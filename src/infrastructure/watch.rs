@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::{csv_reader::CsvType, InfrastructureError};
+
+/// File name glob pattern mapped to the CSV source it feeds, written as GLOB=SOURCE,
+/// e.g. orders_*.csv=orders
+#[derive(Debug, Clone)]
+pub struct WatchPattern {
+    pattern: glob::Pattern,
+    csv_type: CsvType,
+}
+
+impl FromStr for WatchPattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (pattern, source) = value
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Invalid pattern {}, expected GLOB=SOURCE", value))?;
+        Ok(Self {
+            pattern: glob::Pattern::new(pattern).map_err(|e| e.to_string())?,
+            csv_type: source.parse()?,
+        })
+    }
+}
+
+impl WatchPattern {
+    pub fn csv_type(&self) -> &CsvType {
+        &self.csv_type
+    }
+
+    /// Matches the file name only, the directory is the watched one
+    pub fn matches(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| self.pattern.matches(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileState {
+    size: u64,
+    modified: SystemTime,
+}
+
+/// Polls a directory and reports the files which are done being written:
+/// their size and modification date did not change for the settle delay
+pub(crate) struct FolderWatcher {
+    directory: PathBuf,
+    settle: Duration,
+    // Files being written, with the time their current state was first seen
+    pending: HashMap<PathBuf, (FileState, Instant)>,
+    // Files already reported, reported again once they change
+    reported: HashMap<PathBuf, FileState>,
+}
+
+impl FolderWatcher {
+    pub fn new(directory: PathBuf, settle: Duration) -> Self {
+        Self {
+            directory,
+            settle,
+            pending: HashMap::new(),
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Files ready to be imported, oldest first
+    pub fn poll(&mut self, now: Instant) -> Result<Vec<PathBuf>, InfrastructureError> {
+        let mut current = HashMap::new();
+        for entry in fs::read_dir(&self.directory).map_err(InfrastructureError::IOError)? {
+            let path = entry.map_err(InfrastructureError::IOError)?.path();
+            // A file may be removed between listing and reading its metadata
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.is_file() {
+                let modified = metadata.modified().map_err(InfrastructureError::IOError)?;
+                current.insert(
+                    path,
+                    FileState {
+                        size: metadata.len(),
+                        modified,
+                    },
+                );
+            }
+        }
+
+        self.pending.retain(|path, _| current.contains_key(path));
+        self.reported.retain(|path, _| current.contains_key(path));
+
+        let mut ready = Vec::new();
+        for (path, state) in current {
+            if self.reported.get(&path) == Some(&state) {
+                continue;
+            }
+            match self.pending.get(&path) {
+                Some((pending_state, since)) if *pending_state == state => {
+                    if now.duration_since(*since) >= self.settle {
+                        ready.push((state, path));
+                    }
+                }
+                _ => {
+                    self.pending.insert(path, (state, now));
+                }
+            }
+        }
+
+        ready.sort_by(|(a, a_path), (b, b_path)| (a.modified, a_path).cmp(&(b.modified, b_path)));
+        Ok(ready
+            .into_iter()
+            .map(|(state, path)| {
+                self.pending.remove(&path);
+                self.reported.insert(path.clone(), state);
+                path
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_watch_pattern_from_str() {
+        let pattern: WatchPattern = "orders_*.csv=orders".parse().unwrap();
+
+        assert_eq!(pattern.csv_type(), &CsvType::Order);
+        assert!(pattern.matches(Path::new("drop/orders_20261018.csv")));
+        assert!(!pattern.matches(Path::new("drop/invoices_20261018.csv")));
+        assert!("orders_*.csv".parse::<WatchPattern>().is_err());
+        assert!("orders_*.csv=unknown".parse::<WatchPattern>().is_err());
+    }
+
+    #[test]
+    fn test_report_file_once_stable() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("orders.csv");
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(b"c_order_id\n").unwrap();
+        let settle = Duration::from_secs(10);
+        let mut watcher = FolderWatcher::new(directory.path().to_path_buf(), settle);
+        let start = Instant::now();
+
+        assert!(watcher.poll(start).unwrap().is_empty());
+        assert!(watcher.poll(start + settle / 2).unwrap().is_empty());
+
+        // Still being written, the settle delay starts again
+        file.write_all(b"1\n").unwrap();
+        assert!(watcher.poll(start + settle).unwrap().is_empty());
+        assert!(watcher.poll(start + settle * 3 / 2).unwrap().is_empty());
+        assert_eq!(
+            watcher.poll(start + settle * 2).unwrap(),
+            vec![path.clone()]
+        );
+
+        // Reported once until it changes
+        assert!(watcher.poll(start + settle * 3).unwrap().is_empty());
+        file.write_all(b"2\n").unwrap();
+        assert!(watcher.poll(start + settle * 4).unwrap().is_empty());
+        assert_eq!(watcher.poll(start + settle * 5).unwrap(), vec![path]);
+    }
+}
@@ -1,22 +1,59 @@
+use chrono::{DateTime, Local};
 use clap::{Args, CommandFactory, Parser, Subcommand};
-use log::{error, info, warn};
-use std::path::PathBuf;
+use log::{debug, error, info, warn};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
 use crate::{
-    infrastructure::logger,
+    infrastructure::{
+        archive::Archiver,
+        as_of::AsOf,
+        config::{BatchConfig, Config},
+        csv_reader::{CsvSources, CsvType},
+        database::{connection, schema_check::Severity},
+        fingerprint::FingerprintStore,
+        logger,
+        profile::{self, TargetProfile},
+        report,
+        schedule::{load_schedule, JobConfig, ScheduledJob},
+        shutdown::{self, INTERRUPTED_EXIT_CODE},
+        watch::{FolderWatcher, WatchPattern},
+        InfrastructureError,
+    },
     use_cases::{
         clear_product::ClearProductUseCase,
-        helpers::{csv::ImportFromSingleEntityBasedCsvUseCase, model::ImportModelUseCase},
+        doctor::{DoctorUseCase, Entity},
+        helpers::{
+            archive::SourceArchiver, csv::ImportFromSingleEntityBasedCsvUseCase,
+            fingerprint::SourceFingerprintGuard, model::ImportModelUseCase,
+        },
         import_delivery_slip::ImportDeliverySlipUseCase,
         import_invoice::ImportInvoiceUseCase,
+        import_invoice_line::ImportInvoiceLineUseCase,
         import_mapping_client::ImportMappingClientUseCase,
         import_order::ImportOrderUseCase,
         import_order_line::ImportOrderLineUseCase,
+        import_payment::ImportPaymentUseCase,
         import_product::ImportProductUseCase,
+        purge::{
+            PurgeConfig, PurgeDeliverySlipUseCase, PurgeInvoiceUseCase, PurgeOrderUseCase,
+            RetentionPeriod,
+        },
+        sync_deletes::{DeletePolicy, SyncDeletesConfig, SyncDeletesUseCase},
         ExecutableUseCase, UseCaseError,
     },
 };
 
+const DEFAULT_BATCH_SIZE: usize = 100;
+
 // https://docs.rs/clap/latest/clap/_derive/_tutorial
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
@@ -29,6 +66,18 @@ struct Cli {
 enum ActionCommands {
     /// Import Idempiere from CSV files
     Import(EntityCommand),
+
+    /// Purge target rows older than the retention period
+    Purge(PurgeCommand),
+
+    /// Import CSV files as they arrive in a directory
+    Watch(WatchArgs),
+
+    /// Run imports on the cron expressions of a schedule file
+    Serve(ServeArgs),
+
+    /// Check the configuration, source files and databases needed by the imports
+    Doctor(DoctorArgs),
 }
 
 #[derive(Debug, Args)]
@@ -44,22 +93,130 @@ pub enum EntitySubCommand {
     MappingClient(MandatoryArgs),
 
     /// Import Orders from CSV file defined in env file argument
-    Order(MandatoryArgs),
+    Order(SnapshotArgs),
 
     /// Import OrderLines from CSV file defined in env file argument
     Orderline(MandatoryArgs),
 
     // Import Delivery Slips from CSV file defined in env file argument
-    DeliverySlip(MandatoryArgs),
+    DeliverySlip(SnapshotArgs),
 
     /// Import Invoices from CSV file defined in env file argument
     Invoice(MandatoryArgs),
 
+    /// Import Invoice Lines from CSV file defined in env file argument
+    InvoiceLine(MandatoryArgs),
+
+    /// Import Payments and their allocations from CSV files defined in env file argument
+    Payment(MandatoryArgs),
+
     /// Import Products from CSV file defined in env file argument
     Product(ProductArgs),
 }
 
+impl EntitySubCommand {
+    fn entity(&self) -> Entity {
+        match self {
+            EntitySubCommand::MappingClient(_) => Entity::MappingClient,
+            EntitySubCommand::Order(_) => Entity::Order,
+            EntitySubCommand::Orderline(_) => Entity::Orderline,
+            EntitySubCommand::DeliverySlip(_) => Entity::DeliverySlip,
+            EntitySubCommand::Invoice(_) => Entity::Invoice,
+            EntitySubCommand::InvoiceLine(_) => Entity::InvoiceLine,
+            EntitySubCommand::Payment(_) => Entity::Payment,
+            EntitySubCommand::Product(_) => Entity::Product,
+        }
+    }
+
+    fn mandatory(&self) -> &MandatoryArgs {
+        match self {
+            EntitySubCommand::MappingClient(arg)
+            | EntitySubCommand::Orderline(arg)
+            | EntitySubCommand::Invoice(arg)
+            | EntitySubCommand::InvoiceLine(arg)
+            | EntitySubCommand::Payment(arg) => arg,
+            EntitySubCommand::Order(arg) | EntitySubCommand::DeliverySlip(arg) => &arg.mandatory,
+            EntitySubCommand::Product(arg) => &arg.mandatory,
+        }
+    }
+
+    // Import of the entity a CSV source belongs to, without deletes sync nor clear
+    fn from_source(csv_type: &CsvType, mandatory: MandatoryArgs) -> Option<Self> {
+        match csv_type {
+            CsvType::Order => Some(EntitySubCommand::Order(SnapshotArgs {
+                mandatory,
+                sync: SyncDeletesArgs::default(),
+            })),
+            CsvType::OrderLine | CsvType::OrderLineItem => {
+                Some(EntitySubCommand::Orderline(mandatory))
+            }
+            CsvType::DeliverySlip => Some(EntitySubCommand::DeliverySlip(SnapshotArgs {
+                mandatory,
+                sync: SyncDeletesArgs::default(),
+            })),
+            CsvType::Invoice | CsvType::InvoiceDocumentType => {
+                Some(EntitySubCommand::Invoice(mandatory))
+            }
+            CsvType::InvoiceLine => Some(EntitySubCommand::InvoiceLine(mandatory)),
+            CsvType::Payment | CsvType::PaymentAllocation => {
+                Some(EntitySubCommand::Payment(mandatory))
+            }
+            CsvType::ProductSubstitute => Some(EntitySubCommand::Product(ProductArgs {
+                mandatory,
+                clear: ClearArgs { clear: false },
+            })),
+            CsvType::Test(_) => None,
+        }
+    }
+}
+
+// Import command a scheduled job stands for
+#[derive(Debug, Parser)]
+#[command(no_binary_name = true)]
+struct ScheduledImport {
+    #[command(subcommand)]
+    entity: EntitySubCommand,
+}
+
+impl ScheduledImport {
+    fn entity(job: &JobConfig) -> Result<EntitySubCommand, clap::Error> {
+        let mut args = vec![job.entity.clone()];
+        if job.batch {
+            args.push("--batch".to_string());
+            args.push("--batch-size".to_string());
+            args.push(job.batch_size.to_string());
+        }
+        if job.clear {
+            args.push("--clear".to_string());
+        }
+        for profile in &job.profiles {
+            args.push("--profile".to_string());
+            args.push(profile.clone());
+        }
+        ScheduledImport::try_parse_from(args).map(|import| import.entity)
+    }
+}
+
 #[derive(Debug, Args)]
+struct PurgeCommand {
+    /// Entity name
+    #[command(subcommand)]
+    entity: PurgeSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PurgeSubCommand {
+    /// Purge Orders, with their lines, by order date
+    Order(PurgeArgs),
+
+    /// Purge Delivery Slips by shipping date
+    DeliverySlip(PurgeArgs),
+
+    /// Purge Invoices, with their translations, by invoice date
+    Invoice(PurgeArgs),
+}
+
+#[derive(Debug, Clone, Args)]
 pub struct MandatoryArgs {
     /// Sets env file
     #[arg(short, long, value_name = "ENV_FILE")]
@@ -69,9 +226,54 @@ pub struct MandatoryArgs {
     #[arg(short = 'b', long)]
     batch: bool,
 
-    /// Batch chunks size
-    #[arg(short = 's', long, default_value = "100")]
-    batch_size: usize,
+    /// Batch chunks size, 100 by default
+    #[arg(short = 's', long)]
+    batch_size: Option<usize>,
+
+    /// Import even if the source files are unchanged since the last successful import
+    #[arg(long)]
+    force: bool,
+
+    /// Only write rows which are new or changed since the previous import
+    #[arg(long)]
+    incremental: bool,
+
+    /// Date filling the placeholders of source paths: today, yesterday or YYYY-MM-DD
+    #[arg(long, value_name = "DATE")]
+    as_of: Option<AsOf>,
+
+    /// Target profile the rows are loaded into, repeat it to load several targets
+    #[arg(long = "profile", value_name = "PROFILE")]
+    profiles: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct PurgeArgs {
+    /// Sets env file
+    #[arg(short, long, value_name = "ENV_FILE")]
+    env_file: Option<PathBuf>,
+
+    /// Purge rows older than this duration, e.g. 90d, 12w, 6m or 2y
+    #[arg(long, value_name = "DURATION")]
+    older_than: RetentionPeriod,
+
+    /// Only count the rows which would be purged
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Rows deleted per transaction
+    #[arg(short = 's', long, default_value = "1000")]
+    chunk_size: usize,
+}
+
+impl PurgeArgs {
+    fn config(&self) -> PurgeConfig {
+        PurgeConfig {
+            older_than: self.older_than,
+            dry_run: self.dry_run,
+            chunk_size: self.chunk_size,
+        }
+    }
 }
 
 #[derive(Debug, Args)]
@@ -81,6 +283,70 @@ pub struct ClearArgs {
     clear: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct SyncDeletesArgs {
+    /// Delete or flag target rows missing from the CSV file, which must be a full extract
+    #[arg(long)]
+    sync_deletes: bool,
+
+    /// What to do with rows missing from the extract: delete or flag
+    #[arg(long, value_name = "POLICY", default_value = "flag")]
+    delete_policy: DeletePolicy,
+
+    /// Maximum share of target rows removed in one run, from 0 to 1
+    #[arg(long, value_name = "RATIO", default_value = "0.1")]
+    max_delete_ratio: f64,
+}
+
+impl MandatoryArgs {
+    // batch.enabled of the configuration enables batch mode as well,
+    // --batch-size takes precedence over batch.size
+    fn batch_size(&self, config: &BatchConfig) -> Option<usize> {
+        (self.batch || config.enabled == Some(true)).then(|| {
+            self.batch_size
+                .or(config.size)
+                .unwrap_or(DEFAULT_BATCH_SIZE)
+        })
+    }
+}
+
+impl Default for SyncDeletesArgs {
+    fn default() -> Self {
+        Self {
+            sync_deletes: false,
+            delete_policy: DeletePolicy::Flag,
+            max_delete_ratio: 0.1,
+        }
+    }
+}
+
+impl SyncDeletesArgs {
+    fn config(&self) -> Option<SyncDeletesConfig> {
+        if !self.sync_deletes {
+            return None;
+        }
+        if !(0.0..=1.0).contains(&self.max_delete_ratio) {
+            exit(
+                clap::error::ErrorKind::InvalidValue,
+                "--max-delete-ratio must be between 0 and 1",
+            );
+        }
+        Some(SyncDeletesConfig {
+            policy: self.delete_policy,
+            max_ratio: self.max_delete_ratio,
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotArgs {
+    #[clap(flatten)]
+    mandatory: MandatoryArgs,
+
+    #[clap(flatten)]
+    sync: SyncDeletesArgs,
+}
+
 #[derive(Debug, Args)]
 pub struct ProductArgs {
     #[clap(flatten)]
@@ -90,93 +356,584 @@ pub struct ProductArgs {
     clear: ClearArgs,
 }
 
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    /// Directory where CSV files are dropped
+    directory: PathBuf,
+
+    /// File name pattern and the CSV source it feeds, e.g. orders_*.csv=orders
+    #[arg(
+        short = 'p',
+        long = "pattern",
+        value_name = "GLOB=SOURCE",
+        required = true
+    )]
+    patterns: Vec<WatchPattern>,
+
+    /// Seconds between two scans of the directory
+    #[arg(long, value_name = "SECONDS", default_value = "5")]
+    interval: u64,
+
+    /// Seconds a file must stay unchanged before it is imported
+    #[arg(long, value_name = "SECONDS", default_value = "10")]
+    settle: u64,
+
+    #[clap(flatten)]
+    mandatory: MandatoryArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Sets env file
+    #[arg(short, long, value_name = "ENV_FILE")]
+    env_file: Option<PathBuf>,
+
+    /// TOML file with one [[job]] table per scheduled import
+    #[arg(long, value_name = "SCHEDULE_FILE")]
+    schedule: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Sets env file
+    #[arg(short, long, value_name = "ENV_FILE")]
+    env_file: Option<PathBuf>,
+
+    /// Entity to check, every entity by default, e.g. -n order -n invoice
+    #[arg(short = 'n', long = "entity", value_name = "ENTITY")]
+    entities: Vec<Entity>,
+
+    /// Target profile to check instead of TARGET_DATABASE_URL, repeat it to check several targets
+    #[arg(long = "profile", value_name = "PROFILE")]
+    profiles: Vec<String>,
+}
+
 pub fn main_using_clap() {
     let cli = Cli::parse();
+    if let Err(e) = shutdown::install_handler() {
+        eprintln!("Unable to handle SIGINT and SIGTERM: {}", e);
+    }
     match cli.action_command {
-        ActionCommands::Import(entity_command) => match entity_command.entity {
-            EntitySubCommand::Order(arg) => {
-                init(arg.env_file);
-                if arg.batch {
-                    info!("Batch mode not implemented yet");
-                }
-                info!("Importing orders...");
-                error_logger(ImportOrderUseCase.execute());
+        ActionCommands::Import(entity_command) => {
+            let mandatory = entity_command.entity.mandatory();
+            let config = init(mandatory.env_file.clone());
+            let sources = csv_sources(&config, mandatory.as_of);
+            import_entity(entity_command.entity, &config, &sources);
+        }
+        ActionCommands::Watch(arg) => {
+            let config = init(arg.mandatory.env_file.clone());
+            let sources = csv_sources(&config, arg.mandatory.as_of);
+            watch(arg, &config, &sources);
+        }
+        ActionCommands::Serve(arg) => {
+            let config = init(arg.env_file.clone());
+            serve(arg, config);
+        }
+        ActionCommands::Doctor(arg) => {
+            let config = init(arg.env_file.clone());
+            doctor(arg, &config);
+        }
+        ActionCommands::Purge(purge_command) => match purge_command.entity {
+            PurgeSubCommand::Order(arg) => {
+                init(arg.env_file.clone());
+                info!("Purging orders...");
+                error_logger(PurgeOrderUseCase::new(arg.config()).execute());
                 info!("Done");
             }
-            EntitySubCommand::MappingClient(arg) => {
-                init(arg.env_file);
-                if arg.batch {
-                    info!("Batch mode not implemented yet");
-                }
-                info!("Importing mapping clients...");
-                error_logger(ImportMappingClientUseCase.execute());
+            PurgeSubCommand::DeliverySlip(arg) => {
+                init(arg.env_file.clone());
+                info!("Purging delivery slips...");
+                error_logger(PurgeDeliverySlipUseCase::new(arg.config()).execute());
                 info!("Done");
             }
-            EntitySubCommand::Orderline(arg) => {
-                init(arg.env_file);
-                info!("Importing order lines...");
-                let result = ImportOrderLineUseCase::new().map(|mut handler| {
-                    if arg.batch {
-                        info!("Batch mode enabled - batch size: {}", arg.batch_size);
-                        handler.set_batch(arg.batch_size);
-                    }
-                    error_logger(handler.execute());
-                });
-                if let Err(e) = result {
-                    error_logger(Some(e));
-                }
-
+            PurgeSubCommand::Invoice(arg) => {
+                init(arg.env_file.clone());
+                info!("Purging invoices...");
+                error_logger(PurgeInvoiceUseCase::new(arg.config()).execute());
                 info!("Done");
             }
-            EntitySubCommand::DeliverySlip(arg) => {
-                init(arg.env_file);
-                info!("Importing delivery slips...");
-                let mut handler = ImportDeliverySlipUseCase::default();
-                if arg.batch {
-                    info!("Batch mode enabled - batch size: {}", arg.batch_size);
-                    handler.set_batch(arg.batch_size);
-                }
-                error_logger(handler.execute());
-                info!("Done");
+        },
+    }
+    report::log_summary();
+    if shutdown::is_requested() {
+        warn!("Run interrupted, the report above is partial");
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+}
+
+fn import_entity(entity: EntitySubCommand, config: &Config, sources: &CsvSources) {
+    if !select_profiles(config, &entity.mandatory().profiles) {
+        return;
+    }
+    import_selected_entity(entity, config, sources);
+    profile::select(vec![]);
+}
+
+fn import_selected_entity(entity: EntitySubCommand, config: &Config, sources: &CsvSources) {
+    let missing = entity.entity().missing_variables(config);
+    if !missing.is_empty() {
+        error!(
+            "Skipping {} import, missing variables {}, run fluetl doctor for details",
+            entity.entity().name(),
+            missing.join(", ")
+        );
+        return;
+    }
+    if !schema_matches(entity.entity()) {
+        return;
+    }
+    match entity {
+        EntitySubCommand::Order(arg) => {
+            let sync_config = arg.sync.config();
+            if arg.mandatory.batch_size(&config.batch).is_some() {
+                info!("Batch mode not implemented yet");
+            }
+            info!("Importing orders...");
+            error_logger(import_sources(
+                config,
+                sources,
+                vec![CsvType::Order],
+                arg.mandatory.force,
+                || {
+                    let mut handler = match ImportOrderUseCase::new(sources) {
+                        Ok(handler) => handler,
+                        Err(e) => return Some(e),
+                    };
+                    handler.set_incremental(arg.mandatory.incremental);
+                    // Re-imported orders get their CSV completion back until recomputed
+                    let errors =
+                        with_completions(handler.execute(), config.completion_from_lines(), || {
+                            on_each_target(|| handler.update_completions())
+                        });
+                    with_sync_deletes(errors, sync_config, |config| {
+                        on_each_target(|| handler.sync_deletes(config))
+                    })
+                },
+            ));
+            info!("Done");
+        }
+        EntitySubCommand::MappingClient(arg) => {
+            if arg.batch_size(&config.batch).is_some() {
+                info!("Batch mode not implemented yet");
             }
-            EntitySubCommand::Invoice(arg) => {
-                init(arg.env_file);
-                info!("Importing invoices...");
-                let result = ImportInvoiceUseCase::new().map(|mut handler| {
-                    if arg.batch {
-                        info!("Batch mode enabled - batch size: {}", arg.batch_size);
-                        handler.set_batch(arg.batch_size);
+            info!("Importing mapping clients...");
+            let mut handler = ImportMappingClientUseCase::default();
+            handler.set_incremental(arg.incremental);
+            error_logger(handler.execute());
+            info!("Done");
+        }
+        EntitySubCommand::Orderline(arg) => {
+            info!("Importing order lines...");
+            let csv_types = vec![CsvType::OrderLine, CsvType::OrderLineItem];
+            error_logger(import_sources(
+                config,
+                sources,
+                csv_types,
+                arg.force,
+                || {
+                    let rules = match config.order_line_rules() {
+                        Ok(rules) => rules,
+                        Err(e) => return Some(vec![e.into()]),
+                    };
+                    let mut handler = match ImportOrderLineUseCase::new(
+                        sources,
+                        rules,
+                        config.completion_from_lines(),
+                    ) {
+                        Ok(handler) => handler,
+                        Err(e) => return Some(e),
+                    };
+                    handler.set_incremental(arg.incremental);
+                    if let Some(batch_size) = arg.batch_size(&config.batch) {
+                        info!("Batch mode enabled - batch size: {}", batch_size);
+                        handler.set_batch(batch_size);
                     }
-                    error_logger(handler.execute());
-                });
-                if let Err(e) = result {
-                    error_logger(Some(e));
-                }
-                info!("Done");
+                    with_completions(handler.execute(), handler.completion_from_lines(), || {
+                        on_each_target(|| handler.update_completions())
+                    })
+                },
+            ));
+            info!("Done");
+        }
+        EntitySubCommand::DeliverySlip(arg) => {
+            let sync_config = arg.sync.config();
+            info!("Importing delivery slips...");
+            error_logger(import_sources(
+                config,
+                sources,
+                vec![CsvType::DeliverySlip],
+                arg.mandatory.force,
+                || {
+                    let mut handler = match ImportDeliverySlipUseCase::new(sources) {
+                        Ok(handler) => handler,
+                        Err(e) => return Some(e),
+                    };
+                    handler.set_incremental(arg.mandatory.incremental);
+                    if let Some(batch_size) = arg.mandatory.batch_size(&config.batch) {
+                        info!("Batch mode enabled - batch size: {}", batch_size);
+                        handler.set_batch(batch_size);
+                    }
+                    let errors = handler.execute();
+                    with_sync_deletes(errors, sync_config, |config| {
+                        on_each_target(|| handler.sync_deletes(config))
+                    })
+                },
+            ));
+            info!("Done");
+        }
+        EntitySubCommand::Invoice(arg) => {
+            info!("Importing invoices...");
+            let csv_types = vec![CsvType::Invoice, CsvType::InvoiceDocumentType];
+            error_logger(import_sources(
+                config,
+                sources,
+                csv_types,
+                arg.force,
+                || {
+                    let mut handler = match ImportInvoiceUseCase::new(sources) {
+                        Ok(handler) => handler,
+                        Err(e) => return Some(e),
+                    };
+                    handler.set_incremental(arg.incremental);
+                    if let Some(batch_size) = arg.batch_size(&config.batch) {
+                        info!("Batch mode enabled - batch size: {}", batch_size);
+                        handler.set_batch(batch_size);
+                    }
+                    let mut errors = handler.execute().unwrap_or_default();
+                    if errors
+                        .iter()
+                        .any(|e| matches!(e, UseCaseError::Infrastructure(_)))
+                    {
+                        warn!("Import failed, invoice open amounts are not updated");
+                    } else {
+                        info!("Updating invoice open amounts...");
+                        errors.extend(
+                            on_each_target(|| handler.update_open_amounts()).unwrap_or_default(),
+                        );
+                    }
+                    Option::from(errors).filter(|e| !e.is_empty())
+                },
+            ));
+            info!("Done");
+        }
+        EntitySubCommand::InvoiceLine(arg) => {
+            info!("Importing invoice lines...");
+            error_logger(import_sources(
+                config,
+                sources,
+                vec![CsvType::InvoiceLine],
+                arg.force,
+                || {
+                    let mut handler = match ImportInvoiceLineUseCase::new(sources) {
+                        Ok(handler) => handler,
+                        Err(e) => return Some(e),
+                    };
+                    handler.set_incremental(arg.incremental);
+                    if let Some(batch_size) = arg.batch_size(&config.batch) {
+                        info!("Batch mode enabled - batch size: {}", batch_size);
+                        handler.set_batch(batch_size);
+                    }
+                    handler.execute()
+                },
+            ));
+            info!("Done");
+        }
+        EntitySubCommand::Payment(arg) => {
+            info!("Importing payments...");
+            let csv_types = vec![CsvType::Payment, CsvType::PaymentAllocation];
+            error_logger(import_sources(
+                config,
+                sources,
+                csv_types,
+                arg.force,
+                || {
+                    let mut handler = match ImportPaymentUseCase::new(sources) {
+                        Ok(handler) => handler,
+                        Err(e) => return Some(e),
+                    };
+                    handler.set_incremental(arg.incremental);
+                    if let Some(batch_size) = arg.batch_size(&config.batch) {
+                        info!("Batch mode enabled - batch size: {}", batch_size);
+                        handler.set_batch(batch_size);
+                    }
+                    let mut errors = handler.execute().unwrap_or_default();
+                    if errors
+                        .iter()
+                        .any(|e| matches!(e, UseCaseError::Infrastructure(_)))
+                    {
+                        warn!("Import failed, invoice open amounts are not updated");
+                    } else {
+                        info!("Updating invoice open amounts...");
+                        errors.extend(
+                            on_each_target(|| handler.update_open_amounts()).unwrap_or_default(),
+                        );
+                    }
+                    Option::from(errors).filter(|e| !e.is_empty())
+                },
+            ));
+            info!("Done");
+        }
+        EntitySubCommand::Product(arg) => {
+            if arg.mandatory.incremental {
+                error!("Skipping product import, --incremental is not supported for products");
+                return;
             }
-            EntitySubCommand::Product(arg) => {
-                init(arg.mandatory.env_file);
-                if arg.clear.clear {
-                    info!("Clearing product table...");
-                    error_logger(ClearProductUseCase.execute());
-                }
-                info!("Importing Product...");
-                let mut handler = ImportProductUseCase::default();
-                if arg.mandatory.batch {
-                    info!(
-                        "Batch mode enabled - batch size: {}",
-                        arg.mandatory.batch_size
-                    );
-                    handler.set_batch(arg.mandatory.batch_size);
-                }
-                error_logger(handler.execute());
-                info!("Done");
+            if arg.clear.clear {
+                info!("Clearing product table...");
+                error_logger(on_each_target(|| ClearProductUseCase.execute()));
             }
-        },
+            info!("Importing Product...");
+            let mut handler = ImportProductUseCase::new(sources);
+            if let Some(batch_size) = arg.mandatory.batch_size(&config.batch) {
+                info!("Batch mode enabled - batch size: {}", batch_size);
+                handler.set_batch(batch_size);
+            }
+            error_logger(import_sources(
+                config,
+                sources,
+                vec![CsvType::ProductSubstitute],
+                arg.mandatory.force,
+                || handler.execute(),
+            ));
+            info!("Done");
+        }
+    }
+}
+
+fn select_profiles(config: &Config, names: &[String]) -> bool {
+    match names
+        .iter()
+        .map(|name| match config.profile(name) {
+            Some(profile) => TargetProfile::from_config(name, profile),
+            None => Err(InfrastructureError::ConfigError(format!(
+                "Profile {} is not configured",
+                name
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(profiles) => {
+            profile::select(profiles);
+            true
+        }
+        Err(e) => {
+            error!("Invalid target profile: {:?}", e);
+            false
+        }
+    }
+}
+
+// Blocking drifts between the diesel schema and the live tables skip the import
+fn schema_matches(entity: Entity) -> bool {
+    let mut drifts = Vec::new();
+    for (target, result) in profile::for_each_target(|| Some(vec![entity.schema_drifts()])) {
+        match result {
+            Ok(target_drifts) => drifts.extend(target_drifts),
+            Err(e) => {
+                error!(
+                    "Skipping {} import, schema check failed{}: {:?}",
+                    entity.name(),
+                    target
+                        .map(|name| format!(" for {}", name))
+                        .unwrap_or_default(),
+                    e
+                );
+                return false;
+            }
+        }
+    }
+    for drift in &drifts {
+        match drift.severity {
+            Severity::Error => error!("Schema drift: {}", drift),
+            Severity::Warning => warn!("Schema drift: {}", drift),
+        }
+    }
+    if drifts.iter().any(|drift| drift.severity == Severity::Error) {
+        error!(
+            "Skipping {} import, the database does not match the schema, run fluetl doctor for details",
+            entity.name()
+        );
+        return false;
+    }
+    true
+}
+
+fn doctor(arg: DoctorArgs, config: &Config) {
+    if !select_profiles(config, &arg.profiles) {
+        std::process::exit(1);
+    }
+    let sources = csv_sources(config, None);
+    let entities = if arg.entities.is_empty() {
+        Entity::all()
+    } else {
+        arg.entities
+    };
+    let checks = DoctorUseCase::new(entities, config, &sources).run();
+    for check in &checks {
+        match &check.result {
+            Ok(()) => println!("[ OK ] {}", check.label),
+            Err(e) => println!("[FAIL] {}: {}", check.label, e),
+        }
+        for warning in &check.warnings {
+            println!("[WARN] {}", warning);
+        }
+    }
+    let failed = checks.iter().filter(|check| check.result.is_err()).count();
+    if failed > 0 {
+        println!("{} of {} checks failed", failed, checks.len());
+        std::process::exit(1);
+    }
+    println!("All {} checks passed", checks.len());
+}
+
+// Runs until SIGINT or SIGTERM
+fn watch(arg: WatchArgs, config: &Config, sources: &CsvSources) {
+    if !arg.directory.is_dir() {
+        exit(
+            clap::error::ErrorKind::InvalidValue,
+            format!("{:?} is not a directory", arg.directory).as_str(),
+        );
+    }
+    info!("Watching {}...", arg.directory.display());
+    let mut watcher = FolderWatcher::new(arg.directory.clone(), Duration::from_secs(arg.settle));
+    loop {
+        match watcher.poll(Instant::now()) {
+            Ok(files) => files
+                .iter()
+                .take_while(|_| !shutdown::is_requested())
+                .for_each(|file| import_file(&arg, config, sources, file)),
+            Err(e) => error!("Unable to scan {}: {:?}", arg.directory.display(), e),
+        }
+        if !shutdown::sleep(Duration::from_secs(arg.interval)) {
+            info!("Stopped watching {}", arg.directory.display());
+            return;
+        }
     }
 }
 
-fn init(env_file: Option<PathBuf>) {
+// Runs until SIGINT or SIGTERM, a job still running when due again is skipped
+fn serve(arg: ServeArgs, config: Config) {
+    let jobs = load_schedule(&arg.schedule).unwrap_or_else(|e| {
+        exit(
+            clap::error::ErrorKind::InvalidValue,
+            format!("Invalid schedule {:?}: {:?}", arg.schedule, e).as_str(),
+        )
+    });
+    let mut running: HashMap<String, Arc<AtomicBool>> = HashMap::new();
+    for job in jobs.iter() {
+        if let Err(e) = ScheduledImport::entity(&job.config) {
+            exit(
+                clap::error::ErrorKind::InvalidValue,
+                format!("Invalid job {:?}: {}", job.config, e).as_str(),
+            );
+        }
+        running.entry(job.config.entity.clone()).or_default();
+    }
+
+    info!("Serving {} scheduled jobs...", jobs.len());
+    let mut next_runs: Vec<Option<DateTime<Local>>> = jobs
+        .iter()
+        .map(|job| job.schedule.upcoming(Local).next())
+        .collect();
+    let mut handles: Vec<JoinHandle<()>> = Vec::new();
+    loop {
+        let now = Local::now();
+        for (job, next_run) in jobs.iter().zip(next_runs.iter_mut()) {
+            if next_run.is_some_and(|next_run| next_run <= now) {
+                handles.extend(run_scheduled_job(
+                    job,
+                    &running[&job.config.entity],
+                    config.clone(),
+                ));
+                *next_run = job.schedule.after(&now).next();
+            }
+        }
+        handles.retain(|handle| !handle.is_finished());
+        let Some(next_run) = next_runs.iter().flatten().min() else {
+            info!("No scheduled job is left to run");
+            break;
+        };
+        // Woken up at least every minute so a clock change is caught up
+        let delay = (*next_run - Local::now()).to_std().unwrap_or_default();
+        if !shutdown::sleep(delay.min(Duration::from_secs(60))) {
+            info!("Shutting down, waiting for {} running jobs", handles.len());
+            break;
+        }
+    }
+    for handle in handles {
+        let _ = handle.join();
+    }
+}
+
+fn run_scheduled_job(
+    job: &ScheduledJob,
+    running: &Arc<AtomicBool>,
+    config: Config,
+) -> Option<JoinHandle<()>> {
+    let name = job.config.entity.clone();
+    if running.swap(true, Ordering::SeqCst) {
+        warn!("{} import is still running, scheduled run skipped", name);
+        return None;
+    }
+    let entity = match ScheduledImport::entity(&job.config) {
+        Ok(entity) => entity,
+        Err(e) => {
+            error!("Invalid job {:?}: {}", job.config, e);
+            running.store(false, Ordering::SeqCst);
+            return None;
+        }
+    };
+    let running = RunningGuard(Arc::clone(running));
+    Some(thread::spawn(move || {
+        info!("Scheduled {} import started", name);
+        let started_at = Instant::now();
+        // Date placeholders of the source paths are filled with the date of the run
+        match config.csv_sources(None) {
+            Ok(sources) => import_entity(entity, &config, &sources),
+            Err(e) => error!("Invalid sources of {}: {:?}", name, e),
+        }
+        report::log_summary();
+        info!(
+            "Scheduled {} import finished in {:?}",
+            name,
+            started_at.elapsed()
+        );
+        drop(running);
+    }))
+}
+
+// Releases the entity for its next run, even when the import panics
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+fn import_file(arg: &WatchArgs, config: &Config, sources: &CsvSources, file: &Path) {
+    let Some(pattern) = arg.patterns.iter().find(|pattern| pattern.matches(file)) else {
+        debug!("{} matches no pattern, ignored", file.display());
+        return;
+    };
+    let csv_type = pattern.csv_type();
+    let Some(entity) = EntitySubCommand::from_source(csv_type, arg.mandatory.clone()) else {
+        return;
+    };
+
+    info!("{} is ready, importing {}", file.display(), csv_type.name());
+    // The arrived file replaces the configured path for this import only
+    import_entity(
+        entity,
+        config,
+        &sources.with_file(csv_type, file.to_path_buf()),
+    );
+    // Each arrived file is a run of its own
+    report::log_summary();
+    report::start_run();
+}
+
+fn init(env_file: Option<PathBuf>) -> Config {
     info!("Load configuration...");
     // If --env--file argument is not provided, try to get .env file from the root of the crate
     if let Some(env_file_path) = env_file {
@@ -193,19 +950,132 @@ fn init(env_file: Option<PathBuf>) {
         let default_env_file = root_path.join(".env");
         if default_env_file.exists() {
             dotenvy::from_path(default_env_file).expect("Unable to load default env file");
-        } else {
+        } else if Config::file_path().is_none() {
             exit(
                 clap::error::ErrorKind::MissingRequiredArgument,
-                "--env-file argument or a default .env at the root of the binary file is mandatory",
+                "--env-file argument, a default .env at the root of the binary file or a fluetl.toml is mandatory",
             );
         }
     }
 
-    // Init Logger when env file is loaded
-    logger::init();
+    // Env variables loaded above override the configuration file
+    let config = Config::load()
+        .and_then(|config| {
+            connection::configure(config.database_settings()?);
+            Ok(config)
+        })
+        .unwrap_or_else(|e| {
+            exit(
+                clap::error::ErrorKind::InvalidValue,
+                format!("Invalid configuration: {:?}", e).as_str(),
+            )
+        });
+
+    // Init Logger when the configuration is loaded
+    logger::init(&config.log);
+    config
+}
+
+// --as-of overrides sources.as_of of the configuration
+fn csv_sources(config: &Config, as_of: Option<AsOf>) -> CsvSources {
+    config.csv_sources(as_of).unwrap_or_else(|e| {
+        exit(
+            clap::error::ErrorKind::InvalidValue,
+            format!("Invalid sources: {:?}", e).as_str(),
+        )
+    })
+}
+
+// Unchanged sources are skipped, even when their last import rejected rows, as only failed or
+// interrupted imports are retried. Source files of an import are then archived when
+// archive.directory is set
+fn import_sources<F>(
+    config: &Config,
+    sources: &CsvSources,
+    csv_types: Vec<CsvType>,
+    force: bool,
+    import: F,
+) -> Option<Vec<UseCaseError>>
+where
+    F: FnOnce() -> Option<Vec<UseCaseError>>,
+{
+    let archiver = match Archiver::from_config(&config.archive, &report::run_id()) {
+        Ok(archiver) => archiver,
+        Err(e) => return Some(vec![e.into()]),
+    };
+    let store = FingerprintStore::from_config(&config.fingerprints);
+    SourceFingerprintGuard::new(csv_types.clone(), force, store, sources)
+        .execute(|| SourceArchiver::new(csv_types, archiver, sources).execute(import))
+}
+
+// Writes done outside of the import persistence, e.g. deletes sync, are run on each target profile
+fn on_each_target<F>(run: F) -> Option<Vec<UseCaseError>>
+where
+    F: FnMut() -> Option<Vec<UseCaseError>>,
+{
+    let errors: Vec<UseCaseError> = profile::for_each_target(run)
+        .into_iter()
+        .map(|(target, e)| match (target, e) {
+            (Some(name), UseCaseError::Infrastructure(e)) => {
+                UseCaseError::Infrastructure(InfrastructureError::Target(name, Box::new(e)))
+            }
+            (_, e) => e,
+        })
+        .collect();
+    Option::from(errors).filter(|e| !e.is_empty())
+}
+
+// Deletes are only synchronized when the extract was imported without infrastructure failure
+fn with_sync_deletes<F>(
+    errors: Option<Vec<UseCaseError>>,
+    config: Option<SyncDeletesConfig>,
+    sync_deletes: F,
+) -> Option<Vec<UseCaseError>>
+where
+    F: FnOnce(&SyncDeletesConfig) -> Option<Vec<UseCaseError>>,
+{
+    let Some(config) = config else {
+        return errors;
+    };
+    let mut errors = errors.unwrap_or_default();
+    if errors
+        .iter()
+        .any(|e| matches!(e, UseCaseError::Infrastructure(_)))
+    {
+        warn!("Import failed, deleted rows are not synchronized");
+        return Some(errors);
+    }
+    info!("Synchronizing deleted rows...");
+    errors.extend(sync_deletes(&config).unwrap_or_default());
+    Option::from(errors).filter(|e| !e.is_empty())
+}
+
+// Completions are only recomputed when the import had no infrastructure failure
+fn with_completions<F>(
+    errors: Option<Vec<UseCaseError>>,
+    completion_from_lines: bool,
+    update_completions: F,
+) -> Option<Vec<UseCaseError>>
+where
+    F: FnOnce() -> Option<Vec<UseCaseError>>,
+{
+    if !completion_from_lines {
+        return errors;
+    }
+    let mut errors = errors.unwrap_or_default();
+    if errors
+        .iter()
+        .any(|e| matches!(e, UseCaseError::Infrastructure(_)))
+    {
+        warn!("Import failed, order completions are not recomputed");
+        return Some(errors);
+    }
+    info!("Recomputing order completions...");
+    errors.extend(update_completions().unwrap_or_default());
+    Option::from(errors).filter(|e| !e.is_empty())
 }
 
-fn exit(kind: clap::error::ErrorKind, message: &str) {
+fn exit(kind: clap::error::ErrorKind, message: &str) -> ! {
     let mut cmd = Cli::command();
     cmd.error(kind, message).exit();
 }
@@ -1,21 +1,32 @@
 pub(crate) mod delivery_slip;
 pub(crate) mod invoice;
+pub(crate) mod invoice_line;
 pub(crate) mod language;
 pub(crate) mod mapping_client;
 pub(crate) mod order;
 pub(crate) mod order_line;
+pub(crate) mod payment;
 pub(crate) mod product;
 
 use std::error::Error;
 use std::fmt;
 
 use chrono::NaiveDate;
+use log::warn;
+use rust_decimal::Decimal;
 
-use crate::domain::{DomainEntity, DomainError};
-use crate::infrastructure::csv_reader::CsvDTO;
+use crate::domain::{
+    vo::price::{Currency, Price, RoundingPolicy},
+    DomainEntity, DomainError,
+};
+use crate::infrastructure::config::PRICE_ROUNDING;
+use crate::infrastructure::csv_reader::{dialect::CsvDialect, dialect::NumberFormat, CsvDTO};
 use crate::infrastructure::database::models::Model;
 use crate::infrastructure::InfrastructureError;
 
+// Decimals of the quantity columns, decimal(12,3)
+const QUANTITY_SCALE: u32 = 3;
+
 #[derive(Debug)]
 pub enum MappingError {
     Infrastructure(InfrastructureError),
@@ -91,6 +102,19 @@ where
     fn transform_csv_row_to_entity(&self, csv: CSV) -> Result<DE, MappingError>;
 }
 
+/// Conversion of a CSV row whose dates and numbers follow the dialect of its source
+pub trait TryFromCsv<CSV>: Sized {
+    fn try_from_csv(csv: CSV, dialect: &CsvDialect) -> Result<Self, MappingError>;
+}
+
+/// A mapped value with the number of its prices that were rounded, counted by the use case
+/// once the row is accepted
+#[derive(Debug, Clone)]
+pub struct Rounded<T> {
+    pub value: T,
+    pub rounded: usize,
+}
+
 pub trait ModelToEntityParser<M, DE>
 where
     M: Model + TryInto<DE, Error = MappingError>,
@@ -133,17 +157,169 @@ pub fn parse_string_to_u32(key: &str, value: &str) -> Result<u32, MappingError>
     })
 }
 
+/// Number of a column with a '.' decimal point and no grouping, e.g. 1 234,50 => 1234.50
+pub fn normalize_number(
+    format: &NumberFormat,
+    key: &str,
+    value: &str,
+) -> Result<String, MappingError> {
+    format.normalize(value).ok_or_else(|| {
+        MappingError::Parsing(format!(
+            "Invalid number in column {}: {:?}, expected {}",
+            key, value, format
+        ))
+    })
+}
+
+/// Amount and currency of a value suffixed with its ISO 4217 code, e.g. `12.50 CHF`
+pub fn split_currency(value: &str) -> Result<(&str, Option<Currency>), MappingError> {
+    match value.trim().rsplit_once(' ') {
+        Some((amount, code))
+            if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            Ok((amount.trim_end(), Some(Currency::try_from(code)?)))
+        }
+        _ => Ok((value, None)),
+    }
+}
+
+/// Price in cents, also telling whether the rounding policy changed the amount
+pub fn parse_price(
+    format: &NumberFormat,
+    rounding: RoundingPolicy,
+    key: &str,
+    value: &str,
+) -> Result<(Price, bool), MappingError> {
+    let (price, rounded) = Price::parse_rounded(&normalize_number(format, key, value)?, rounding)
+        .map_err(|_| {
+        MappingError::Parsing(format!(
+            "Invalid price in column {}: {:?}, at most 2 decimals are expected with {} {:?}",
+            key, value, PRICE_ROUNDING, rounding
+        ))
+    })?;
+    if rounded {
+        warn!(
+            "Price in column {} rounded from {:?} to {}",
+            key, value, price
+        );
+    }
+    Ok((price, rounded))
+}
+
+pub fn parse_decimal(
+    format: &NumberFormat,
+    key: &str,
+    value: &str,
+) -> Result<Decimal, MappingError> {
+    let number = normalize_number(format, key, value)?;
+    Decimal::from_str_exact(&number).map_err(|e| {
+        MappingError::Parsing(format!(
+            "Invalid number in column {}: {:?}, {}",
+            key, value, e
+        ))
+    })
+}
+
+/// Signed quantity with up to 3 decimals, e.g. 2,5 metres or -1 for a return
+pub fn parse_decimal_quantity(
+    format: &NumberFormat,
+    key: &str,
+    value: &str,
+) -> Result<Decimal, MappingError> {
+    let quantity = parse_decimal(format, key, value)?.normalize();
+    if quantity.scale() > QUANTITY_SCALE {
+        return Err(MappingError::Parsing(format!(
+            "Invalid quantity in column {}: {:?} has more than {} decimals",
+            key, value, QUANTITY_SCALE
+        )));
+    }
+    Ok(quantity)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn french() -> NumberFormat {
+        NumberFormat::new(",", Some("space")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_errors_name_column_and_value() {
+        let Err(MappingError::Parsing(message)) =
+            parse_price(&french(), RoundingPolicy::HalfUp, "total_tax_excl", "1.234")
+        else {
+            panic!("Expected a parsing error");
+        };
+        assert_eq!(
+            message,
+            "Invalid number in column total_tax_excl: \"1.234\", expected decimal separator ',', thousands separator ' '"
+        );
+        let Err(MappingError::Parsing(message)) =
+            parse_decimal_quantity(&french(), "qty_ordered", "0,0001")
+        else {
+            panic!("Expected a parsing error");
+        };
+        assert!(message.contains("qty_ordered") && message.contains("\"0,0001\""));
+        let Err(MappingError::Parsing(message)) = parse_price(
+            &french(),
+            RoundingPolicy::Reject,
+            "total_tax_excl",
+            "18,549",
+        ) else {
+            panic!("Expected a parsing error");
+        };
+        assert!(message.contains("total_tax_excl") && message.contains("\"18,549\""));
+    }
+
+    #[test]
+    fn test_split_currency() {
+        assert_eq!(
+            split_currency("1 250,00 CHF").unwrap(),
+            ("1 250,00", Some(Currency::Chf))
+        );
+        assert_eq!(split_currency("1 250,00").unwrap(), ("1 250,00", None));
+        assert!(split_currency("12.50 USD").is_err());
+    }
+
+    #[test]
+    fn test_parse_price_rounding() {
+        let (price, rounded) = parse_price(
+            &french(),
+            RoundingPolicy::HalfUp,
+            "total_tax_excl",
+            "1 018,545",
+        )
+        .unwrap();
+        assert_eq!(price.to_string(), "1018.55");
+        assert!(rounded);
+        let (_, rounded) =
+            parse_price(&french(), RoundingPolicy::HalfUp, "total_tax_excl", "18,54").unwrap();
+        assert!(!rounded);
+    }
+
+    #[test]
+    fn test_parse_decimal_quantity() {
+        assert_eq!(
+            parse_decimal_quantity(&french(), "qty_ordered", "2,5").unwrap(),
+            Decimal::new(25, 1)
+        );
+        assert_eq!(
+            parse_decimal_quantity(&french(), "qty_ordered", "-1 200,000").unwrap(),
+            Decimal::from(-1200)
+        );
+        assert!(parse_decimal_quantity(&french(), "qty_ordered", "0,0001").is_err());
+    }
+
     #[test]
     fn test_convert_string_to_option_string() {
         let s = String::from("test");
-        let result = super::convert_string_to_option_string(s);
+        let result = convert_string_to_option_string(s);
         assert!(result.is_some());
         assert_eq!(result.unwrap(), "test");
 
         let s = String::new();
-        let result = super::convert_string_to_option_string(s);
+        let result = convert_string_to_option_string(s);
         assert!(result.is_none());
     }
 }
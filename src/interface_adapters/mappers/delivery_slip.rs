@@ -1,29 +1,26 @@
-use std::env;
-
 use crate::{
     domain::delivery_slip::{DeliverySlip, DeliverySlipDomainFactory},
     infrastructure::{
-        csv_reader::delivery_slip::CsvDeliverySlipDTO,
-        database::models::delivery_slip::DeliverySlipModel, InfrastructureError,
+        csv_reader::{delivery_slip::CsvDeliverySlipDTO, dialect::CsvDialect},
+        database::models::delivery_slip::DeliverySlipModel,
     },
 };
 
 use super::{
     convert_string_to_option_date, convert_string_to_option_string, parse_string_to_u32,
-    MappingError,
+    MappingError, TryFromCsv,
 };
 
-impl TryFrom<CsvDeliverySlipDTO> for DeliverySlipDomainFactory {
-    type Error = MappingError;
-    fn try_from(dto: CsvDeliverySlipDTO) -> Result<DeliverySlipDomainFactory, MappingError> {
-        let date_format = env::var("CSV_DATE_FORMAT")
-            .map_err(|e| MappingError::Infrastructure(InfrastructureError::EnvVarError(e)))?;
-
+impl TryFromCsv<CsvDeliverySlipDTO> for DeliverySlipDomainFactory {
+    fn try_from_csv(
+        dto: CsvDeliverySlipDTO,
+        dialect: &CsvDialect,
+    ) -> Result<DeliverySlipDomainFactory, MappingError> {
         Ok(DeliverySlipDomainFactory {
             delivery_slip_id: parse_string_to_u32("delivery_slip_id", &dto.m_inout_id)?,
             client_id: parse_string_to_u32("c_bpartner_id", &dto.c_bpartner_id)?,
             reference: dto.documentno,
-            shipping_date: convert_string_to_option_date(dto.shipping_date, &date_format)
+            shipping_date: convert_string_to_option_date(dto.shipping_date, &dialect.date_format)
                 .transpose()?,
             po_ref: convert_string_to_option_string(dto.po_ref),
             carrier_name: convert_string_to_option_string(dto.carrier_name),
@@ -70,7 +67,7 @@ mod tests {
             &self,
             csv: CsvDeliverySlipDTO,
         ) -> Result<DeliverySlip, MappingError> {
-            let factory: DeliverySlipDomainFactory = csv.try_into()?;
+            let factory = DeliverySlipDomainFactory::try_from_csv(csv, &CsvDialect::default())?;
             factory.make().map_err(MappingError::Domain)
         }
     }
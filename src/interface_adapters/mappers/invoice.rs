@@ -1,29 +1,50 @@
-use std::env;
-
 use crate::{
     domain::{
         dto::date_dto::{DateDTO, StringDateDTO},
         invoice::{Invoice, InvoiceDomainFactory, InvoiceLocalizedTypeFactory},
-        vo::{locale::Locale, Translation},
+        vo::{document_type::DocumentType, locale::Locale, price::Currency, Translation},
     },
     infrastructure::{
-        csv_reader::invoice::{CsvInvoiceDTO, CsvInvoiceLocalizedItemDTO},
+        csv_reader::{
+            dialect::CsvDialect,
+            invoice::{CsvInvoiceDTO, CsvInvoiceLocalizedItemDTO},
+        },
         database::models::invoice::{InvoiceLangModel, InvoiceModel},
-        InfrastructureError,
+        profile,
     },
 };
 
-use super::{convert_string_to_option_string, parse_string_to_u32, MappingError};
-
-impl TryFrom<CsvInvoiceDTO> for InvoiceDomainFactory {
-    type Error = MappingError;
-    fn try_from(dto: CsvInvoiceDTO) -> Result<InvoiceDomainFactory, MappingError> {
-        let date_format = env::var("CSV_DATE_FORMAT")
-            .map_err(|e| MappingError::Infrastructure(InfrastructureError::EnvVarError(e)))?;
-
-        let date_dto = DateDTO::from(StringDateDTO::new(dto.date, date_format));
+use super::{
+    convert_string_to_option_string, parse_price, parse_string_to_u32, split_currency,
+    MappingError, Rounded, TryFromCsv,
+};
 
-        Ok(InvoiceDomainFactory {
+impl TryFromCsv<CsvInvoiceDTO> for Rounded<InvoiceDomainFactory> {
+    fn try_from_csv(
+        dto: CsvInvoiceDTO,
+        dialect: &CsvDialect,
+    ) -> Result<Rounded<InvoiceDomainFactory>, MappingError> {
+        let currency = match convert_string_to_option_string(dto.currency) {
+            Some(code) => Currency::try_from(code.as_str())?,
+            None => Currency::Eur,
+        };
+
+        // A total suffixed with its own code, e.g. 12.50 CHF, overrides the currency column
+        let total = |key: &str, value: &str| {
+            let (amount, total_currency) = split_currency(value)?;
+            let (price, rounded) =
+                parse_price(&dialect.number_format, dialect.rounding, key, amount)?;
+            Ok::<_, MappingError>((
+                price.with_currency(total_currency.unwrap_or(currency)),
+                rounded,
+            ))
+        };
+        let (total_tax_excl, excl_rounded) = total("total_tax_excl", &dto.total_tax_excl)?;
+        let (total_tax_incl, incl_rounded) = total("total_tax_incl", &dto.total_tax_incl)?;
+
+        let date_dto = DateDTO::from(StringDateDTO::new(dto.date, dialect.date_format.clone()));
+
+        let value = InvoiceDomainFactory {
             invoice_id: parse_string_to_u32("invoice_id", &dto.c_invoice_id)?,
             client_id: parse_string_to_u32("c_bpartner_id", &dto.c_bpartner_id)?,
             client_name: convert_string_to_option_string(dto.client_name),
@@ -32,8 +53,15 @@ impl TryFrom<CsvInvoiceDTO> for InvoiceDomainFactory {
             date_dto,
             po_ref: convert_string_to_option_string(dto.po_ref),
             invoice_types: Vec::new(),
-            total_tax_excl: dto.total_tax_excl,
-            total_tax_incl: dto.total_tax_incl,
+            total_tax_excl,
+            total_tax_incl,
+            document_type: convert_string_to_option_string(dto.doc_base_type)
+                .map(|document_type| DocumentType::try_from(document_type.as_str()))
+                .transpose()?,
+        };
+        Ok(Rounded {
+            value,
+            rounded: usize::from(excl_rounded) + usize::from(incl_rounded),
         })
     }
 }
@@ -58,7 +86,10 @@ impl From<Invoice> for (InvoiceModel, Vec<InvoiceLangModel>) {
             .iter()
             .map(|localized_item| InvoiceLangModel {
                 id_invoice: invoice.invoice_id(),
-                id_lang: localized_item.language().id(),
+                id_lang: profile::language_id(
+                    localized_item.language().locale().as_str(),
+                    localized_item.language().id(),
+                ),
                 type_name: localized_item.name().as_str().to_string(),
             })
             .collect();
@@ -73,6 +104,8 @@ impl From<Invoice> for (InvoiceModel, Vec<InvoiceLangModel>) {
                 po_ref: invoice.po_ref().map(|s| s.to_string()),
                 total_tax_excl: invoice.total_tax_excl(),
                 total_tax_incl: invoice.total_tax_incl(),
+                currency: invoice.currency().code().to_string(),
+                document_type: invoice.document_type().as_str().to_string(),
             },
             invoice_types,
         )
@@ -87,6 +120,7 @@ mod tests {
         domain::{
             invoice::tests::invoice_fixtures,
             vo::localized_item::{tests::localized_item_fixtures, LocalizedItem},
+            DomainError,
         },
         infrastructure::{
             csv_reader::invoice::tests::csv_invoice_dto_fixtures,
@@ -105,7 +139,8 @@ mod tests {
     struct CsvParser;
     impl CsvEntityParser<CsvInvoiceDTO, Invoice> for CsvParser {
         fn transform_csv_row_to_entity(&self, csv: CsvInvoiceDTO) -> Result<Invoice, MappingError> {
-            let mut factory: InvoiceDomainFactory = csv.try_into()?;
+            let mut factory =
+                Rounded::<InvoiceDomainFactory>::try_from_csv(csv, &CsvDialect::default())?.value;
             invoice_types_hashmap_fixture()
                 .contains_key(&factory.invoice_id)
                 .then(|| {
@@ -154,6 +189,29 @@ mod tests {
         assert_eq!(results[2].as_ref().unwrap(), &invoice_fixtures[1]);
     }
 
+    #[test]
+    fn test_convert_dto_currency() {
+        load_unit_test_env();
+        let [dto, _, _] = csv_invoice_dto_fixtures();
+
+        let invoice = CsvParser
+            .parse(CsvInvoiceDTO {
+                currency: "chf".to_string(),
+                ..dto.clone()
+            })
+            .unwrap();
+        assert_eq!(invoice.currency(), Currency::Chf);
+
+        let result = CsvParser.parse(CsvInvoiceDTO {
+            currency: "USD".to_string(),
+            ..dto
+        });
+        assert!(matches!(
+            result,
+            Err(MappingError::Domain(DomainError::ValidationError(_)))
+        ));
+    }
+
     #[test]
     fn test_convert_invoices_to_models() {
         let models_fixtures = invoice_model_fixtures();
@@ -0,0 +1,117 @@
+use crate::{
+    domain::invoice_line::{InvoiceLine, InvoiceLineDomainFactory},
+    infrastructure::{
+        csv_reader::{dialect::CsvDialect, invoice_line::CsvInvoiceLineDTO},
+        database::models::invoice_line::InvoiceLineModel,
+    },
+};
+
+use super::{
+    parse_decimal, parse_decimal_quantity, parse_price, parse_string_to_u32, MappingError, Rounded,
+    TryFromCsv,
+};
+
+impl TryFromCsv<CsvInvoiceLineDTO> for Rounded<InvoiceLineDomainFactory> {
+    fn try_from_csv(
+        dto: CsvInvoiceLineDTO,
+        dialect: &CsvDialect,
+    ) -> Result<Rounded<InvoiceLineDomainFactory>, MappingError> {
+        let number_format = &dialect.number_format;
+
+        let mut rounded = 0;
+        let mut price = |key: &str, value: &str| {
+            let (price, was_rounded) = parse_price(number_format, dialect.rounding, key, value)?;
+            rounded += usize::from(was_rounded);
+            Ok::<_, MappingError>(price)
+        };
+        let unit_price = price("price_actual", &dto.price_actual)?;
+        let total_tax_excl = price("line_total_tax_excl", &dto.line_total_tax_excl)?;
+        let total_tax_incl = price("line_total_tax_incl", &dto.line_total_tax_incl)?;
+
+        let value = InvoiceLineDomainFactory {
+            invoiceline_id: parse_string_to_u32("c_invoiceline_id", &dto.c_invoiceline_id)?,
+            invoice_id: parse_string_to_u32("c_invoice_id", &dto.c_invoice_id)?,
+            product_ref: dto.product_ref,
+            quantity: parse_decimal_quantity(number_format, "qty_invoiced", &dto.qty_invoiced)?,
+            unit_price,
+            tax_rate: parse_decimal(number_format, "tax_rate", &dto.tax_rate)?,
+            total_tax_excl,
+            total_tax_incl,
+        };
+        Ok(Rounded { value, rounded })
+    }
+}
+
+impl From<InvoiceLine> for InvoiceLineModel {
+    fn from(invoice_line: InvoiceLine) -> Self {
+        Self {
+            id_invoice_line: invoice_line.invoiceline_id(),
+            id_invoice: invoice_line.invoice_id(),
+            product_ref: invoice_line.product_ref().to_string(),
+            quantity: invoice_line.quantity(),
+            unit_price: invoice_line.unit_price(),
+            tax_rate: invoice_line.tax_rate(),
+            total_tax_excl: invoice_line.total_tax_excl(),
+            total_tax_incl: invoice_line.total_tax_incl(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::{
+            invoice_line::tests::invoice_line_fixtures,
+            vo::{document_type::DocumentType, price::Currency},
+        },
+        infrastructure::{
+            csv_reader::invoice_line::tests::csv_invoice_line_dto_fixtures,
+            database::models::invoice_line::tests::invoice_line_model_fixtures,
+        },
+        interface_adapters::mappers::convert_domain_entity_to_model,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_convert_dtos_to_invoice_lines() {
+        let [first, _, credit_note] = csv_invoice_line_dto_fixtures();
+        let line = Rounded::<InvoiceLineDomainFactory>::try_from_csv(first, &CsvDialect::default())
+            .unwrap()
+            .value
+            .make(Currency::Eur, DocumentType::Invoice)
+            .unwrap();
+        assert_eq!(line, invoice_line_fixtures()[0]);
+
+        let line =
+            Rounded::<InvoiceLineDomainFactory>::try_from_csv(credit_note, &CsvDialect::default())
+                .unwrap()
+                .value
+                .make(Currency::Eur, DocumentType::CreditNote)
+                .unwrap();
+        assert_eq!(line, invoice_line_fixtures()[2]);
+    }
+
+    #[test]
+    fn test_convert_dto_with_invalid_tax_rate() {
+        let [first, _, _] = csv_invoice_line_dto_fixtures();
+        let result = Rounded::<InvoiceLineDomainFactory>::try_from_csv(
+            CsvInvoiceLineDTO {
+                tax_rate: "twenty".to_string(),
+                ..first
+            },
+            &CsvDialect::default(),
+        );
+        assert!(
+            matches!(result, Err(MappingError::Parsing(message)) if message.contains("tax_rate"))
+        );
+    }
+
+    #[test]
+    fn test_convert_invoice_lines_to_models() {
+        let results: Vec<InvoiceLineModel> =
+            convert_domain_entity_to_model(invoice_line_fixtures().to_vec());
+
+        assert_eq!(results, invoice_line_model_fixtures().to_vec());
+    }
+}
@@ -1,30 +1,37 @@
-use std::env;
-
 use crate::{
     domain::{
         dto::date_dto::{DateDTO, StringDateDTO},
         order::{Order, OrderDomainFactory},
         vo::completion::Completion,
     },
-    infrastructure::{csv_reader::order::CsvOrderDTO, InfrastructureError},
+    infrastructure::csv_reader::{dialect::CsvDialect, order::CsvOrderDTO},
 };
 use chrono::NaiveDateTime;
 
 use crate::infrastructure::database::models::order::OrderModel;
 
-use super::{convert_string_to_option_string, parse_string_to_u32, MappingError};
-
-impl TryFrom<CsvOrderDTO> for OrderDomainFactory {
-    type Error = MappingError;
-    fn try_from(dto: CsvOrderDTO) -> Result<OrderDomainFactory, MappingError> {
-        let date_format = env::var("CSV_DATE_FORMAT")
-            .map_err(|e| MappingError::Infrastructure(InfrastructureError::EnvVarError(e)))?;
+use super::{
+    convert_string_to_option_string, normalize_number, parse_string_to_u32, MappingError,
+    TryFromCsv,
+};
 
+impl TryFromCsv<CsvOrderDTO> for OrderDomainFactory {
+    fn try_from_csv(
+        dto: CsvOrderDTO,
+        dialect: &CsvDialect,
+    ) -> Result<OrderDomainFactory, MappingError> {
         let completion = convert_string_to_option_string(dto.completion)
-            .map(Completion::try_from)
+            .map(|completion| {
+                let completion = normalize_number(
+                    &dialect.number_format,
+                    "completion",
+                    completion.trim().trim_end_matches('%'),
+                )?;
+                Completion::try_from(completion).map_err(MappingError::from)
+            })
             .transpose()?;
 
-        let date_dto = DateDTO::from(StringDateDTO::new(dto.date, date_format));
+        let date_dto = DateDTO::from(StringDateDTO::new(dto.date, dialect.date_format.clone()));
         Ok(OrderDomainFactory {
             order_id: parse_string_to_u32("c_order_id", &dto.c_order_id)?,
             client_id: parse_string_to_u32("c_bpartner_id", &dto.c_bpartner_id)?,
@@ -48,6 +55,7 @@ impl From<Order> for OrderModel {
             order_ref: order.order_ref().to_string(),
             po_ref: order.po_ref().map(|s| s.to_string()),
             completion: order.completion(),
+            csv_completion: order.completion(),
             date: NaiveDateTime::new(
                 order.date(),
                 chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
@@ -98,7 +106,7 @@ mod tests {
     impl ModelToEntityParser<OrderModel, Order> for ModelParser {}
     impl CsvEntityParser<CsvOrderDTO, Order> for CsvParser {
         fn transform_csv_row_to_entity(&self, csv: CsvOrderDTO) -> Result<Order, MappingError> {
-            let factory: OrderDomainFactory = csv.try_into()?;
+            let factory = OrderDomainFactory::try_from_csv(csv, &CsvDialect::default())?;
             factory.make().map_err(MappingError::Domain)
         }
     }
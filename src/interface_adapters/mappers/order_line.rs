@@ -1,33 +1,62 @@
-use std::env;
+use log::warn;
 
 use crate::{
     domain::{
-        order_line::{OrderLine, OrderLineLocalizedItemFactory, OrderLinePrimaryFields},
-        vo::{locale::Locale, Translation},
+        order_line::{
+            OrderLine, OrderLineDomainFactory, OrderLineLocalizedItemFactory,
+            OrderLinePrimaryFields,
+        },
+        vo::{locale::Locale, unit_of_measure::UnitOfMeasure, Translation},
     },
     infrastructure::{
-        csv_reader::order_line::{CsvOrderLineDTO, CsvOrderLineLocalizedItemDTO},
+        csv_reader::{
+            dialect::CsvDialect,
+            order_line::{CsvOrderLineDTO, CsvOrderLineLocalizedItemDTO},
+        },
         database::models::order_line::{OrderLineLangModel, OrderLineModel},
-        InfrastructureError,
+        profile, report,
     },
 };
 
-use super::{convert_string_to_option_date, parse_string_to_u32, MappingError};
+use super::{
+    convert_string_to_option_date, convert_string_to_option_string, parse_decimal_quantity,
+    parse_string_to_u32, MappingError, TryFromCsv,
+};
 
-impl TryFrom<CsvOrderLineDTO> for OrderLinePrimaryFields {
-    type Error = MappingError;
-    fn try_from(dto: CsvOrderLineDTO) -> Result<OrderLinePrimaryFields, MappingError> {
-        let date_format = env::var("CSV_DATE_FORMAT")
-            .map_err(|e| MappingError::Infrastructure(InfrastructureError::EnvVarError(e)))?;
+/// Broken rules configured as warnings are logged and counted, the line being kept
+pub fn make_order_line(factory: OrderLineDomainFactory) -> Result<OrderLine, MappingError> {
+    let orderline_id = factory.orderline_id;
+    let (order_line, warnings) = factory.make_with_warnings()?;
+    if !warnings.is_empty() {
+        warn!("Order line {}: {}", orderline_id, warnings.join("; "));
+        report::record("order_line", |r| r.warned += 1);
+    }
+    Ok(order_line)
+}
+
+impl TryFromCsv<CsvOrderLineDTO> for OrderLinePrimaryFields {
+    fn try_from_csv(
+        dto: CsvOrderLineDTO,
+        dialect: &CsvDialect,
+    ) -> Result<OrderLinePrimaryFields, MappingError> {
+        let number_format = &dialect.number_format;
 
         Ok(OrderLinePrimaryFields {
             order_id: parse_string_to_u32("order_id", &dto.c_order_id)?,
             orderline_id: parse_string_to_u32("orderline_id", &dto.c_orderline_id)?,
             item_ref: dto.item_ref,
-            qty_ordered: parse_string_to_u32("qty_ordered", &dto.qty_ordered)?,
-            qty_reserved: parse_string_to_u32("qty_reserved", &dto.qty_reserved)?,
-            qty_delivered: parse_string_to_u32("qty_delivered", &dto.qty_delivered)?,
-            due_date: convert_string_to_option_date(dto.due_date, &date_format).transpose()?,
+            qty_ordered: parse_decimal_quantity(number_format, "qty_ordered", &dto.qty_ordered)?,
+            qty_reserved: parse_decimal_quantity(number_format, "qty_reserved", &dto.qty_reserved)?,
+            qty_delivered: parse_decimal_quantity(
+                number_format,
+                "qty_delivered",
+                &dto.qty_delivered,
+            )?,
+            uom: convert_string_to_option_string(dto.uom)
+                .map(|uom| UnitOfMeasure::try_from(uom.as_str()))
+                .transpose()?,
+            due_date: convert_string_to_option_date(dto.due_date, &dialect.date_format)
+                .transpose()?,
         })
     }
 }
@@ -52,7 +81,10 @@ impl From<OrderLine> for (OrderLineModel, Vec<OrderLineLangModel>) {
             .iter()
             .map(|item_name| OrderLineLangModel {
                 id_order_line: order_line.orderline_id(),
-                id_lang: item_name.language().id(),
+                id_lang: profile::language_id(
+                    item_name.language().locale().as_str(),
+                    item_name.language().id(),
+                ),
                 product_name: item_name.name().as_str().to_string(),
             })
             .collect();
@@ -64,6 +96,7 @@ impl From<OrderLine> for (OrderLineModel, Vec<OrderLineLangModel>) {
                 qty_ordered: order_line.qty_ordered(),
                 qty_reserved: order_line.qty_reserved(),
                 qty_delivered: order_line.qty_delivered(),
+                uom: order_line.uom().map(|uom| uom.as_str().to_string()),
                 due_date: order_line.due_date(),
             },
             order_line_items,
@@ -78,7 +111,7 @@ mod tests {
     use crate::{
         domain::{
             order::Order,
-            order_line::{tests::order_line_fixtures, OrderLineDomainFactory},
+            order_line::tests::order_line_fixtures,
             vo::localized_item::{tests::localized_item_fixtures, LocalizedItem},
         },
         infrastructure::{
@@ -100,7 +133,7 @@ mod tests {
             &self,
             csv: CsvOrderLineDTO,
         ) -> Result<OrderLine, MappingError> {
-            let raw_fields: Result<OrderLinePrimaryFields, MappingError> = csv.try_into();
+            let raw_fields = OrderLinePrimaryFields::try_from_csv(csv, &CsvDialect::default());
             raw_fields.and_then(|fields| {
                 let order_model = mock_fetching_order(&fields.order_id);
                 let order: Order = order_model.try_into()?;
@@ -113,7 +146,7 @@ mod tests {
                             .unwrap()
                             .to_owned();
                     });
-                factory.make().map_err(MappingError::Domain)
+                make_order_line(factory)
             })
         }
     }
@@ -173,6 +206,34 @@ mod tests {
         assert!(result.is_err_and(|e| matches!(e, MappingError::Parsing(_))));
     }
 
+    #[test]
+    fn test_convert_csv_dto_with_fractional_quantities() {
+        load_unit_test_env();
+
+        let dto_fixture = CsvOrderLineDTO {
+            qty_ordered: "2.5".to_string(),
+            qty_reserved: "0".to_string(),
+            qty_delivered: "-1".to_string(),
+            uom: "mtr".to_string(),
+            ..csv_order_line_dto_fixtures()[0].clone()
+        };
+        let fields =
+            OrderLinePrimaryFields::try_from_csv(dto_fixture.clone(), &CsvDialect::default())
+                .unwrap();
+        assert_eq!(fields.qty_ordered, rust_decimal::Decimal::new(25, 1));
+        assert_eq!(fields.qty_delivered, rust_decimal::Decimal::from(-1));
+        assert_eq!(fields.uom.unwrap().as_str(), "MTR");
+
+        let invalid = CsvOrderLineDTO {
+            uom: "m²".to_string(),
+            ..dto_fixture
+        };
+        assert!(
+            OrderLinePrimaryFields::try_from_csv(invalid, &CsvDialect::default())
+                .is_err_and(|e| matches!(e, MappingError::Domain(_)))
+        );
+    }
+
     #[test]
     fn test_convert_order_lines_to_models() {
         let models_fixtures = order_line_model_fixtures();
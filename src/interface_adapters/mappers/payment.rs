@@ -0,0 +1,203 @@
+use crate::{
+    domain::{
+        dto::date_dto::{DateDTO, StringDateDTO},
+        payment::{InvoiceOpenAmount, Payment, PaymentAllocationFactory, PaymentDomainFactory},
+        vo::price::{Currency, Price},
+    },
+    infrastructure::{
+        csv_reader::{
+            dialect::CsvDialect,
+            payment::{CsvPaymentAllocationDTO, CsvPaymentDTO},
+        },
+        database::models::{
+            invoice::InvoiceOpenAmountModel,
+            payment::{PaymentAllocationModel, PaymentModel},
+        },
+    },
+};
+
+use super::{
+    convert_string_to_option_string, parse_price, parse_string_to_u32, MappingError, Rounded,
+    TryFromCsv,
+};
+
+impl TryFromCsv<CsvPaymentDTO> for Rounded<PaymentDomainFactory> {
+    fn try_from_csv(
+        dto: CsvPaymentDTO,
+        dialect: &CsvDialect,
+    ) -> Result<Rounded<PaymentDomainFactory>, MappingError> {
+        let currency = match convert_string_to_option_string(dto.currency) {
+            Some(code) => Currency::try_from(code.as_str())?,
+            None => Currency::Eur,
+        };
+        let (amount, rounded) = parse_price(
+            &dialect.number_format,
+            dialect.rounding,
+            "pay_amt",
+            &dto.pay_amt,
+        )?;
+
+        let value = PaymentDomainFactory {
+            payment_id: parse_string_to_u32("c_payment_id", &dto.c_payment_id)?,
+            payment_ref: dto.documentno,
+            date_dto: DateDTO::from(StringDateDTO::new(
+                dto.date_trx,
+                dialect.date_format.clone(),
+            )),
+            amount: amount.with_currency(currency),
+        };
+        Ok(Rounded {
+            value,
+            rounded: usize::from(rounded),
+        })
+    }
+}
+
+impl TryFromCsv<CsvPaymentAllocationDTO> for Rounded<PaymentAllocationFactory> {
+    fn try_from_csv(
+        dto: CsvPaymentAllocationDTO,
+        dialect: &CsvDialect,
+    ) -> Result<Rounded<PaymentAllocationFactory>, MappingError> {
+        let mut rounded = 0;
+        let mut price = |key: &str, value: String| {
+            let Some(value) = convert_string_to_option_string(value) else {
+                return Ok(Price::new(0, Currency::Eur));
+            };
+            let (price, was_rounded) =
+                parse_price(&dialect.number_format, dialect.rounding, key, &value)?;
+            rounded += usize::from(was_rounded);
+            Ok::<_, MappingError>(price)
+        };
+        let amount = price("amount", dto.amount)?;
+        let discount = price("discount_amt", dto.discount_amt)?;
+        let write_off = price("write_off_amt", dto.write_off_amt)?;
+
+        let value = PaymentAllocationFactory {
+            allocationline_id: parse_string_to_u32(
+                "c_allocationline_id",
+                &dto.c_allocationline_id,
+            )?,
+            payment_id: parse_string_to_u32("c_payment_id", &dto.c_payment_id)?,
+            invoice_id: parse_string_to_u32("c_invoice_id", &dto.c_invoice_id)?,
+            amount,
+            discount,
+            write_off,
+        };
+        Ok(Rounded { value, rounded })
+    }
+}
+
+impl From<Payment> for (PaymentModel, Vec<PaymentAllocationModel>) {
+    fn from(payment: Payment) -> Self {
+        let allocations = payment
+            .allocations()
+            .iter()
+            .map(|allocation| PaymentAllocationModel {
+                id_allocation_line: allocation.allocationline_id(),
+                id_payment: allocation.payment_id(),
+                id_invoice: allocation.invoice_id(),
+                amount: allocation.amount(),
+                discount: allocation.discount(),
+                write_off: allocation.write_off(),
+            })
+            .collect();
+        (
+            PaymentModel {
+                id_payment: payment.payment_id(),
+                payment_ref: payment.payment_ref().to_string(),
+                date: *payment.date(),
+                amount: payment.amount(),
+                currency: payment.currency().code().to_string(),
+            },
+            allocations,
+        )
+    }
+}
+
+impl From<InvoiceOpenAmount> for InvoiceOpenAmountModel {
+    fn from(open_amount: InvoiceOpenAmount) -> Self {
+        Self {
+            id_invoice: open_amount.invoice_id(),
+            open_amount: open_amount.open_amount(),
+            is_paid: open_amount.is_paid(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        domain::payment::tests::{payment_allocation_fixtures, payment_fixtures},
+        infrastructure::{
+            csv_reader::payment::tests::{
+                csv_payment_allocation_dto_fixtures, csv_payment_dto_fixtures,
+            },
+            database::models::payment::tests::{
+                payment_allocation_model_fixtures, payment_model_fixtures,
+            },
+        },
+        interface_adapters::mappers::convert_domain_entity_to_model,
+        tests::load_unit_test_env,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_convert_dtos_to_payments() {
+        load_unit_test_env();
+        let payments = csv_payment_dto_fixtures()
+            .into_iter()
+            .zip(csv_payment_allocation_dto_fixtures())
+            .map(|(payment, allocation)| {
+                let factory =
+                    Rounded::<PaymentDomainFactory>::try_from_csv(payment, &CsvDialect::default())
+                        .unwrap()
+                        .value;
+                let allocation = Rounded::<PaymentAllocationFactory>::try_from_csv(
+                    allocation,
+                    &CsvDialect::default(),
+                )
+                .unwrap()
+                .value
+                .make(factory.currency(), Currency::Eur)
+                .unwrap();
+                factory.make(vec![allocation]).unwrap()
+            })
+            .collect::<Vec<Payment>>();
+
+        assert_eq!(payments, payment_fixtures().to_vec());
+        assert_eq!(
+            payments[1].allocations(),
+            &[payment_allocation_fixtures()[1].clone()]
+        );
+    }
+
+    #[test]
+    fn test_convert_dto_with_invalid_amount() {
+        load_unit_test_env();
+        let [first, _, _] = csv_payment_allocation_dto_fixtures();
+        let result = Rounded::<PaymentAllocationFactory>::try_from_csv(
+            CsvPaymentAllocationDTO {
+                amount: "paid".to_string(),
+                ..first
+            },
+            &CsvDialect::default(),
+        );
+        assert!(
+            matches!(result, Err(MappingError::Parsing(message)) if message.contains("amount"))
+        );
+    }
+
+    #[test]
+    fn test_convert_payments_to_models() {
+        let results: Vec<(PaymentModel, Vec<PaymentAllocationModel>)> =
+            convert_domain_entity_to_model(payment_fixtures().to_vec());
+
+        let expected: Vec<(PaymentModel, Vec<PaymentAllocationModel>)> = payment_model_fixtures()
+            .into_iter()
+            .zip(payment_allocation_model_fixtures())
+            .map(|(payment, allocation)| (payment, vec![allocation]))
+            .collect();
+        assert_eq!(results, expected);
+    }
+}
@@ -6,13 +6,18 @@ use crate::{
 };
 
 pub(crate) mod clear_product;
+pub(crate) mod doctor;
 pub(crate) mod helpers;
 pub(crate) mod import_delivery_slip;
 pub(crate) mod import_invoice;
+pub(crate) mod import_invoice_line;
 pub(crate) mod import_mapping_client;
 pub(crate) mod import_order;
 pub(crate) mod import_order_line;
+pub(crate) mod import_payment;
 pub(crate) mod import_product;
+pub(crate) mod purge;
+pub(crate) mod sync_deletes;
 pub trait ExecutableUseCase {
     fn execute(&self) -> Option<Vec<UseCaseError>>;
 }
@@ -0,0 +1,373 @@
+use std::fs::File;
+use std::str::FromStr;
+
+use crate::infrastructure::{
+    config::Config,
+    csv_reader::{CsvSources, CsvType},
+    database::{
+        connection::Database,
+        schema_check::{check_tables, Drift, Severity},
+    },
+    profile, InfrastructureError,
+};
+
+/// Entity as named by the import command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Entity {
+    MappingClient,
+    Order,
+    Orderline,
+    DeliverySlip,
+    Invoice,
+    InvoiceLine,
+    Payment,
+    Product,
+}
+
+impl FromStr for Entity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Entity::all()
+            .into_iter()
+            .find(|entity| entity.name() == value)
+            .ok_or_else(|| format!("Unknown entity {}", value))
+    }
+}
+
+impl Entity {
+    pub fn all() -> Vec<Entity> {
+        vec![
+            Entity::MappingClient,
+            Entity::Order,
+            Entity::Orderline,
+            Entity::DeliverySlip,
+            Entity::Invoice,
+            Entity::InvoiceLine,
+            Entity::Payment,
+            Entity::Product,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Entity::MappingClient => "mapping-client",
+            Entity::Order => "order",
+            Entity::Orderline => "orderline",
+            Entity::DeliverySlip => "delivery-slip",
+            Entity::Invoice => "invoice",
+            Entity::InvoiceLine => "invoice-line",
+            Entity::Payment => "payment",
+            Entity::Product => "product",
+        }
+    }
+
+    pub fn sources(&self) -> Vec<CsvType> {
+        match self {
+            Entity::MappingClient => vec![],
+            Entity::Order => vec![CsvType::Order],
+            Entity::Orderline => vec![CsvType::OrderLine, CsvType::OrderLineItem],
+            Entity::DeliverySlip => vec![CsvType::DeliverySlip],
+            Entity::Invoice => vec![CsvType::Invoice, CsvType::InvoiceDocumentType],
+            Entity::InvoiceLine => vec![CsvType::InvoiceLine],
+            Entity::Payment => vec![CsvType::Payment, CsvType::PaymentAllocation],
+            Entity::Product => vec![CsvType::ProductSubstitute],
+        }
+    }
+
+    pub(crate) fn databases(&self) -> Vec<Database> {
+        match self {
+            Entity::Order | Entity::DeliverySlip | Entity::InvoiceLine | Entity::Payment => {
+                vec![Database::Target]
+            }
+            // Language list and staging tables are read from the legacy staging database
+            Entity::MappingClient | Entity::Orderline | Entity::Invoice | Entity::Product => {
+                vec![Database::Target, Database::LegacyStaging]
+            }
+        }
+    }
+
+    // Tables read or written by the import, fluetl_row_hash being used by --incremental
+    pub(crate) fn tables(&self, database: Database) -> Vec<&'static str> {
+        match (self, database) {
+            (Entity::MappingClient, Database::Target) => {
+                vec!["mapping_client_contact", "fluetl_row_hash"]
+            }
+            (Entity::MappingClient, Database::LegacyStaging) => vec!["staging_customer"],
+            (Entity::Order, Database::Target) => vec!["order", "fluetl_row_hash"],
+            (Entity::Orderline, Database::Target) => {
+                vec!["order_line", "order_line_lang", "fluetl_row_hash"]
+            }
+            (Entity::DeliverySlip, Database::Target) => vec!["delivery_slip", "fluetl_row_hash"],
+            // Open amounts already derived are updated with the new totals
+            (Entity::Invoice, Database::Target) => vec![
+                "invoice",
+                "invoice_lang",
+                "payment_allocation",
+                "invoice_open_amount",
+                "fluetl_row_hash",
+            ],
+            // Parent invoices are read to check currencies, signs and totals
+            (Entity::InvoiceLine, Database::Target) => {
+                vec!["invoice_line", "invoice", "fluetl_row_hash"]
+            }
+            // Open amounts are derived from the invoices and all stored allocations
+            (Entity::Payment, Database::Target) => vec![
+                "payment",
+                "payment_allocation",
+                "invoice",
+                "invoice_open_amount",
+                "fluetl_row_hash",
+            ],
+            (Entity::Orderline | Entity::Invoice, Database::LegacyStaging) => {
+                vec!["language_list"]
+            }
+            (Entity::Product, Database::Target) => vec!["product_substitute"],
+            (Entity::Product, Database::LegacyStaging) => vec!["staging_product"],
+            (
+                Entity::Order | Entity::DeliverySlip | Entity::InvoiceLine | Entity::Payment,
+                Database::LegacyStaging,
+            ) => vec![],
+        }
+    }
+
+    /// Differences between the diesel schema and the tables used by the import
+    pub fn schema_drifts(&self) -> Result<Vec<Drift>, InfrastructureError> {
+        let mut drifts = Vec::new();
+        for database in self.databases() {
+            drifts.extend(check_tables(database, &self.tables(database))?);
+        }
+        Ok(drifts)
+    }
+
+    pub fn required_variables(&self) -> Vec<&'static str> {
+        let mut variables = vec!["LOG_DIRECTORY"];
+        if matches!(
+            self,
+            Entity::Order
+                | Entity::Orderline
+                | Entity::DeliverySlip
+                | Entity::Invoice
+                | Entity::InvoiceLine
+                | Entity::Payment
+        ) {
+            variables.push("CSV_DATE_FORMAT");
+        }
+        variables.extend(self.sources().iter().filter_map(CsvType::env_var));
+        variables.extend(self.databases().iter().map(Database::env_var));
+        variables
+    }
+
+    /// Required variables which are neither set nor configured
+    pub fn missing_variables(&self, config: &Config) -> Vec<&'static str> {
+        self.required_variables()
+            .into_iter()
+            .filter(|name| !is_set(config, name))
+            .collect()
+    }
+}
+
+// Whether the configuration has the key of the variable,
+// the target database URL may come from the active target profile
+fn is_set(config: &Config, name: &str) -> bool {
+    match name {
+        "LOG_DIRECTORY" => config.log.directory.is_some(),
+        "CSV_DATE_FORMAT" => config.dialect.date_format.is_some(),
+        _ if name == Database::Target.env_var() => {
+            profile::active_target_url().is_some() || config.databases.target_url.is_some()
+        }
+        _ if name == Database::LegacyStaging.env_var() => {
+            config.databases.legacy_staging_url.is_some()
+        }
+        _ => CsvType::all()
+            .iter()
+            .find(|csv_type| csv_type.env_var() == Some(name))
+            .is_some_and(|csv_type| config.sources.path(csv_type).is_some()),
+    }
+}
+
+/// One line of the doctor checklist
+#[derive(Debug)]
+pub struct Check {
+    pub label: String,
+    pub result: Result<(), String>,
+    pub warnings: Vec<String>,
+}
+
+impl Check {
+    fn new(label: String, result: Result<(), String>) -> Self {
+        Self {
+            label,
+            result,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Checks the configuration, sources and databases needed by the entities
+pub struct DoctorUseCase<'a> {
+    entities: Vec<Entity>,
+    config: &'a Config,
+    sources: &'a CsvSources,
+}
+
+impl<'a> DoctorUseCase<'a> {
+    pub fn new(entities: Vec<Entity>, config: &'a Config, sources: &'a CsvSources) -> Self {
+        Self {
+            entities,
+            config,
+            sources,
+        }
+    }
+
+    pub fn run(&self) -> Vec<Check> {
+        let mut checks = Vec::new();
+
+        let mut variables: Vec<&str> = Vec::new();
+        let mut sources: Vec<CsvType> = Vec::new();
+        let mut databases: Vec<Database> = Vec::new();
+        for entity in &self.entities {
+            variables.extend(entity.required_variables());
+            sources.extend(entity.sources());
+            databases.extend(entity.databases());
+        }
+        dedup(&mut variables);
+        dedup(&mut sources);
+        dedup(&mut databases);
+
+        for name in variables {
+            let result = if is_set(self.config, name) {
+                Ok(())
+            } else {
+                Err("not set".to_string())
+            };
+            checks.push(Check::new(format!("Variable {}", name), result));
+        }
+        for source in sources {
+            checks.push(Check::new(
+                format!("Source {}", source.name()),
+                check_source(self.sources, &source),
+            ));
+        }
+        for database in databases {
+            let mut tables: Vec<&str> = self
+                .entities
+                .iter()
+                .flat_map(|entity| entity.tables(database))
+                .collect();
+            dedup(&mut tables);
+            if database == Database::Target {
+                // Each target profile has its own database
+                checks.extend(
+                    profile::for_each_target(|| Some(check_database(database, &tables)))
+                        .into_iter()
+                        .map(|(_, check)| check),
+                );
+            } else {
+                checks.extend(check_database(database, &tables));
+            }
+        }
+        checks
+    }
+}
+
+fn dedup<T: PartialEq>(values: &mut Vec<T>) {
+    let mut unique = Vec::new();
+    for value in values.drain(..) {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    *values = unique;
+}
+
+fn check_source(sources: &CsvSources, source: &CsvType) -> Result<(), String> {
+    let files = sources.files(source).map_err(|e| format!("{:?}", e))?;
+    for file in files {
+        File::open(&file).map_err(|e| format!("{} is not readable: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+// Connection check, then one check per table, failing on blocking drifts
+fn check_database(database: Database, tables: &[&str]) -> Vec<Check> {
+    let name = match (database, profile::active_name()) {
+        (Database::Target, Some(profile)) => format!("{}@{}", database.name(), profile),
+        _ => database.name().to_string(),
+    };
+    let label = format!("Database {}", name);
+    let drifts = match check_tables(database, tables) {
+        Ok(drifts) => drifts,
+        Err(e) => return vec![Check::new(label, Err(format!("{:?}", e)))],
+    };
+    let mut checks = vec![Check::new(label, Ok(()))];
+
+    for table in tables {
+        let (errors, warnings): (Vec<&Drift>, Vec<&Drift>) = drifts
+            .iter()
+            .filter(|drift| drift.table == *table)
+            .partition(|drift| drift.severity == Severity::Error);
+        let result = if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors
+                .iter()
+                .map(|drift| drift.to_string())
+                .collect::<Vec<_>>()
+                .join("; "))
+        };
+        let mut check = Check::new(format!("Table {}.{}", name, table), result);
+        check.warnings = warnings.iter().map(|drift| drift.to_string()).collect();
+        checks.push(check);
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::database::schema_check::shapes;
+
+    #[test]
+    fn test_entity_from_str() {
+        assert_eq!("delivery-slip".parse::<Entity>(), Ok(Entity::DeliverySlip));
+        assert_eq!(
+            "mapping-client".parse::<Entity>(),
+            Ok(Entity::MappingClient)
+        );
+        assert!("orders".parse::<Entity>().is_err());
+    }
+
+    #[test]
+    fn test_entity_tables_are_in_schema() {
+        for entity in Entity::all() {
+            for database in entity.databases() {
+                let shapes = shapes(database);
+                for table in entity.tables(database) {
+                    assert!(
+                        shapes.iter().any(|shape| shape.name == table),
+                        "{} is not in the {} schema",
+                        table,
+                        database.name()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_missing_variables() {
+        let mut config = Config::default();
+        config.log.directory = Some("logs".to_string());
+        config.dialect.date_format = Some("%Y-%m-%d".to_string());
+        config.databases.target_url = Some("mysql://localhost/target".to_string());
+
+        assert_eq!(
+            Entity::DeliverySlip.missing_variables(&config),
+            vec!["DELIVERY_SLIPS_CSV_PATH"]
+        );
+
+        config.sources.delivery_slips = Some("exports/delivery_slips.csv".to_string());
+        assert!(Entity::DeliverySlip.missing_variables(&config).is_empty());
+    }
+}
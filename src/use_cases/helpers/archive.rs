@@ -0,0 +1,212 @@
+use log::{debug, info, warn};
+
+use crate::{
+    infrastructure::{
+        archive::{ArchiveOutcome, Archiver},
+        csv_reader::{CsvSources, CsvType},
+        InfrastructureError,
+    },
+    use_cases::UseCaseError,
+};
+
+/// Archives the source files of an import once it is done, except lookup sources. An import
+/// failed when any row was rejected or not written, its files then go to failed/
+pub(crate) struct SourceArchiver<'a> {
+    sources: Vec<CsvType>,
+    archiver: Option<Archiver>,
+    paths: &'a CsvSources,
+}
+
+impl<'a> SourceArchiver<'a> {
+    pub fn new(sources: Vec<CsvType>, archiver: Option<Archiver>, paths: &'a CsvSources) -> Self {
+        Self {
+            sources,
+            archiver,
+            paths,
+        }
+    }
+
+    pub fn execute<F>(self, import: F) -> Option<Vec<UseCaseError>>
+    where
+        F: FnOnce() -> Option<Vec<UseCaseError>>,
+    {
+        let Some(archiver) = &self.archiver else {
+            debug!("ARCHIVE_DIRECTORY is not set, source files are left in place");
+            return import();
+        };
+
+        // Resolved before the import so a file dropped meanwhile is not archived unread
+        let files: Vec<_> = self
+            .sources
+            .iter()
+            .filter(|csv_type| !csv_type.is_lookup())
+            .flat_map(|csv_type| self.paths.files(csv_type).unwrap_or_default())
+            .collect();
+
+        let mut errors = import().unwrap_or_default();
+
+        // An interrupted import is run again on the same files
+        if errors.iter().any(|e| {
+            matches!(
+                e,
+                UseCaseError::Infrastructure(InfrastructureError::Interrupted)
+            )
+        }) {
+            info!("Import interrupted, source files are left in place");
+            return Some(errors);
+        }
+
+        // Rows rejected by validation are missing from the target as well
+        let outcome = if !errors.is_empty() {
+            ArchiveOutcome::Failed
+        } else {
+            ArchiveOutcome::Imported
+        };
+        for file in files {
+            match archiver.archive(&file, outcome) {
+                Ok(archived) if outcome == ArchiveOutcome::Failed => {
+                    warn!(
+                        "{} was archived as failed to {}",
+                        file.display(),
+                        archived.display()
+                    )
+                }
+                Ok(archived) => info!("{} was archived to {}", file.display(), archived.display()),
+                Err(e) => errors.push(e.into()),
+            }
+        }
+
+        Option::from(errors).filter(|e| !e.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{domain::DomainError, infrastructure::archive::ArchiveMode};
+
+    fn source_archiver<'a>(
+        csv: &Path,
+        archive_directory: &Path,
+        paths: &'a CsvSources,
+    ) -> SourceArchiver<'a> {
+        SourceArchiver::new(
+            vec![CsvType::Test(csv.to_path_buf())],
+            Some(Archiver::new(
+                archive_directory.to_path_buf(),
+                ArchiveMode::Move,
+                false,
+                "ab12",
+            )),
+            paths,
+        )
+    }
+
+    fn archived_files(directory: &Path) -> Vec<String> {
+        fs::read_dir(directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_file())
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_archive_imported_source() {
+        let directory = tempdir().unwrap();
+        let csv = directory.path().join("orders.csv");
+        fs::write(&csv, "c_order_id\n1\n").unwrap();
+        let archive_directory = directory.path().join("archive");
+
+        let errors =
+            source_archiver(&csv, &archive_directory, &CsvSources::default()).execute(|| None);
+
+        assert!(errors.is_none(), "Failed to archive: {:?}", errors);
+        assert!(!csv.exists());
+        let archived = archived_files(&archive_directory);
+        assert_eq!(archived.len(), 1);
+        assert!(archived[0].starts_with("orders_") && archived[0].ends_with("_ab12.csv"));
+    }
+
+    #[test]
+    fn test_archive_failed_source() {
+        let directory = tempdir().unwrap();
+        let csv = directory.path().join("orders.csv");
+        fs::write(&csv, "c_order_id\n1\n").unwrap();
+        let archive_directory = directory.path().join("archive");
+
+        let errors =
+            source_archiver(&csv, &archive_directory, &CsvSources::default()).execute(|| {
+                Some(vec![UseCaseError::Infrastructure(
+                    InfrastructureError::NotImplementedError("failure".to_string()),
+                )])
+            });
+
+        assert_eq!(errors.map(|e| e.len()), Some(1));
+        assert!(archived_files(&archive_directory).is_empty());
+        assert_eq!(archived_files(&archive_directory.join("failed")).len(), 1);
+    }
+
+    #[test]
+    fn test_archive_source_with_rejected_rows_as_failed() {
+        let directory = tempdir().unwrap();
+        let csv = directory.path().join("orders.csv");
+        fs::write(&csv, "c_order_id\n1\n").unwrap();
+        let archive_directory = directory.path().join("archive");
+
+        let errors =
+            source_archiver(&csv, &archive_directory, &CsvSources::default()).execute(|| {
+                Some(vec![UseCaseError::Domain(DomainError::ValidationError(
+                    "rejected row".to_string(),
+                ))])
+            });
+
+        assert_eq!(errors.map(|e| e.len()), Some(1));
+        assert!(archived_files(&archive_directory).is_empty());
+        assert_eq!(archived_files(&archive_directory.join("failed")).len(), 1);
+    }
+
+    #[test]
+    fn test_interrupted_source_is_left_in_place() {
+        let directory = tempdir().unwrap();
+        let csv = directory.path().join("orders.csv");
+        fs::write(&csv, "c_order_id\n1\n").unwrap();
+        let archive_directory = directory.path().join("archive");
+
+        let errors = source_archiver(&csv, &archive_directory, &CsvSources::default())
+            .execute(|| Some(vec![InfrastructureError::Interrupted.into()]));
+
+        assert_eq!(errors.map(|e| e.len()), Some(1));
+        assert!(csv.exists());
+        assert!(archived_files(&archive_directory.join("failed")).is_empty());
+    }
+
+    #[test]
+    fn test_lookup_source_is_left_in_place() {
+        let archive_directory = tempdir().unwrap();
+        let paths = CsvSources::default();
+        let archiver = SourceArchiver::new(
+            vec![CsvType::InvoiceDocumentType],
+            Some(Archiver::new(
+                archive_directory.path().to_path_buf(),
+                ArchiveMode::Move,
+                false,
+                "ab12",
+            )),
+            &paths,
+        );
+
+        // The lookup path is not even resolved
+        let errors = archiver.execute(|| None);
+
+        assert!(errors.is_none(), "Failed to archive: {:?}", errors);
+        assert!(archived_files(archive_directory.path()).is_empty());
+    }
+}
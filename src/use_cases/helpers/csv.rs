@@ -1,7 +1,9 @@
 use crate::{
     domain::DomainEntity,
     infrastructure::{
-        csv_reader::CsvDTO, data_source::CanReadCSVDataSource, database::models::CanUpsertModel,
+        csv_reader::CsvDTO,
+        data_source::CanReadCSVDataSource,
+        database::models::{CanHashModel, CanUpsertModel},
     },
     interface_adapters::mappers::CsvEntityParser,
     use_cases::UseCaseError,
@@ -16,8 +18,8 @@ pub(crate) trait ImportFromSingleEntityBasedCsvUseCase<CSV, DE, M>:
     CanReadCSVDataSource<CSV> + CsvEntityParser<CSV, DE> + CanPersistIntoDatabaseUseCase<DE, M>
 where
     CSV: CsvDTO + for<'a> Deserialize<'a> + Debug,
-    DE: DomainEntity + Into<M>,
-    M: CanUpsertModel,
+    DE: DomainEntity + Into<M> + Clone,
+    M: CanUpsertModel + CanHashModel,
 {
     fn execute(&self) -> Option<Vec<UseCaseError>> {
         let data = self.find_all();
@@ -34,7 +36,7 @@ where
             .filter_map(|entity| entity.map_err(|e| domain_errors.push(e)).ok())
             .collect();
 
-        let database_errors = self.persist(entities);
+        let database_errors = self.persist_to_targets(entities);
 
         let mut errors: Vec<UseCaseError> = domain_errors.into_iter().map(|e| e.into()).collect();
         errors.append(
@@ -0,0 +1,205 @@
+use log::{debug, info};
+
+use crate::{
+    infrastructure::{
+        csv_reader::{CsvError, CsvSources, CsvType},
+        fingerprint::{Fingerprint, FingerprintStore},
+        InfrastructureError,
+    },
+    interface_adapters::mappers::MappingError,
+    use_cases::UseCaseError,
+};
+
+/// Skips an import when none of its CSV sources changed since the last import without failure,
+/// rows rejected by validation aside
+pub(crate) struct SourceFingerprintGuard<'a> {
+    sources: Vec<CsvType>,
+    force: bool,
+    store: Option<FingerprintStore>,
+    paths: &'a CsvSources,
+}
+
+impl<'a> SourceFingerprintGuard<'a> {
+    pub fn new(
+        sources: Vec<CsvType>,
+        force: bool,
+        store: Option<FingerprintStore>,
+        paths: &'a CsvSources,
+    ) -> Self {
+        Self {
+            sources,
+            force,
+            store,
+            paths,
+        }
+    }
+
+    pub fn execute<F>(self, import: F) -> Option<Vec<UseCaseError>>
+    where
+        F: FnOnce() -> Option<Vec<UseCaseError>>,
+    {
+        let Some(store) = &self.store else {
+            debug!("FINGERPRINT_DIRECTORY is not set, unchanged source detection disabled");
+            return import();
+        };
+
+        let fingerprints = self.compute();
+        if let Some(fingerprints) = &fingerprints {
+            if !self.force && Self::is_unchanged(store, fingerprints) {
+                info!(
+                    "Source files {} are unchanged since the last successful import, skipping (use --force to import anyway)",
+                    self.source_names()
+                );
+                return None;
+            }
+        }
+
+        let mut errors = import().unwrap_or_default();
+
+        // Rows rejected by validation would be rejected again from the same files, only failed
+        // or interrupted writes must be retried on the next run
+        if let (false, Some(fingerprints)) = (errors.iter().any(is_failure), fingerprints) {
+            for (csv_type, fingerprint) in fingerprints {
+                if let Err(e) = store.save(&csv_type, &fingerprint) {
+                    errors.push(e.into());
+                }
+            }
+        }
+
+        Option::from(errors).filter(|e| !e.is_empty())
+    }
+
+    // None when a source cannot be fingerprinted, the import itself will report the problem
+    fn compute(&self) -> Option<Vec<(CsvType, Fingerprint)>> {
+        self.sources
+            .iter()
+            .map(|csv_type| {
+                self.paths
+                    .files(csv_type)
+                    .and_then(|files| {
+                        Fingerprint::from_files(&files)
+                            .map_err(|e| InfrastructureError::CsvError(CsvError::IOError(e)))
+                    })
+                    .map(|fingerprint| (csv_type.clone(), fingerprint))
+                    .map_err(|e| debug!("Unable to fingerprint {}: {:?}", csv_type.name(), e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    fn is_unchanged(store: &FingerprintStore, fingerprints: &[(CsvType, Fingerprint)]) -> bool {
+        fingerprints
+            .iter()
+            .all(|(csv_type, fingerprint)| store.load(csv_type).as_ref() == Some(fingerprint))
+    }
+
+    fn source_names(&self) -> String {
+        self.sources
+            .iter()
+            .map(|csv_type| csv_type.name())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
+
+fn is_failure(error: &UseCaseError) -> bool {
+    match error {
+        UseCaseError::Domain(_) => false,
+        UseCaseError::Mapping(MappingError::Infrastructure(_)) => true,
+        UseCaseError::Mapping(_) => false,
+        UseCaseError::Infrastructure(_) | UseCaseError::Unknown(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io::Write};
+
+    use tempfile::{tempdir, NamedTempFile};
+
+    use super::*;
+    use crate::domain::DomainError;
+
+    fn guard<'a>(
+        csv: &NamedTempFile,
+        store_directory: &std::path::Path,
+        force: bool,
+        paths: &'a CsvSources,
+    ) -> SourceFingerprintGuard<'a> {
+        SourceFingerprintGuard::new(
+            vec![CsvType::Test(csv.path().to_path_buf())],
+            force,
+            Some(FingerprintStore::new(store_directory.to_path_buf())),
+            paths,
+        )
+    }
+
+    #[test]
+    fn test_skip_unchanged_source() {
+        let directory = tempdir().unwrap();
+        let mut csv = NamedTempFile::new().unwrap();
+        csv.write_all(b"c_order_id\n1\n").unwrap();
+        let runs = Cell::new(0);
+        let import = || {
+            runs.set(runs.get() + 1);
+            None
+        };
+
+        assert!(guard(&csv, directory.path(), false, &CsvSources::default())
+            .execute(import)
+            .is_none());
+        assert!(guard(&csv, directory.path(), false, &CsvSources::default())
+            .execute(import)
+            .is_none());
+        assert_eq!(runs.get(), 1);
+
+        guard(&csv, directory.path(), true, &CsvSources::default()).execute(import);
+        assert_eq!(runs.get(), 2);
+
+        csv.write_all(b"2\n").unwrap();
+        guard(&csv, directory.path(), false, &CsvSources::default()).execute(import);
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn test_failed_import_is_not_fingerprinted() {
+        let directory = tempdir().unwrap();
+        let mut csv = NamedTempFile::new().unwrap();
+        csv.write_all(b"c_order_id\n1\n").unwrap();
+        let runs = Cell::new(0);
+        let failing_import = || {
+            runs.set(runs.get() + 1);
+            Some(vec![UseCaseError::Infrastructure(
+                InfrastructureError::NotImplementedError("failure".to_string()),
+            )])
+        };
+
+        assert!(guard(&csv, directory.path(), false, &CsvSources::default())
+            .execute(failing_import)
+            .is_some());
+        assert!(guard(&csv, directory.path(), false, &CsvSources::default())
+            .execute(failing_import)
+            .is_some());
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn test_import_with_rejected_rows_is_fingerprinted() {
+        let directory = tempdir().unwrap();
+        let mut csv = NamedTempFile::new().unwrap();
+        csv.write_all(b"c_order_id\n1\n").unwrap();
+        let runs = Cell::new(0);
+        let rejecting_import = || {
+            runs.set(runs.get() + 1);
+            Some(vec![UseCaseError::Domain(DomainError::ValidationError(
+                "rejected row".to_string(),
+            ))])
+        };
+
+        assert!(guard(&csv, directory.path(), false, &CsvSources::default())
+            .execute(rejecting_import)
+            .is_some());
+        guard(&csv, directory.path(), false, &CsvSources::default()).execute(rejecting_import);
+        assert_eq!(runs.get(), 1);
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use diesel::result::Error as DieselError;
+use elsa::map::FrozenMap;
+use log::info;
+
+use crate::{
+    domain::{
+        payment::InvoiceOpenAmount,
+        vo::price::{Currency, Price},
+    },
+    infrastructure::{
+        database::{
+            batch::{Batch, BatchConfig},
+            connection::DbConnection,
+            models::{
+                invoice::{batch_upsert_open_amounts, InvoiceModel, InvoiceOpenAmountModel},
+                payment::PaymentAllocationModel,
+            },
+        },
+        InfrastructureError,
+    },
+    interface_adapters::mappers::{convert_domain_entity_to_model, MappingError},
+    use_cases::UseCaseError,
+};
+
+/// Invoices already imported into the target database, each read once per import
+pub(crate) type InvoiceCache = FrozenMap<u32, Box<InvoiceModel>>;
+
+/// None when the invoice was not imported
+pub(crate) fn find_invoice<'a>(
+    cache: &'a InvoiceCache,
+    id: u32,
+    connection: &mut DbConnection,
+) -> Result<Option<&'a InvoiceModel>, MappingError> {
+    if let Some(invoice) = cache.get(&id) {
+        return Ok(Some(invoice));
+    }
+
+    match InvoiceModel::select_by_id(connection, &id) {
+        Ok(invoice) => Ok(Some(cache.insert(id, Box::new(invoice)))),
+        Err(DieselError::NotFound) => Ok(None),
+        Err(e) => Err(MappingError::Infrastructure(
+            InfrastructureError::DatabaseError(e),
+        )),
+    }
+}
+
+/// Derives the open amount of the given invoices from the allocations of the target database,
+/// their total when nothing was allocated
+pub(crate) fn update_open_amounts(
+    mut connection: DbConnection,
+    batch: Option<BatchConfig>,
+    invoice_ids: &[u32],
+) -> Option<Vec<UseCaseError>> {
+    let database_error = |e| {
+        Some(vec![UseCaseError::Infrastructure(
+            InfrastructureError::DatabaseError(e),
+        )])
+    };
+    let invoices = match InvoiceModel::select_totals(&mut connection, invoice_ids) {
+        Ok(invoices) => invoices,
+        Err(e) => return database_error(e),
+    };
+    let amounts =
+        match PaymentAllocationModel::select_settled_by_invoice(&mut connection, invoice_ids) {
+            Ok(amounts) => amounts,
+            Err(e) => return database_error(e),
+        };
+
+    let mut errors = Vec::new();
+    let mut settled: HashMap<u32, Vec<Price>> = HashMap::new();
+    for (invoice_id, amount) in amounts {
+        match Price::try_from(amount.to_string()) {
+            Ok(amount) => settled.entry(invoice_id).or_default().push(amount),
+            Err(e) => errors.push(UseCaseError::Domain(e)),
+        }
+    }
+    let open_amounts: Vec<InvoiceOpenAmount> = invoices
+        .into_iter()
+        .filter_map(|(invoice_id, total_tax_incl, currency)| {
+            let total = Price::try_from(total_tax_incl.to_string())
+                .and_then(|total| Ok(total.with_currency(Currency::try_from(currency.as_str())?)))
+                .map_err(|e| errors.push(UseCaseError::Domain(e)))
+                .ok()?;
+            Some(InvoiceOpenAmount::compute(
+                invoice_id,
+                total,
+                settled.get(&invoice_id).map_or(&[], Vec::as_slice),
+            ))
+        })
+        .collect();
+    let models: Vec<InvoiceOpenAmountModel> = convert_domain_entity_to_model(open_amounts);
+    // Chunked to stay below the placeholders limit of MySQL
+    match Batch::new(&models, batch, batch_upsert_open_amounts, connection).run() {
+        None => info!("{} invoice open amounts updated", models.len()),
+        Some(batch_errors) => errors.extend(
+            batch_errors
+                .into_iter()
+                .map(|e| UseCaseError::Infrastructure(InfrastructureError::DatabaseError(e))),
+        ),
+    }
+    Option::from(errors).filter(|e| !e.is_empty())
+}
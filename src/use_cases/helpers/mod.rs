@@ -1,4 +1,8 @@
+pub(crate) mod archive;
 pub(crate) mod csv;
+pub(crate) mod fingerprint;
+pub(crate) mod invoice;
 pub(crate) mod language;
 pub(crate) mod localized_item;
 pub(crate) mod model;
+pub(crate) mod order;
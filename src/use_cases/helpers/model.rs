@@ -1,3 +1,4 @@
+use diesel::result::Error as DieselError;
 use log::debug;
 use std::fmt::Debug;
 
@@ -6,10 +7,13 @@ use crate::{
     infrastructure::{
         database::{
             batch::Batch,
-            connection::HasConnection,
-            models::{CanSelectAllModel, CanUpsertModel, Model},
+            connection::{DbConnection, HasConnection},
+            models::{
+                row_hash::{self, RowHashModel},
+                CanHashModel, CanSelectAllModel, CanUpsertModel, Model,
+            },
         },
-        InfrastructureError,
+        profile, report, shutdown, InfrastructureError,
     },
     interface_adapters::mappers::{
         convert_domain_entity_to_model, MappingError, ModelToEntityParser,
@@ -23,8 +27,8 @@ pub(crate) trait ImportModelUseCase<M1, DE, M2>:
     + ModelToEntityParser<M1, DE>
 where
     M1: Model + TryInto<DE, Error = MappingError> + Debug,
-    DE: DomainEntity + Into<M2>,
-    M2: CanUpsertModel,
+    DE: DomainEntity + Into<M2> + Clone,
+    M2: CanUpsertModel + CanHashModel,
 {
     fn execute(&self) -> Option<Vec<UseCaseError>> where {
         let data = self.read_all();
@@ -39,7 +43,7 @@ where
             .filter_map(|entity| entity.map_err(|e| domain_errors.push(e)).ok())
             .collect();
 
-        let database_errors = self.persist(entities);
+        let database_errors = self.persist_to_targets(entities);
 
         let mut errors: Vec<UseCaseError> = domain_errors.into_iter().map(|e| e.into()).collect();
         errors.append(
@@ -73,17 +77,50 @@ pub(crate) trait CanReadAllModelUseCase {
 pub(crate) trait CanPersistIntoDatabaseUseCase<DE, M>
 where
     DE: DomainEntity + Into<M>,
-    M: CanUpsertModel,
+    M: CanUpsertModel + CanHashModel,
 {
     type DbConnection: HasConnection;
 
+    /// Entities validated once are written to every selected target profile
+    fn persist_to_targets(&self, entities: Vec<DE>) -> Option<Vec<InfrastructureError>>
+    where
+        DE: Clone,
+    {
+        if !profile::is_selected() {
+            return self.persist(entities);
+        }
+        let errors: Vec<InfrastructureError> =
+            profile::for_each_target(|| self.persist(entities.clone()))
+                .into_iter()
+                .map(|(target, e)| match target {
+                    Some(name) => InfrastructureError::Target(name, Box::new(e)),
+                    None => e,
+                })
+                .collect();
+        Option::from(errors).filter(|e| !e.is_empty())
+    }
+
     fn persist(&self, entities: Vec<DE>) -> Option<Vec<InfrastructureError>> {
         let mut errors: Vec<InfrastructureError> = Vec::new();
         let mut connection = Self::DbConnection::get_pooled_connection();
         let models: Vec<M> = convert_domain_entity_to_model(entities);
 
+        let (models, changes) = if self.is_incremental() {
+            match detect_changes(models, &mut connection) {
+                Ok(changes) => changes,
+                Err(err) => return Some(vec![InfrastructureError::DatabaseError(err)]),
+            }
+        } else {
+            (models, vec![])
+        };
+
+        let mut persisted = vec![true; models.len()];
         if let Some(batch) = self.set_batch(&models) {
             let batch_errors = batch.run();
+            if batch_errors.is_some() || shutdown::is_requested() {
+                // Failed or skipped rows are unknown, they will all be written again on the next run
+                persisted.fill(false);
+            }
             if let Some(batch_errors) = batch_errors {
                 errors.extend(
                     batch_errors
@@ -92,11 +129,44 @@ where
                 );
             }
         } else {
-            for model in models {
-                let _ = model
-                    .upsert(&mut connection)
+            for (model, persisted) in models.iter().zip(persisted.iter_mut()) {
+                if shutdown::is_requested() {
+                    *persisted = false;
+                    continue;
+                }
+                let _ = model.upsert(&mut connection).map_err(|err| {
+                    *persisted = false;
+                    errors.push(InfrastructureError::DatabaseError(err))
+                });
+            }
+        }
+
+        if shutdown::is_requested() {
+            errors.push(InfrastructureError::Interrupted);
+        }
+
+        if self.is_incremental() {
+            let changes: RowChanges = changes
+                .into_iter()
+                .zip(persisted)
+                .filter_map(|(change, persisted)| persisted.then_some(change))
+                .collect();
+            report::record(M::ENTITY, |r| {
+                changes.iter().for_each(|(_, change)| match change {
+                    RowChange::Inserted => r.inserted += 1,
+                    RowChange::Updated => r.updated += 1,
+                })
+            });
+            let row_hashes: Vec<RowHashModel> =
+                changes.into_iter().map(|(row_hash, _)| row_hash).collect();
+            if !row_hashes.is_empty() {
+                let _ = row_hash::batch_upsert(&row_hashes, &mut connection)
                     .map_err(|err| errors.push(InfrastructureError::DatabaseError(err)));
             }
+        } else {
+            report::record(M::ENTITY, |r| {
+                r.written += persisted.iter().filter(|p| **p).count()
+            });
         }
 
         Option::from(errors).filter(|e| !e.is_empty())
@@ -105,4 +175,51 @@ where
     fn set_batch<'a>(&'a self, _models: &'a [M]) -> Option<Batch<M>> {
         None
     }
+
+    /// Only write rows which are new or changed since the previous run
+    fn is_incremental(&self) -> bool {
+        false
+    }
+}
+
+enum RowChange {
+    Inserted,
+    Updated,
+}
+
+type RowChanges = Vec<(RowHashModel, RowChange)>;
+
+// Keep only new and changed models, along with their up to date row hash
+fn detect_changes<M: CanHashModel>(
+    models: Vec<M>,
+    connection: &mut DbConnection,
+) -> Result<(Vec<M>, RowChanges), DieselError> {
+    let stored_hashes = RowHashModel::select_by_entity(connection, M::ENTITY)?;
+    let mut unchanged = 0;
+    let mut changed_models = Vec::new();
+    let mut changes = Vec::new();
+
+    for model in models {
+        let row_hash = RowHashModel::from_model(&model);
+        let change = match stored_hashes.get(&row_hash.row_key) {
+            None => RowChange::Inserted,
+            Some(stored) if *stored != row_hash.row_hash => RowChange::Updated,
+            Some(_) => {
+                unchanged += 1;
+                continue;
+            }
+        };
+        changed_models.push(model);
+        changes.push((row_hash, change));
+    }
+
+    debug!(
+        "{} {} rows to write, {} unchanged",
+        changed_models.len(),
+        M::ENTITY,
+        unchanged
+    );
+    report::record(M::ENTITY, |r| r.unchanged += unchanged);
+
+    Ok((changed_models, changes))
 }
@@ -0,0 +1,82 @@
+use std::collections::{BTreeMap, HashMap};
+
+use log::info;
+use rust_decimal::Decimal;
+
+use crate::{
+    domain::vo::completion::Completion,
+    infrastructure::{
+        database::{
+            connection::DbConnection,
+            models::{order::OrderModel, order_line::OrderLineModel},
+        },
+        report, InfrastructureError,
+    },
+    use_cases::UseCaseError,
+};
+
+/// Derives the completion of the given orders from their lines in the target database.
+/// Orders without lines, or with nothing ordered, keep the completion of the CSV
+pub(crate) fn update_completions(
+    connection: &mut DbConnection,
+    order_ids: &[u32],
+) -> Option<Vec<UseCaseError>> {
+    let database_error = |e| {
+        Some(vec![UseCaseError::Infrastructure(
+            InfrastructureError::DatabaseError(e),
+        )])
+    };
+    let quantities = match OrderLineModel::select_quantities(connection, order_ids) {
+        Ok(quantities) => quantities,
+        Err(e) => return database_error(e),
+    };
+    let completions: HashMap<u32, (Option<u32>, Option<u32>)> =
+        match OrderModel::select_completions(connection, order_ids) {
+            Ok(completions) => completions
+                .into_iter()
+                .map(|(order_id, completion, csv_completion)| {
+                    (order_id, (completion, csv_completion))
+                })
+                .collect(),
+            Err(e) => return database_error(e),
+        };
+
+    let mut quantities_by_order: BTreeMap<u32, Vec<(Decimal, Decimal)>> = BTreeMap::new();
+    for (order_id, qty_ordered, qty_delivered) in quantities {
+        quantities_by_order
+            .entry(order_id)
+            .or_default()
+            .push((qty_ordered, qty_delivered));
+    }
+    let mut changes = Vec::new();
+    let mut recomputed = 0;
+    for (order_id, quantities) in quantities_by_order {
+        let (Some(completion), Some((stored_completion, csv_completion))) = (
+            Completion::from_quantities(&quantities),
+            completions.get(&order_id),
+        ) else {
+            continue;
+        };
+        if *csv_completion != Some(completion.as_u32()) {
+            info!(
+                "Order {}: completion {}% from its lines, {} in the CSV",
+                order_id,
+                completion.as_u32(),
+                csv_completion.map_or("none".to_string(), |c| format!("{}%", c))
+            );
+            recomputed += 1;
+        }
+        // A previous run may have stored another completion, even one matching the CSV again
+        if *stored_completion != Some(completion.as_u32()) {
+            changes.push((order_id, completion.as_u32()));
+        }
+    }
+    match OrderModel::update_completions(connection, &changes) {
+        Ok(updated) => {
+            info!("{} order completions updated", updated);
+            report::record("order", |r| r.recomputed += recomputed);
+            None
+        }
+        Err(e) => database_error(e),
+    }
+}
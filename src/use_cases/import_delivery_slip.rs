@@ -1,7 +1,7 @@
 use crate::{
     domain::delivery_slip::{DeliverySlip, DeliverySlipDomainFactory},
     infrastructure::{
-        csv_reader::{delivery_slip::CsvDeliverySlipDTO, CsvType},
+        csv_reader::{delivery_slip::CsvDeliverySlipDTO, dialect::CsvDialect, CsvSources, CsvType},
         data_source::CanReadCSVDataSource,
         database::{
             batch::{Batch, BatchConfig},
@@ -9,30 +9,52 @@ use crate::{
             models::delivery_slip::{batch_upsert, DeliverySlipModel},
         },
     },
-    interface_adapters::mappers::CsvEntityParser,
+    interface_adapters::mappers::{CsvEntityParser, TryFromCsv},
 };
 
 use super::{
     helpers::{csv::ImportFromSingleEntityBasedCsvUseCase, model::CanPersistIntoDatabaseUseCase},
+    sync_deletes::{ExtractIds, SyncDeletesUseCase},
     *,
 };
 
 #[derive(Default)]
 pub struct ImportDeliverySlipUseCase {
+    sources: CsvSources,
+    dialect: CsvDialect,
     batch: bool,
     batch_size: usize,
+    incremental: bool,
+    extract_ids: ExtractIds,
 }
 
 impl ImportDeliverySlipUseCase {
+    pub fn new(sources: &CsvSources) -> Result<Self, Vec<UseCaseError>> {
+        Ok(Self {
+            sources: sources.clone(),
+            dialect: sources
+                .dialect(&CsvType::DeliverySlip)
+                .map_err(|e| vec![e.into()])?,
+            ..Self::default()
+        })
+    }
+
     pub fn set_batch(&mut self, batch_size: usize) {
         self.batch = true;
         self.batch_size = batch_size;
     }
+
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
 }
 
 impl CanReadCSVDataSource<CsvDeliverySlipDTO> for ImportDeliverySlipUseCase {
     fn find_all(&self) -> Result<Vec<CsvDeliverySlipDTO>, InfrastructureError> {
-        self.read(CsvType::DeliverySlip)
+        let delivery_slips = self.read(&self.sources, CsvType::DeliverySlip)?;
+        self.extract_ids
+            .keep(delivery_slips.iter().filter_map(Self::source_id));
+        Ok(delivery_slips)
     }
 }
 impl CsvEntityParser<CsvDeliverySlipDTO, DeliverySlip> for ImportDeliverySlipUseCase {
@@ -40,7 +62,7 @@ impl CsvEntityParser<CsvDeliverySlipDTO, DeliverySlip> for ImportDeliverySlipUse
         &self,
         csv: CsvDeliverySlipDTO,
     ) -> Result<DeliverySlip, MappingError> {
-        let factory: DeliverySlipDomainFactory = csv.try_into()?;
+        let factory = DeliverySlipDomainFactory::try_from_csv(csv, &self.dialect)?;
         factory.make().map_err(MappingError::Domain)
     }
 }
@@ -61,12 +83,28 @@ impl CanPersistIntoDatabaseUseCase<DeliverySlip, DeliverySlipModel> for ImportDe
             None
         }
     }
+
+    fn is_incremental(&self) -> bool {
+        self.incremental
+    }
 }
 impl ImportFromSingleEntityBasedCsvUseCase<CsvDeliverySlipDTO, DeliverySlip, DeliverySlipModel>
     for ImportDeliverySlipUseCase
 {
 }
 
+impl SyncDeletesUseCase<CsvDeliverySlipDTO, DeliverySlipModel> for ImportDeliverySlipUseCase {
+    type DbConnection = HasTargetConnection;
+
+    fn source_id(csv: &CsvDeliverySlipDTO) -> Option<u32> {
+        csv.m_inout_id.parse().ok()
+    }
+
+    fn extract_ids(&self) -> &ExtractIds {
+        &self.extract_ids
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -100,7 +138,7 @@ mod tests {
                 .join("fixtures")
                 .join("delivery_slip_for_unit_test.csv");
 
-            self.read(CsvType::Test(csv_path))
+            self.read(&CsvSources::default(), CsvType::Test(csv_path))
         }
     }
     impl CsvEntityParser<CsvDeliverySlipDTO, DeliverySlip> for ImportDeliverySlipUseCaseTest {
@@ -108,7 +146,7 @@ mod tests {
             &self,
             csv: CsvDeliverySlipDTO,
         ) -> Result<DeliverySlip, MappingError> {
-            let factory: DeliverySlipDomainFactory = csv.try_into()?;
+            let factory = DeliverySlipDomainFactory::try_from_csv(csv, &CsvDialect::default())?;
             factory.make().map_err(MappingError::Domain)
         }
     }
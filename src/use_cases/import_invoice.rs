@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
 
 use crate::{
     domain::{
@@ -7,8 +8,9 @@ use crate::{
     },
     infrastructure::{
         csv_reader::{
+            dialect::CsvDialect,
             invoice::{CsvInvoiceDTO, CsvInvoiceLocalizedItemDTO},
-            CsvType,
+            CsvSources, CsvType,
         },
         data_source::CanReadCSVDataSource,
         database::{
@@ -16,27 +18,31 @@ use crate::{
             connection::{HasConnection, HasTargetConnection},
             models::invoice::{batch_upsert, InvoiceLangModel, InvoiceModel},
         },
+        report,
     },
-    interface_adapters::mappers::CsvEntityParser,
+    interface_adapters::mappers::{CsvEntityParser, Rounded, TryFromCsv},
 };
 
 use super::{
     helpers::{
-        csv::ImportFromSingleEntityBasedCsvUseCase, language::CanFetchLanguages,
-        localized_item::ImportLocalizedItem, model::CanPersistIntoDatabaseUseCase,
+        csv::ImportFromSingleEntityBasedCsvUseCase, invoice::update_open_amounts,
+        language::CanFetchLanguages, localized_item::ImportLocalizedItem,
+        model::CanPersistIntoDatabaseUseCase,
     },
     *,
 };
 
-struct ImportInvoiceTypesUseCase;
-impl CanReadCSVDataSource<CsvInvoiceLocalizedItemDTO> for ImportInvoiceTypesUseCase {
+struct ImportInvoiceTypesUseCase<'a> {
+    sources: &'a CsvSources,
+}
+impl CanReadCSVDataSource<CsvInvoiceLocalizedItemDTO> for ImportInvoiceTypesUseCase<'_> {
     fn find_all(&self) -> Result<Vec<CsvInvoiceLocalizedItemDTO>, InfrastructureError> {
-        self.read(CsvType::InvoiceDocumentType)
+        self.read(self.sources, CsvType::InvoiceDocumentType)
     }
 }
-impl CanFetchLanguages for ImportInvoiceTypesUseCase {}
+impl CanFetchLanguages for ImportInvoiceTypesUseCase<'_> {}
 impl ImportLocalizedItem<InvoiceLocalizedTypeFactory, CsvInvoiceLocalizedItemDTO>
-    for ImportInvoiceTypesUseCase
+    for ImportInvoiceTypesUseCase<'_>
 {
     fn source(&self) -> Result<Vec<CsvInvoiceLocalizedItemDTO>, UseCaseError> {
         self.find_all().map_err(|e| e.into())
@@ -45,16 +51,25 @@ impl ImportLocalizedItem<InvoiceLocalizedTypeFactory, CsvInvoiceLocalizedItemDTO
 
 #[derive(Default)]
 pub struct ImportInvoiceUseCase {
+    sources: CsvSources,
+    dialect: CsvDialect,
     invoice_types: HashMap<u32, Vec<LocalizedItem>>,
+    // Invoices of the accepted rows, whose open amounts are derived again
+    invoice_ids: RefCell<BTreeSet<u32>>,
     batch: bool,
     batch_size: usize,
+    incremental: bool,
 }
 
 impl ImportInvoiceUseCase {
-    pub fn new() -> Result<Self, Vec<UseCaseError>> {
-        let invoice_types = ImportInvoiceTypesUseCase.make_localized_items()?;
+    pub fn new(sources: &CsvSources) -> Result<Self, Vec<UseCaseError>> {
+        let invoice_types = ImportInvoiceTypesUseCase { sources }.make_localized_items()?;
 
         Ok(Self {
+            sources: sources.clone(),
+            dialect: sources
+                .dialect(&CsvType::Invoice)
+                .map_err(|e| vec![e.into()])?,
             invoice_types: ImportInvoiceTypesUseCase::group_localized_items(invoice_types),
             ..Self::default()
         })
@@ -63,16 +78,34 @@ impl ImportInvoiceUseCase {
         self.batch = true;
         self.batch_size = batch_size;
     }
+
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+
+    /// Derives the open amounts of the imported invoices, new ones included and existing ones
+    /// as their totals may have changed
+    pub fn update_open_amounts(&self) -> Option<Vec<UseCaseError>> {
+        let invoice_ids: Vec<u32> = self.invoice_ids.borrow().iter().copied().collect();
+        update_open_amounts(
+            HasTargetConnection::get_pooled_connection(),
+            self.batch.then(|| BatchConfig::new(self.batch_size)),
+            &invoice_ids,
+        )
+    }
 }
 
 impl CanReadCSVDataSource<CsvInvoiceDTO> for ImportInvoiceUseCase {
     fn find_all(&self) -> Result<Vec<CsvInvoiceDTO>, InfrastructureError> {
-        self.read(CsvType::Invoice)
+        self.read(&self.sources, CsvType::Invoice)
     }
 }
 impl CsvEntityParser<CsvInvoiceDTO, Invoice> for ImportInvoiceUseCase {
     fn transform_csv_row_to_entity(&self, csv: CsvInvoiceDTO) -> Result<Invoice, MappingError> {
-        let mut factory: InvoiceDomainFactory = csv.try_into()?;
+        let Rounded {
+            value: mut factory,
+            rounded,
+        } = Rounded::<InvoiceDomainFactory>::try_from_csv(csv, &self.dialect)?;
         self.invoice_types
             .contains_key(&factory.invoice_id)
             .then(|| {
@@ -82,7 +115,12 @@ impl CsvEntityParser<CsvInvoiceDTO, Invoice> for ImportInvoiceUseCase {
                     .unwrap()
                     .to_owned();
             });
-        factory.make().map_err(MappingError::Domain)
+        let invoice = factory.make().map_err(MappingError::Domain)?;
+        self.invoice_ids.borrow_mut().insert(invoice.invoice_id());
+        if rounded > 0 {
+            report::record("invoice", |r| r.rounded += rounded);
+        }
+        Ok(invoice)
     }
 }
 impl CanPersistIntoDatabaseUseCase<Invoice, (InvoiceModel, Vec<InvoiceLangModel>)>
@@ -104,6 +142,10 @@ impl CanPersistIntoDatabaseUseCase<Invoice, (InvoiceModel, Vec<InvoiceLangModel>
             None
         }
     }
+
+    fn is_incremental(&self) -> bool {
+        self.incremental
+    }
 }
 impl
     ImportFromSingleEntityBasedCsvUseCase<
@@ -145,7 +187,7 @@ mod tests {
                 .join("tests")
                 .join("fixtures")
                 .join("invoice_lang_for_unit_test.csv");
-            self.read(CsvType::Test(csv_path))
+            self.read(&CsvSources::default(), CsvType::Test(csv_path))
         }
     }
     impl CanFetchLanguages for ImportInvoiceTypeUseCaseTest {}
@@ -190,12 +232,13 @@ mod tests {
                 .join("fixtures")
                 .join("invoice_for_unit_test.csv");
 
-            self.read(CsvType::Test(csv_path))
+            self.read(&CsvSources::default(), CsvType::Test(csv_path))
         }
     }
     impl CsvEntityParser<CsvInvoiceDTO, Invoice> for ImportInvoiceUseCaseTest {
         fn transform_csv_row_to_entity(&self, csv: CsvInvoiceDTO) -> Result<Invoice, MappingError> {
-            let mut factory: InvoiceDomainFactory = csv.try_into()?;
+            let mut factory =
+                Rounded::<InvoiceDomainFactory>::try_from_csv(csv, &CsvDialect::default())?.value;
             self.invoice_types
                 .contains_key(&factory.invoice_id)
                 .then(|| {
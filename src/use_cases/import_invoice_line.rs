@@ -0,0 +1,383 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use rust_decimal::Decimal;
+
+use crate::{
+    domain::{
+        invoice_line::{check_lines_total, InvoiceLine, InvoiceLineDomainFactory},
+        vo::{document_type::DocumentType, price::Currency},
+        DomainError,
+    },
+    infrastructure::{
+        csv_reader::{dialect::CsvDialect, invoice_line::CsvInvoiceLineDTO, CsvSources, CsvType},
+        data_source::CanReadCSVDataSource,
+        database::{
+            batch::{Batch, BatchConfig},
+            connection::{DbConnection, HasConnection, HasTargetConnection},
+            models::invoice_line::{batch_upsert, InvoiceLineModel},
+        },
+        report, InfrastructureError,
+    },
+    interface_adapters::mappers::{CsvEntityParser, Rounded, TryFromCsv},
+};
+
+use super::{
+    helpers::{
+        csv::ImportFromSingleEntityBasedCsvUseCase,
+        invoice::{find_invoice, InvoiceCache},
+        model::CanPersistIntoDatabaseUseCase,
+    },
+    *,
+};
+
+#[derive(Default)]
+pub struct ImportInvoiceLineUseCase {
+    sources: CsvSources,
+    dialect: CsvDialect,
+    invoice_cache: InvoiceCache,
+    batch: bool,
+    batch_size: usize,
+    incremental: bool,
+}
+
+impl ImportInvoiceLineUseCase {
+    pub fn new(sources: &CsvSources) -> Result<Self, Vec<UseCaseError>> {
+        Ok(Self {
+            sources: sources.clone(),
+            dialect: sources
+                .dialect(&CsvType::InvoiceLine)
+                .map_err(|e| vec![e.into()])?,
+            ..Self::default()
+        })
+    }
+
+    pub fn set_batch(&mut self, batch_size: usize) {
+        self.batch = true;
+        self.batch_size = batch_size;
+    }
+
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+}
+
+fn make_invoice_line(
+    dialect: &CsvDialect,
+    cache: &InvoiceCache,
+    csv: CsvInvoiceLineDTO,
+    connection: &mut DbConnection,
+) -> Result<Rounded<InvoiceLine>, MappingError> {
+    let Rounded {
+        value: factory,
+        rounded,
+    } = Rounded::<InvoiceLineDomainFactory>::try_from_csv(csv, dialect)?;
+    let invoice = find_invoice(cache, factory.invoice_id, connection)?.ok_or_else(|| {
+        MappingError::Domain(DomainError::ValidationError(format!(
+            "Invoice {} does not exist, import invoices first",
+            factory.invoice_id
+        )))
+    })?;
+    let currency = Currency::try_from(invoice.currency.as_str())?;
+    let document_type = DocumentType::try_from(invoice.document_type.as_str())?;
+    let value = factory
+        .make(currency, document_type)
+        .map_err(MappingError::Domain)?;
+    Ok(Rounded { value, rounded })
+}
+
+// Lines of an invoice whose sum differs from its total are all rejected. Stored lines missing
+// from the source, e.g. in an incremental extract, are counted with the source ones.
+fn check_invoice_totals(
+    cache: &InvoiceCache,
+    lines: Vec<Result<InvoiceLine, MappingError>>,
+    connection: &mut DbConnection,
+) -> Vec<Result<InvoiceLine, MappingError>> {
+    let mut line_totals: BTreeMap<u32, Vec<Decimal>> = BTreeMap::new();
+    let mut line_ids = HashSet::new();
+    for line in lines.iter().flatten() {
+        line_totals
+            .entry(line.invoice_id())
+            .or_default()
+            .push(line.total_tax_excl());
+        line_ids.insert(line.invoiceline_id());
+    }
+    let invoice_ids: Vec<u32> = line_totals.keys().copied().collect();
+    let mut mismatches: BTreeMap<u32, String> = BTreeMap::new();
+    match InvoiceLineModel::select_totals_by_invoice(connection, &invoice_ids) {
+        Ok(stored_lines) => {
+            for (line_id, invoice_id, total) in stored_lines {
+                if !line_ids.contains(&line_id) {
+                    line_totals.entry(invoice_id).or_default().push(total);
+                }
+            }
+            for (invoice_id, totals) in line_totals {
+                let Some(invoice) = cache.get(&invoice_id) else {
+                    continue;
+                };
+                if let Err(DomainError::ValidationError(message)) =
+                    check_lines_total(invoice_id, &totals, invoice.total_tax_excl)
+                {
+                    mismatches.insert(invoice_id, message);
+                }
+            }
+        }
+        Err(e) => {
+            for invoice_id in invoice_ids {
+                mismatches.insert(
+                    invoice_id,
+                    format!(
+                        "Stored lines of invoice {} cannot be read, {}",
+                        invoice_id, e
+                    ),
+                );
+            }
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|line| match line {
+            Ok(line) if mismatches.contains_key(&line.invoice_id()) => {
+                Err(MappingError::Domain(DomainError::ValidationError(format!(
+                    "Invoice line {}: {}",
+                    line.invoiceline_id(),
+                    mismatches[&line.invoice_id()]
+                ))))
+            }
+            line => line,
+        })
+        .collect()
+}
+
+impl CanReadCSVDataSource<CsvInvoiceLineDTO> for ImportInvoiceLineUseCase {
+    fn find_all(&self) -> Result<Vec<CsvInvoiceLineDTO>, InfrastructureError> {
+        self.read(&self.sources, CsvType::InvoiceLine)
+    }
+}
+impl CsvEntityParser<CsvInvoiceLineDTO, InvoiceLine> for ImportInvoiceLineUseCase {
+    fn parse_all(
+        &self,
+        csv_dtos: Vec<CsvInvoiceLineDTO>,
+    ) -> Vec<Result<InvoiceLine, MappingError>> {
+        // One connection for the whole source, invoices being read once
+        let mut connection = HasTargetConnection::get_pooled_connection();
+        let mut rounded_by_line = HashMap::new();
+        let lines = csv_dtos
+            .into_iter()
+            .map(|csv| {
+                make_invoice_line(&self.dialect, &self.invoice_cache, csv, &mut connection).map(
+                    |Rounded { value, rounded }| {
+                        rounded_by_line.insert(value.invoiceline_id(), rounded);
+                        value
+                    },
+                )
+            })
+            .collect();
+        let lines = check_invoice_totals(&self.invoice_cache, lines, &mut connection);
+        let rounded: usize = lines
+            .iter()
+            .flatten()
+            .map(|line| rounded_by_line[&line.invoiceline_id()])
+            .sum();
+        if rounded > 0 {
+            report::record("invoice_line", |r| r.rounded += rounded);
+        }
+        lines
+    }
+
+    fn transform_csv_row_to_entity(
+        &self,
+        csv: CsvInvoiceLineDTO,
+    ) -> Result<InvoiceLine, MappingError> {
+        let mut connection = HasTargetConnection::get_pooled_connection();
+        let Rounded { value, rounded } =
+            make_invoice_line(&self.dialect, &self.invoice_cache, csv, &mut connection)?;
+        if rounded > 0 {
+            report::record("invoice_line", |r| r.rounded += rounded);
+        }
+        Ok(value)
+    }
+}
+impl CanPersistIntoDatabaseUseCase<InvoiceLine, InvoiceLineModel> for ImportInvoiceLineUseCase {
+    type DbConnection = HasTargetConnection;
+    fn set_batch<'a>(
+        &'a self,
+        models: &'a [InvoiceLineModel],
+    ) -> Option<Batch<'a, InvoiceLineModel>> {
+        if self.batch {
+            Some(Batch::new(
+                models,
+                Some(BatchConfig::new(self.batch_size)),
+                batch_upsert,
+                HasTargetConnection::get_pooled_connection(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+}
+impl ImportFromSingleEntityBasedCsvUseCase<CsvInvoiceLineDTO, InvoiceLine, InvoiceLineModel>
+    for ImportInvoiceLineUseCase
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::infrastructure::database::{
+        connection::tests::{get_test_pooled_connection, reset_test_database, HasTestConnection},
+        models::{
+            invoice::tests::{insert_invoice, invoice_model_fixtures},
+            invoice_line::tests::{invoice_line_model_fixtures, read_invoice_lines},
+        },
+    };
+
+    #[derive(Default)]
+    pub struct ImportInvoiceLineUseCaseTest {
+        invoice_cache: InvoiceCache,
+        pub use_batch: bool,
+    }
+    impl CanReadCSVDataSource<CsvInvoiceLineDTO> for ImportInvoiceLineUseCaseTest {
+        fn find_all(&self) -> Result<Vec<CsvInvoiceLineDTO>, InfrastructureError> {
+            let root_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            let csv_path = root_path
+                .join("tests")
+                .join("fixtures")
+                .join("invoice_lines_for_unit_test.csv");
+            self.read(&CsvSources::default(), CsvType::Test(csv_path))
+        }
+    }
+    impl CsvEntityParser<CsvInvoiceLineDTO, InvoiceLine> for ImportInvoiceLineUseCaseTest {
+        fn parse_all(
+            &self,
+            csv_dtos: Vec<CsvInvoiceLineDTO>,
+        ) -> Vec<Result<InvoiceLine, MappingError>> {
+            let lines = csv_dtos
+                .into_iter()
+                .map(|csv| self.transform_csv_row_to_entity(csv))
+                .collect();
+            let mut connection = HasTestConnection::get_pooled_connection();
+            check_invoice_totals(&self.invoice_cache, lines, &mut connection)
+        }
+
+        fn transform_csv_row_to_entity(
+            &self,
+            csv: CsvInvoiceLineDTO,
+        ) -> Result<InvoiceLine, MappingError> {
+            let mut connection = HasTestConnection::get_pooled_connection();
+            make_invoice_line(
+                &CsvDialect::default(),
+                &self.invoice_cache,
+                csv,
+                &mut connection,
+            )
+            .map(|line| line.value)
+        }
+    }
+    impl CanPersistIntoDatabaseUseCase<InvoiceLine, InvoiceLineModel> for ImportInvoiceLineUseCaseTest {
+        type DbConnection = HasTestConnection;
+        fn set_batch<'a>(
+            &'a self,
+            models: &'a [InvoiceLineModel],
+        ) -> Option<Batch<'a, InvoiceLineModel>> {
+            if self.use_batch {
+                return Some(Batch::new(
+                    models,
+                    None,
+                    batch_upsert,
+                    HasTestConnection::get_pooled_connection(),
+                ));
+            }
+            None
+        }
+    }
+    impl ImportFromSingleEntityBasedCsvUseCase<CsvInvoiceLineDTO, InvoiceLine, InvoiceLineModel>
+        for ImportInvoiceLineUseCaseTest
+    {
+    }
+
+    fn arrange(connection: &mut DbConnection) {
+        reset_test_database(connection);
+        for invoice in invoice_model_fixtures() {
+            insert_invoice(connection, false, &invoice).expect("Failed to insert invoice");
+        }
+    }
+
+    fn assert_results(errors: Option<Vec<UseCaseError>>, connection: &mut DbConnection) {
+        assert!(
+            errors.is_some_and(|errs| errs.len() == 1
+                && format!("{:?}", errs[0]).contains("Invoice 2 does not exist")),
+            "Failed to execute use case: the line of invoice 2 has no parent",
+        );
+        assert_eq!(
+            read_invoice_lines(connection),
+            invoice_line_model_fixtures().to_vec()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_invoice_line_use_case() {
+        let mut connection = get_test_pooled_connection();
+        arrange(&mut connection);
+
+        let errors = ImportInvoiceLineUseCaseTest::default().execute();
+
+        assert_results(errors, &mut connection);
+    }
+
+    #[test]
+    #[serial]
+    fn test_batch_invoice_line_use_case() {
+        let mut connection = get_test_pooled_connection();
+        arrange(&mut connection);
+
+        let use_case = ImportInvoiceLineUseCaseTest {
+            use_batch: true,
+            ..Default::default()
+        };
+        let errors = use_case.execute();
+
+        assert_results(errors, &mut connection);
+    }
+
+    #[test]
+    #[serial]
+    fn test_lines_not_matching_the_invoice_total_are_rejected() {
+        let mut connection = get_test_pooled_connection();
+        arrange(&mut connection);
+        let use_case = ImportInvoiceLineUseCaseTest::default();
+
+        let dtos = use_case.find_all().unwrap();
+        let results = use_case.parse_all(dtos.into_iter().skip(1).collect());
+
+        assert!(matches!(
+            &results[0],
+            Err(MappingError::Domain(DomainError::ValidationError(message)))
+                if message.starts_with("Invoice line 2: Invoice 1 lines add up to 40")
+        ));
+        assert!(results[1].is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_stored_lines_count_in_the_invoice_total() {
+        let mut connection = get_test_pooled_connection();
+        arrange(&mut connection);
+        batch_upsert(&invoice_line_model_fixtures()[..1], &mut connection).unwrap();
+        let use_case = ImportInvoiceLineUseCaseTest::default();
+
+        let dtos = use_case.find_all().unwrap();
+        let results = use_case.parse_all(dtos.into_iter().skip(1).collect());
+
+        assert!(results[0].is_ok());
+    }
+}
@@ -11,7 +11,16 @@ use super::helpers::model::{
     CanPersistIntoDatabaseUseCase, CanReadAllModelUseCase, ImportModelUseCase,
 };
 
-pub struct ImportMappingClientUseCase;
+#[derive(Default)]
+pub struct ImportMappingClientUseCase {
+    incremental: bool,
+}
+
+impl ImportMappingClientUseCase {
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+}
 
 impl CanReadAllModelUseCase for ImportMappingClientUseCase {
     type ModelImpl = MappingClientSource;
@@ -23,6 +32,10 @@ impl CanPersistIntoDatabaseUseCase<MappingClient, MappingClientModel>
     for ImportMappingClientUseCase
 {
     type DbConnection = HasTargetConnection;
+
+    fn is_incremental(&self) -> bool {
+        self.incremental
+    }
 }
 
 impl ModelToEntityParser<MappingClientSource, MappingClient> for ImportMappingClientUseCase {}
@@ -1,34 +1,88 @@
 use crate::{
     domain::order::{Order, OrderDomainFactory},
     infrastructure::{
-        csv_reader::{order::CsvOrderDTO, CsvType},
+        csv_reader::{dialect::CsvDialect, order::CsvOrderDTO, CsvSources, CsvType},
         data_source::CanReadCSVDataSource,
-        database::{connection::HasTargetConnection, models::order::OrderModel},
+        database::{
+            connection::{HasConnection, HasTargetConnection},
+            models::order::OrderModel,
+        },
     },
-    interface_adapters::mappers::CsvEntityParser,
+    interface_adapters::mappers::{CsvEntityParser, TryFromCsv},
 };
 
 use super::{
-    helpers::{csv::ImportFromSingleEntityBasedCsvUseCase, model::CanPersistIntoDatabaseUseCase},
+    helpers::{
+        csv::ImportFromSingleEntityBasedCsvUseCase, model::CanPersistIntoDatabaseUseCase,
+        order::update_completions,
+    },
+    sync_deletes::{ExtractIds, SyncDeletesUseCase},
     *,
 };
 
-pub struct ImportOrderUseCase;
+#[derive(Default)]
+pub struct ImportOrderUseCase {
+    sources: CsvSources,
+    dialect: CsvDialect,
+    incremental: bool,
+    extract_ids: ExtractIds,
+}
+
+impl ImportOrderUseCase {
+    pub fn new(sources: &CsvSources) -> Result<Self, Vec<UseCaseError>> {
+        Ok(Self {
+            sources: sources.clone(),
+            dialect: sources
+                .dialect(&CsvType::Order)
+                .map_err(|e| vec![e.into()])?,
+            ..Self::default()
+        })
+    }
+
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+
+    /// Derives the completion of the imported orders from their lines, as the import restores
+    /// the CSV value
+    pub fn update_completions(&self) -> Option<Vec<UseCaseError>> {
+        let mut connection = HasTargetConnection::get_pooled_connection();
+        update_completions(&mut connection, &self.extract_ids.to_vec())
+    }
+}
 impl CanReadCSVDataSource<CsvOrderDTO> for ImportOrderUseCase {
     fn find_all(&self) -> Result<Vec<CsvOrderDTO>, InfrastructureError> {
-        self.read(CsvType::Order)
+        let orders = self.read(&self.sources, CsvType::Order)?;
+        self.extract_ids
+            .keep(orders.iter().filter_map(Self::source_id));
+        Ok(orders)
     }
 }
 impl CsvEntityParser<CsvOrderDTO, Order> for ImportOrderUseCase {
     fn transform_csv_row_to_entity(&self, csv: CsvOrderDTO) -> Result<Order, MappingError> {
-        let factory: OrderDomainFactory = csv.try_into()?;
+        let factory = OrderDomainFactory::try_from_csv(csv, &self.dialect)?;
         factory.make().map_err(MappingError::Domain)
     }
 }
 impl CanPersistIntoDatabaseUseCase<Order, OrderModel> for ImportOrderUseCase {
     type DbConnection = HasTargetConnection;
+
+    fn is_incremental(&self) -> bool {
+        self.incremental
+    }
 }
 impl ImportFromSingleEntityBasedCsvUseCase<CsvOrderDTO, Order, OrderModel> for ImportOrderUseCase {}
+impl SyncDeletesUseCase<CsvOrderDTO, OrderModel> for ImportOrderUseCase {
+    type DbConnection = HasTargetConnection;
+
+    fn source_id(csv: &CsvOrderDTO) -> Option<u32> {
+        csv.c_order_id.parse().ok()
+    }
+
+    fn extract_ids(&self) -> &ExtractIds {
+        &self.extract_ids
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -44,6 +98,7 @@ mod tests {
         infrastructure::{
             csv_reader::CsvType,
             database::models::order::{bench::order_model_fixtures, tests::read_orders},
+            report,
         },
         interface_adapters::mappers::CsvEntityParser,
         use_cases::helpers::{
@@ -51,7 +106,10 @@ mod tests {
         },
     };
 
-    pub struct ImportOrderUseCaseTest;
+    #[derive(Default)]
+    pub struct ImportOrderUseCaseTest {
+        pub incremental: bool,
+    }
     impl CanReadCSVDataSource<CsvOrderDTO> for ImportOrderUseCaseTest {
         fn find_all(&self) -> Result<Vec<CsvOrderDTO>, InfrastructureError> {
             let root_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -59,17 +117,21 @@ mod tests {
                 .join("tests")
                 .join("fixtures")
                 .join("order_for_unit_test.csv");
-            self.read(CsvType::Test(csv_path))
+            self.read(&CsvSources::default(), CsvType::Test(csv_path))
         }
     }
     impl CsvEntityParser<CsvOrderDTO, Order> for ImportOrderUseCaseTest {
         fn transform_csv_row_to_entity(&self, csv: CsvOrderDTO) -> Result<Order, MappingError> {
-            let factory: OrderDomainFactory = csv.try_into()?;
+            let factory = OrderDomainFactory::try_from_csv(csv, &CsvDialect::default())?;
             factory.make().map_err(MappingError::Domain)
         }
     }
     impl CanPersistIntoDatabaseUseCase<Order, OrderModel> for ImportOrderUseCaseTest {
         type DbConnection = HasTestConnection;
+
+        fn is_incremental(&self) -> bool {
+            self.incremental
+        }
     }
     impl ImportFromSingleEntityBasedCsvUseCase<CsvOrderDTO, Order, OrderModel>
         for ImportOrderUseCaseTest
@@ -84,7 +146,7 @@ mod tests {
         reset_test_database(&mut connection);
 
         // Result
-        let use_case = ImportOrderUseCaseTest;
+        let use_case = ImportOrderUseCaseTest::default();
         let errors = use_case.execute();
 
         // Assert
@@ -99,5 +161,30 @@ mod tests {
         assert_eq!(persisted_orders[1], order_model_fixtures()[1]);
     }
 
+    #[test]
+    #[serial]
+    fn test_incremental_order_use_case() {
+        // Arrange
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+        let use_case = ImportOrderUseCaseTest { incremental: true };
+        let before = report::entity_report("order").unwrap_or_default();
+
+        // Result
+        let first_run = use_case.execute();
+        let after_first_run = report::entity_report("order").unwrap_or_default();
+        let second_run = use_case.execute();
+        let after_second_run = report::entity_report("order").unwrap_or_default();
+
+        // Assert
+        assert!(first_run.is_none() && second_run.is_none());
+        assert_eq!(after_first_run.inserted - before.inserted, 2);
+        assert_eq!(after_first_run.unchanged - before.unchanged, 0);
+        assert_eq!(after_second_run.inserted, after_first_run.inserted);
+        assert_eq!(after_second_run.updated, after_first_run.updated);
+        assert_eq!(after_second_run.unchanged - after_first_run.unchanged, 2);
+        assert_eq!(read_orders(&mut connection).len(), 2);
+    }
+
     // TODO: Test with failure
 }
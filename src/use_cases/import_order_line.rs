@@ -1,18 +1,20 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
 
 use crate::{
     domain::{
         order::Order,
         order_line::{
             OrderLine, OrderLineDomainFactory, OrderLineLocalizedItemFactory,
-            OrderLinePrimaryFields,
+            OrderLinePrimaryFields, OrderLineRules,
         },
         vo::localized_item::LocalizedItem,
     },
     infrastructure::{
         csv_reader::{
+            dialect::CsvDialect,
             order_line::{CsvOrderLineDTO, CsvOrderLineLocalizedItemDTO},
-            CsvType,
+            CsvSources, CsvType,
         },
         data_source::CanReadCSVDataSource,
         database::{
@@ -25,26 +27,29 @@ use crate::{
         },
         InfrastructureError,
     },
-    interface_adapters::mappers::CsvEntityParser,
+    interface_adapters::mappers::{order_line::make_order_line, CsvEntityParser, TryFromCsv},
 };
 
 use super::{
     helpers::{
         csv::ImportFromSingleEntityBasedCsvUseCase, language::CanFetchLanguages,
         localized_item::ImportLocalizedItem, model::CanPersistIntoDatabaseUseCase,
+        order::update_completions,
     },
     *,
 };
 
-struct ImportOrderLineItemNamesUseCase;
-impl CanReadCSVDataSource<CsvOrderLineLocalizedItemDTO> for ImportOrderLineItemNamesUseCase {
+struct ImportOrderLineItemNamesUseCase<'a> {
+    sources: &'a CsvSources,
+}
+impl CanReadCSVDataSource<CsvOrderLineLocalizedItemDTO> for ImportOrderLineItemNamesUseCase<'_> {
     fn find_all(&self) -> Result<Vec<CsvOrderLineLocalizedItemDTO>, InfrastructureError> {
-        self.read(CsvType::OrderLineItem)
+        self.read(self.sources, CsvType::OrderLineItem)
     }
 }
-impl CanFetchLanguages for ImportOrderLineItemNamesUseCase {}
+impl CanFetchLanguages for ImportOrderLineItemNamesUseCase<'_> {}
 impl ImportLocalizedItem<OrderLineLocalizedItemFactory, CsvOrderLineLocalizedItemDTO>
-    for ImportOrderLineItemNamesUseCase
+    for ImportOrderLineItemNamesUseCase<'_>
 {
     fn source(&self) -> Result<Vec<CsvOrderLineLocalizedItemDTO>, UseCaseError> {
         self.find_all().map_err(|e| e.into())
@@ -53,27 +58,59 @@ impl ImportLocalizedItem<OrderLineLocalizedItemFactory, CsvOrderLineLocalizedIte
 
 #[derive(Default)]
 pub struct ImportOrderLineUseCase {
+    sources: CsvSources,
+    dialect: CsvDialect,
     order_cache: elsa::map::FrozenMap<u32, Box<Order>>,
+    // Orders of the accepted lines, whose completions are recomputed after the import
+    order_ids: RefCell<BTreeSet<u32>>,
     item_names: HashMap<u32, Vec<LocalizedItem>>,
+    rules: OrderLineRules,
+    completion_from_lines: bool,
     batch: bool,
     batch_size: usize,
+    incremental: bool,
 }
 
 impl ImportOrderLineUseCase {
-    pub fn new() -> Result<Self, Vec<UseCaseError>> {
-        let item_names = ImportOrderLineItemNamesUseCase.make_localized_items()?;
+    pub fn new(
+        sources: &CsvSources,
+        rules: OrderLineRules,
+        completion_from_lines: bool,
+    ) -> Result<Self, Vec<UseCaseError>> {
+        let item_names = ImportOrderLineItemNamesUseCase { sources }.make_localized_items()?;
 
         Ok(Self {
+            sources: sources.clone(),
+            dialect: sources
+                .dialect(&CsvType::OrderLine)
+                .map_err(|e| vec![e.into()])?,
             item_names: ImportOrderLineItemNamesUseCase::group_localized_items(item_names),
+            rules,
+            completion_from_lines,
             ..Self::default()
         })
     }
 
+    pub fn completion_from_lines(&self) -> bool {
+        self.completion_from_lines
+    }
+
+    /// Derives the completion of the orders of the imported lines in the target database
+    pub fn update_completions(&self) -> Option<Vec<UseCaseError>> {
+        let mut connection = HasTargetConnection::get_pooled_connection();
+        let order_ids: Vec<u32> = self.order_ids.borrow().iter().copied().collect();
+        update_completions(&mut connection, &order_ids)
+    }
+
     pub fn set_batch(&mut self, batch_size: usize) {
         self.batch = true;
         self.batch_size = batch_size;
     }
 
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+
     fn get_order(&self, id: u32, connection: &mut DbConnection) -> Result<&Order, MappingError> {
         if let Some(order) = self.order_cache.get(&id) {
             return Ok(order);
@@ -91,16 +128,17 @@ impl ImportOrderLineUseCase {
 
 impl CanReadCSVDataSource<CsvOrderLineDTO> for ImportOrderLineUseCase {
     fn find_all(&self) -> Result<Vec<CsvOrderLineDTO>, InfrastructureError> {
-        self.read(CsvType::OrderLine)
+        self.read(&self.sources, CsvType::OrderLine)
     }
 }
 impl CsvEntityParser<CsvOrderLineDTO, OrderLine> for ImportOrderLineUseCase {
     fn transform_csv_row_to_entity(&self, csv: CsvOrderLineDTO) -> Result<OrderLine, MappingError> {
-        let raw_fields: Result<OrderLinePrimaryFields, MappingError> = csv.try_into();
+        let raw_fields = OrderLinePrimaryFields::try_from_csv(csv, &self.dialect);
         raw_fields.and_then(|fields| {
             let mut connection = HasTargetConnection::get_pooled_connection();
             let order = self.get_order(fields.order_id, &mut connection)?.clone();
             let mut factory = OrderLineDomainFactory::new_from_order(order, &fields);
+            factory.rules = self.rules;
 
             self.item_names.contains_key(&fields.orderline_id).then(|| {
                 factory.item_names = self
@@ -109,7 +147,9 @@ impl CsvEntityParser<CsvOrderLineDTO, OrderLine> for ImportOrderLineUseCase {
                     .unwrap()
                     .to_owned();
             });
-            factory.make().map_err(MappingError::Domain)
+            let order_line = make_order_line(factory)?;
+            self.order_ids.borrow_mut().insert(fields.order_id);
+            Ok(order_line)
         })
     }
 }
@@ -132,6 +172,10 @@ impl CanPersistIntoDatabaseUseCase<OrderLine, (OrderLineModel, Vec<OrderLineLang
             None
         }
     }
+
+    fn is_incremental(&self) -> bool {
+        self.incremental
+    }
 }
 impl
     ImportFromSingleEntityBasedCsvUseCase<
@@ -165,6 +209,7 @@ mod tests {
             connection::tests::{get_test_pooled_connection, reset_test_database},
             models::order::bench::order_model_fixtures,
         },
+        infrastructure::report,
     };
 
     struct ImportOrderLineItemNamesUseCaseTest;
@@ -175,7 +220,7 @@ mod tests {
                 .join("tests")
                 .join("fixtures")
                 .join("order_lines_items_for_unit_test.csv");
-            self.read(CsvType::Test(csv_path))
+            self.read(&CsvSources::default(), CsvType::Test(csv_path))
         }
     }
     impl CanFetchLanguages for ImportOrderLineItemNamesUseCaseTest {}
@@ -198,6 +243,7 @@ mod tests {
     #[derive(Default)]
     pub struct ImportOrderLineUseCaseTest {
         order_cache: elsa::map::FrozenMap<u32, Box<Order>>,
+        order_ids: RefCell<BTreeSet<u32>>,
         item_names: HashMap<u32, Vec<LocalizedItem>>,
         pub use_batch: bool,
     }
@@ -237,7 +283,7 @@ mod tests {
                 .join("tests")
                 .join("fixtures")
                 .join("order_lines_for_unit_test.csv");
-            self.read(CsvType::Test(csv_path))
+            self.read(&CsvSources::default(), CsvType::Test(csv_path))
         }
     }
     impl CsvEntityParser<CsvOrderLineDTO, OrderLine> for ImportOrderLineUseCaseTest {
@@ -245,7 +291,7 @@ mod tests {
             &self,
             csv: CsvOrderLineDTO,
         ) -> Result<OrderLine, MappingError> {
-            let raw_fields: Result<OrderLinePrimaryFields, MappingError> = csv.try_into();
+            let raw_fields = OrderLinePrimaryFields::try_from_csv(csv, &CsvDialect::default());
             // todo!()
             raw_fields.and_then(|fields| {
                 let mut connection = HasTestConnection::get_pooled_connection();
@@ -259,7 +305,9 @@ mod tests {
                         .unwrap()
                         .to_owned();
                 });
-                factory.make().map_err(MappingError::Domain)
+                let order_line = make_order_line(factory)?;
+                self.order_ids.borrow_mut().insert(fields.order_id);
+                Ok(order_line)
             })
         }
     }
@@ -355,4 +403,54 @@ mod tests {
             assert_eq!(order_line_items, order_line_lang_model_fixtures()[i]);
         }
     }
+
+    #[test]
+    #[serial]
+    fn test_update_completions() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        for order_model in &order_model_fixtures() {
+            insert_order(&mut connection, false, order_model).expect("Failed to insert order");
+        }
+        let use_case = ImportOrderLineUseCaseTest::new().unwrap();
+        use_case.execute();
+        let order_ids: Vec<u32> = use_case.order_ids.borrow().iter().copied().collect();
+        assert_eq!(order_ids, vec![1, 2]);
+
+        assert!(update_completions(&mut connection, &order_ids).is_none());
+
+        // Order 3 has no lines and keeps its completion
+        assert_eq!(
+            OrderModel::select_completions(&mut connection, &[1, 2, 3]).unwrap(),
+            vec![
+                (1, Some(50), Some(30)),
+                (2, Some(50), Some(20)),
+                (3, None, None)
+            ]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_update_completions_of_given_orders() {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+
+        for order_model in &order_model_fixtures() {
+            insert_order(&mut connection, false, order_model).expect("Failed to insert order");
+        }
+        ImportOrderLineUseCaseTest::new().unwrap().execute();
+        report::start_run();
+
+        assert!(update_completions(&mut connection, &[1]).is_none());
+        assert!(update_completions(&mut connection, &[1]).is_none());
+
+        // Order 2 is left as is, order 1 still differs from its CSV completion
+        assert_eq!(
+            OrderModel::select_completions(&mut connection, &[1, 2]).unwrap(),
+            vec![(1, Some(50), Some(30)), (2, Some(20), Some(20))]
+        );
+        assert_eq!(report::entity_report("order").unwrap().recomputed, 2);
+    }
 }
@@ -0,0 +1,426 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    domain::{
+        payment::{Payment, PaymentAllocation, PaymentAllocationFactory, PaymentDomainFactory},
+        vo::price::Currency,
+        DomainError,
+    },
+    infrastructure::{
+        csv_reader::{
+            dialect::CsvDialect,
+            payment::{CsvPaymentAllocationDTO, CsvPaymentDTO},
+            CsvSources, CsvType,
+        },
+        data_source::CanReadCSVDataSource,
+        database::{
+            batch::{Batch, BatchConfig},
+            connection::{DbConnection, HasConnection, HasTargetConnection},
+            models::payment::{batch_upsert, PaymentAllocationModel, PaymentModel},
+        },
+        report, InfrastructureError,
+    },
+    interface_adapters::mappers::{CsvEntityParser, Rounded, TryFromCsv},
+};
+
+use super::{
+    helpers::{
+        csv::ImportFromSingleEntityBasedCsvUseCase,
+        invoice::{find_invoice, update_open_amounts, InvoiceCache},
+        model::CanPersistIntoDatabaseUseCase,
+    },
+    *,
+};
+
+struct ImportPaymentAllocationsUseCase<'a> {
+    sources: &'a CsvSources,
+}
+impl CanReadCSVDataSource<CsvPaymentAllocationDTO> for ImportPaymentAllocationsUseCase<'_> {
+    fn find_all(&self) -> Result<Vec<CsvPaymentAllocationDTO>, InfrastructureError> {
+        self.read(self.sources, CsvType::PaymentAllocation)
+    }
+}
+
+type Allocations = HashMap<u32, Vec<Rounded<PaymentAllocationFactory>>>;
+
+// A malformed allocation aborts the import, open amounts would be wrong otherwise
+fn group_allocations(
+    dtos: Vec<CsvPaymentAllocationDTO>,
+    dialect: &CsvDialect,
+) -> Result<Allocations, Vec<UseCaseError>> {
+    let mut allocations: Allocations = HashMap::new();
+    let mut errors = Vec::new();
+    for dto in dtos {
+        match Rounded::<PaymentAllocationFactory>::try_from_csv(dto, dialect) {
+            Ok(allocation) => allocations
+                .entry(allocation.value.payment_id)
+                .or_default()
+                .push(allocation),
+            Err(e) => errors.push(e.into()),
+        }
+    }
+    if errors.is_empty() {
+        Ok(allocations)
+    } else {
+        Err(errors)
+    }
+}
+
+#[derive(Default)]
+pub struct ImportPaymentUseCase {
+    sources: CsvSources,
+    dialect: CsvDialect,
+    allocations: Allocations,
+    invoice_cache: InvoiceCache,
+    // Invoices of the stored and exported allocations of the payments, whose open amounts change
+    invoice_ids: RefCell<BTreeSet<u32>>,
+    batch: bool,
+    batch_size: usize,
+    incremental: bool,
+}
+
+impl ImportPaymentUseCase {
+    pub fn new(sources: &CsvSources) -> Result<Self, Vec<UseCaseError>> {
+        let allocations = ImportPaymentAllocationsUseCase { sources }
+            .find_all()
+            .map_err(|e| vec![e.into()])?;
+        let allocation_dialect = sources
+            .dialect(&CsvType::PaymentAllocation)
+            .map_err(|e| vec![e.into()])?;
+
+        Ok(Self {
+            sources: sources.clone(),
+            dialect: sources
+                .dialect(&CsvType::Payment)
+                .map_err(|e| vec![e.into()])?,
+            allocations: group_allocations(allocations, &allocation_dialect)?,
+            ..Self::default()
+        })
+    }
+
+    pub fn set_batch(&mut self, batch_size: usize) {
+        self.batch = true;
+        self.batch_size = batch_size;
+    }
+
+    pub fn set_incremental(&mut self, incremental: bool) {
+        self.incremental = incremental;
+    }
+
+    /// Derives the open amount of the invoices the imported payments were or are allocated to
+    pub fn update_open_amounts(&self) -> Option<Vec<UseCaseError>> {
+        let invoice_ids: Vec<u32> = self.invoice_ids.borrow().iter().copied().collect();
+        update_open_amounts(
+            HasTargetConnection::get_pooled_connection(),
+            self.batch.then(|| BatchConfig::new(self.batch_size)),
+            &invoice_ids,
+        )
+    }
+}
+
+fn make_payment(
+    dialect: &CsvDialect,
+    allocations: &Allocations,
+    cache: &InvoiceCache,
+    csv: CsvPaymentDTO,
+    connection: &mut DbConnection,
+) -> Result<Payment, MappingError> {
+    let Rounded {
+        value: factory,
+        mut rounded,
+    } = Rounded::<PaymentDomainFactory>::try_from_csv(csv, dialect)?;
+    let allocations = allocations
+        .get(&factory.payment_id)
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|allocation| {
+            rounded += allocation.rounded;
+            let allocation = allocation.value;
+            let invoice =
+                find_invoice(cache, allocation.invoice_id, connection)?.ok_or_else(|| {
+                    MappingError::Domain(DomainError::ValidationError(format!(
+                        "Allocation {} of payment {} points to unknown invoice {}",
+                        allocation.allocationline_id, allocation.payment_id, allocation.invoice_id
+                    )))
+                })?;
+            let invoice_currency = Currency::try_from(invoice.currency.as_str())?;
+            allocation
+                .make(factory.currency(), invoice_currency)
+                .map_err(MappingError::Domain)
+        })
+        .collect::<Result<Vec<PaymentAllocation>, MappingError>>()?;
+    let payment = factory.make(allocations).map_err(MappingError::Domain)?;
+    if rounded > 0 {
+        report::record("payment", |r| r.rounded += rounded);
+    }
+    Ok(payment)
+}
+
+impl CanReadCSVDataSource<CsvPaymentDTO> for ImportPaymentUseCase {
+    // Read before the payments are written, which replaces their stored allocations
+    fn find_all(&self) -> Result<Vec<CsvPaymentDTO>, InfrastructureError> {
+        let payments = self.read(&self.sources, CsvType::Payment)?;
+        let payment_ids: Vec<u32> = payments
+            .iter()
+            .filter_map(|payment| payment.c_payment_id.parse().ok())
+            .collect();
+        let mut connection = HasTargetConnection::get_pooled_connection();
+        let stored_invoice_ids =
+            PaymentAllocationModel::select_invoice_ids(&mut connection, &payment_ids)
+                .map_err(InfrastructureError::DatabaseError)?;
+        let mut invoice_ids = self.invoice_ids.borrow_mut();
+        invoice_ids.extend(stored_invoice_ids);
+        invoice_ids.extend(payment_ids.iter().flat_map(|payment_id| {
+            self.allocations
+                .get(payment_id)
+                .into_iter()
+                .flatten()
+                .map(|allocation| allocation.value.invoice_id)
+        }));
+        Ok(payments)
+    }
+}
+impl CsvEntityParser<CsvPaymentDTO, Payment> for ImportPaymentUseCase {
+    fn transform_csv_row_to_entity(&self, csv: CsvPaymentDTO) -> Result<Payment, MappingError> {
+        let mut connection = HasTargetConnection::get_pooled_connection();
+        make_payment(
+            &self.dialect,
+            &self.allocations,
+            &self.invoice_cache,
+            csv,
+            &mut connection,
+        )
+    }
+}
+impl CanPersistIntoDatabaseUseCase<Payment, (PaymentModel, Vec<PaymentAllocationModel>)>
+    for ImportPaymentUseCase
+{
+    type DbConnection = HasTargetConnection;
+    fn set_batch<'a>(
+        &'a self,
+        models: &'a [(PaymentModel, Vec<PaymentAllocationModel>)],
+    ) -> Option<Batch<'a, (PaymentModel, Vec<PaymentAllocationModel>)>> {
+        if self.batch {
+            Some(Batch::new(
+                models,
+                Some(BatchConfig::new(self.batch_size)),
+                batch_upsert,
+                HasTargetConnection::get_pooled_connection(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+}
+impl
+    ImportFromSingleEntityBasedCsvUseCase<
+        CsvPaymentDTO,
+        Payment,
+        (PaymentModel, Vec<PaymentAllocationModel>),
+    > for ImportPaymentUseCase
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rust_decimal::Decimal;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::{
+        infrastructure::database::{
+            connection::tests::{
+                get_test_pooled_connection, reset_test_database, HasTestConnection,
+            },
+            models::{
+                invoice::{
+                    tests::{insert_invoice, invoice_model_fixtures, read_open_amounts},
+                    InvoiceOpenAmountModel,
+                },
+                payment::tests::{batch_tuple_fixtures, read_payment_allocations, read_payments},
+            },
+        },
+        tests::load_unit_test_env,
+    };
+
+    fn fixture_path(file_name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(file_name)
+    }
+
+    pub struct ImportPaymentUseCaseTest {
+        allocations: Allocations,
+        invoice_cache: InvoiceCache,
+        pub use_batch: bool,
+    }
+    impl Default for ImportPaymentUseCaseTest {
+        fn default() -> Self {
+            let sources = CsvSources::default();
+            let allocations = ImportPaymentAllocationsUseCase { sources: &sources }
+                .read(
+                    &sources,
+                    CsvType::Test(fixture_path("payment_allocations_for_unit_test.csv")),
+                )
+                .unwrap();
+            Self {
+                allocations: group_allocations(allocations, &CsvDialect::default()).unwrap(),
+                invoice_cache: InvoiceCache::default(),
+                use_batch: false,
+            }
+        }
+    }
+    impl CanReadCSVDataSource<CsvPaymentDTO> for ImportPaymentUseCaseTest {
+        fn find_all(&self) -> Result<Vec<CsvPaymentDTO>, InfrastructureError> {
+            self.read(
+                &CsvSources::default(),
+                CsvType::Test(fixture_path("payments_for_unit_test.csv")),
+            )
+        }
+    }
+    impl CsvEntityParser<CsvPaymentDTO, Payment> for ImportPaymentUseCaseTest {
+        fn transform_csv_row_to_entity(&self, csv: CsvPaymentDTO) -> Result<Payment, MappingError> {
+            let mut connection = HasTestConnection::get_pooled_connection();
+            make_payment(
+                &CsvDialect::default(),
+                &self.allocations,
+                &self.invoice_cache,
+                csv,
+                &mut connection,
+            )
+        }
+    }
+    impl CanPersistIntoDatabaseUseCase<Payment, (PaymentModel, Vec<PaymentAllocationModel>)>
+        for ImportPaymentUseCaseTest
+    {
+        type DbConnection = HasTestConnection;
+        fn set_batch<'a>(
+            &'a self,
+            models: &'a [(PaymentModel, Vec<PaymentAllocationModel>)],
+        ) -> Option<Batch<'a, (PaymentModel, Vec<PaymentAllocationModel>)>> {
+            if self.use_batch {
+                return Some(Batch::new(
+                    models,
+                    None,
+                    batch_upsert,
+                    HasTestConnection::get_pooled_connection(),
+                ));
+            }
+            None
+        }
+    }
+    impl
+        ImportFromSingleEntityBasedCsvUseCase<
+            CsvPaymentDTO,
+            Payment,
+            (PaymentModel, Vec<PaymentAllocationModel>),
+        > for ImportPaymentUseCaseTest
+    {
+    }
+
+    fn arrange(connection: &mut DbConnection) {
+        load_unit_test_env();
+        reset_test_database(connection);
+        for invoice in invoice_model_fixtures() {
+            insert_invoice(connection, false, &invoice).expect("Failed to insert invoice");
+        }
+    }
+
+    fn assert_results(errors: Option<Vec<UseCaseError>>, connection: &mut DbConnection) {
+        assert!(
+            errors.is_some_and(|errs| errs.len() == 1
+                && format!("{:?}", errs[0]).contains("points to unknown invoice 2")),
+            "Failed to execute use case: the allocation of payment 4 has no invoice",
+        );
+        let expected = batch_tuple_fixtures();
+        let payments = read_payments(connection);
+        assert_eq!(
+            payments,
+            expected
+                .iter()
+                .map(|(payment, _)| payment.clone())
+                .collect::<Vec<PaymentModel>>()
+        );
+        for (payment, allocations) in expected {
+            assert_eq!(read_payment_allocations(connection, &payment), allocations);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_payment_use_case() {
+        let mut connection = get_test_pooled_connection();
+        arrange(&mut connection);
+
+        let errors = ImportPaymentUseCaseTest::default().execute();
+
+        assert_results(errors, &mut connection);
+    }
+
+    #[test]
+    #[serial]
+    fn test_batch_payment_use_case() {
+        let mut connection = get_test_pooled_connection();
+        arrange(&mut connection);
+
+        let use_case = ImportPaymentUseCaseTest {
+            use_batch: true,
+            ..Default::default()
+        };
+        let errors = use_case.execute();
+
+        assert_results(errors, &mut connection);
+    }
+
+    #[test]
+    #[serial]
+    fn test_update_open_amounts() {
+        let mut connection = get_test_pooled_connection();
+        arrange(&mut connection);
+
+        // An invoice without allocations is open for its total
+        assert!(update_open_amounts(get_test_pooled_connection(), None, &[1]).is_none());
+        let unpaid = InvoiceOpenAmountModel {
+            id_invoice: 1,
+            open_amount: Decimal::new(12000, 2),
+            is_paid: false,
+        };
+        assert_eq!(read_open_amounts(&mut connection), vec![unpaid.clone()]);
+
+        // Only the given invoices are derived again
+        batch_upsert(&batch_tuple_fixtures(), &mut connection).unwrap();
+        assert!(update_open_amounts(get_test_pooled_connection(), None, &[3]).is_none());
+        let credit_note = InvoiceOpenAmountModel {
+            id_invoice: 3,
+            open_amount: Decimal::new(-16000, 2),
+            is_paid: false,
+        };
+        assert_eq!(
+            read_open_amounts(&mut connection),
+            vec![unpaid, credit_note.clone()]
+        );
+
+        assert!(update_open_amounts(get_test_pooled_connection(), None, &[1, 3]).is_none());
+
+        assert_eq!(
+            read_open_amounts(&mut connection),
+            vec![
+                InvoiceOpenAmountModel {
+                    id_invoice: 1,
+                    open_amount: Decimal::new(0, 2),
+                    is_paid: true,
+                },
+                credit_note,
+            ]
+        );
+    }
+}
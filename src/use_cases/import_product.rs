@@ -5,7 +5,10 @@ use crate::{
         ProductMutateRepository, ProductReadAllRepository, ProductSubstituteReadAllRepository,
     },
     infrastructure::{
-        csv_reader::product::{CsvProductSubstituteDTO, ProductCsvDataSourceReader},
+        csv_reader::{
+            product::{CsvProductSubstituteDTO, ProductCsvDataSourceReader},
+            CsvSources,
+        },
         data_source::CanReadCSVDataSource,
         database::{
             batch::CanMakeBatchTransaction,
@@ -15,6 +18,7 @@ use crate::{
                 product_substitute::{ProductSubstituteModel, ProductSubstituteModelDataSource},
             },
         },
+        profile,
         repository::product::{
             CsvProductRepository, IdProductSubstituteLookupRepository,
             TargetDProductSubstituteRepository,
@@ -26,11 +30,19 @@ use super::{ExecutableUseCase, UseCaseError};
 
 #[derive(Default)]
 pub struct ImportProductUseCase {
+    sources: CsvSources,
     batch: bool,
     batch_size: Option<usize>, //TODO: be consistent between usize or Option<usize>
 }
 
 impl ImportProductUseCase {
+    pub fn new(sources: &CsvSources) -> Self {
+        Self {
+            sources: sources.clone(),
+            ..Self::default()
+        }
+    }
+
     pub fn set_batch(&mut self, batch_size: usize) {
         self.batch = batch_size > 1;
         self.batch_size = Some(batch_size);
@@ -49,39 +61,42 @@ impl ExecutableUseCase for ImportProductUseCase {
         if let Err(e) = substitute_lookup {
             return Some(vec![e]);
         }
+        let substitute_lookup = substitute_lookup.ok();
         let substitute_importer = ImportProductSubstitutesUseCase::new(
-            CsvProductRepository::new(ProductCsvDataSourceReader),
-            TargetDProductSubstituteRepository::new(
-                ProductSubstituteModelDataSource,
-                substitute_lookup.ok(),
-                self.batch,
-                self.batch_size,
-                HasTargetConnection::get_pooled_connection(),
-            ),
+            CsvProductRepository::new(ProductCsvDataSourceReader::new(self.sources.clone())),
+            || {
+                TargetDProductSubstituteRepository::new(
+                    ProductSubstituteModelDataSource,
+                    substitute_lookup.clone(),
+                    self.batch,
+                    self.batch_size,
+                    HasTargetConnection::get_pooled_connection(),
+                )
+            },
         );
 
         substitute_importer.execute()
     }
 }
 
-struct ImportProductSubstitutesUseCase<T, U>
+struct ImportProductSubstitutesUseCase<T, U, F>
 where
     T: CanReadCSVDataSource<CsvProductSubstituteDTO>,
     U: CanMakeBatchTransaction<ProductSubstituteModel>,
+    F: Fn() -> TargetDProductSubstituteRepository<U>,
 {
     csv_repository: CsvProductRepository<T>,
-    db_target_repository: TargetDProductSubstituteRepository<U>,
+    // Called once per target profile
+    db_target_repository: F,
 }
 
-impl<T, U> ImportProductSubstitutesUseCase<T, U>
+impl<T, U, F> ImportProductSubstitutesUseCase<T, U, F>
 where
     T: CanReadCSVDataSource<CsvProductSubstituteDTO>,
     U: CanMakeBatchTransaction<ProductSubstituteModel>,
+    F: Fn() -> TargetDProductSubstituteRepository<U>,
 {
-    pub fn new(
-        csv_repository: CsvProductRepository<T>,
-        db_target_repository: TargetDProductSubstituteRepository<U>,
-    ) -> Self {
+    pub fn new(csv_repository: CsvProductRepository<T>, db_target_repository: F) -> Self {
         Self {
             csv_repository,
             db_target_repository,
@@ -89,10 +104,11 @@ where
     }
 }
 
-impl<T, U> ExecutableUseCase for ImportProductSubstitutesUseCase<T, U>
+impl<T, U, F> ExecutableUseCase for ImportProductSubstitutesUseCase<T, U, F>
 where
     T: CanReadCSVDataSource<CsvProductSubstituteDTO>,
     U: CanMakeBatchTransaction<ProductSubstituteModel>,
+    F: Fn() -> TargetDProductSubstituteRepository<U>,
 {
     fn execute(&self) -> Option<Vec<UseCaseError>> {
         debug!("Fetching products...");
@@ -114,12 +130,14 @@ where
             }
         });
 
-        if let Some(db_errors) = self
-            .db_target_repository
-            .save_substitutes(products.as_slice())
-        {
-            errors.extend(db_errors);
-        }
+        // Products are read and validated once, then saved to every target profile
+        errors.extend(
+            profile::for_each_target(|| {
+                (self.db_target_repository)().save_substitutes(products.as_slice())
+            })
+            .into_iter()
+            .map(|(target, e)| profile::tag_error(target, e)),
+        );
 
         if errors.is_empty() {
             None
@@ -175,18 +193,20 @@ mod tests {
         reset_test_database(&mut connection);
         let use_case = ImportProductSubstitutesUseCase {
             csv_repository: CsvProductRepository::new(MockProductCsvDataSourceReader),
-            db_target_repository: TargetDProductSubstituteRepository::new(
-                ProductMockBatchTransaction,
-                IdProductSubstituteLookupRepository::new(
-                    MockProductLegacyStagingDataSource,
-                    product_legacy_staging_model_to_lookup,
+            db_target_repository: || {
+                TargetDProductSubstituteRepository::new(
+                    ProductMockBatchTransaction,
+                    IdProductSubstituteLookupRepository::new(
+                        MockProductLegacyStagingDataSource,
+                        product_legacy_staging_model_to_lookup,
+                    )
+                    .find_all()
+                    .ok(),
+                    false,
+                    None,
+                    HasTestConnection::get_pooled_connection(),
                 )
-                .find_all()
-                .ok(),
-                false,
-                None,
-                HasTestConnection::get_pooled_connection(),
-            ),
+            },
         };
 
         let errors = use_case.execute();
@@ -0,0 +1,280 @@
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use chrono::{Duration, Local, Months, NaiveDate};
+use diesel::Connection;
+use log::{debug, info};
+
+use crate::{
+    domain::DomainError,
+    infrastructure::{
+        database::{
+            connection::{HasConnection, HasTargetConnection},
+            models::{
+                delivery_slip::DeliverySlipModel,
+                invoice::{InvoiceLangModel, InvoiceModel},
+                order::OrderModel,
+                row_hash::RowHashModel,
+                CanHashModel, CanPurgeModel,
+            },
+        },
+        report, shutdown, InfrastructureError,
+    },
+};
+
+use super::{ExecutableUseCase, UseCaseError};
+
+/// Retention period given as a number followed by d, w, m or y, e.g. 90d or 2y
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RetentionPeriod {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl FromStr for RetentionPeriod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid duration {}, expected a number followed by d, w, m or y",
+                value
+            )
+        };
+        let unit = value.chars().last().ok_or_else(invalid)?;
+        let amount: u32 = value[..value.len() - unit.len_utf8()]
+            .parse()
+            .map_err(|_| invalid())?;
+        match unit {
+            'd' => Ok(RetentionPeriod::Days(amount)),
+            'w' => Ok(RetentionPeriod::Weeks(amount)),
+            'm' => Ok(RetentionPeriod::Months(amount)),
+            'y' => Ok(RetentionPeriod::Years(amount)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl RetentionPeriod {
+    /// Rows strictly older than the returned date are purged
+    pub fn cutoff(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            RetentionPeriod::Days(days) => today.checked_sub_signed(Duration::days(days.into())),
+            RetentionPeriod::Weeks(weeks) => {
+                today.checked_sub_signed(Duration::weeks(weeks.into()))
+            }
+            RetentionPeriod::Months(months) => today.checked_sub_months(Months::new(months)),
+            RetentionPeriod::Years(years) => {
+                today.checked_sub_months(Months::new(years.checked_mul(12)?))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PurgeConfig {
+    pub older_than: RetentionPeriod,
+    pub dry_run: bool,
+    pub chunk_size: usize,
+}
+
+pub type PurgeOrderUseCase = PurgeUseCase<OrderModel, HasTargetConnection>;
+pub type PurgeInvoiceUseCase =
+    PurgeUseCase<(InvoiceModel, Vec<InvoiceLangModel>), HasTargetConnection>;
+pub type PurgeDeliverySlipUseCase = PurgeUseCase<DeliverySlipModel, HasTargetConnection>;
+
+/// Deletes rows older than the retention period, one transaction per chunk
+pub struct PurgeUseCase<M, C> {
+    config: PurgeConfig,
+    today: NaiveDate,
+    _marker: PhantomData<(M, C)>,
+}
+
+impl<M, C> PurgeUseCase<M, C>
+where
+    M: CanPurgeModel + CanHashModel,
+    C: HasConnection,
+{
+    pub fn new(config: PurgeConfig) -> Self {
+        Self {
+            config,
+            today: Local::now().date_naive(),
+            _marker: PhantomData,
+        }
+    }
+
+    // Chunks already purged stay purged when a later one fails
+    fn purge(&self, cutoff: NaiveDate) -> (usize, Option<UseCaseError>) {
+        let mut connection = C::get_pooled_connection();
+        let mut purged = 0;
+        loop {
+            if shutdown::is_requested() {
+                return (purged, Some(InfrastructureError::Interrupted.into()));
+            }
+            let result = connection.transaction(|connection| {
+                let ids = M::select_ids_older_than(
+                    connection,
+                    cutoff,
+                    self.config.chunk_size.max(1) as i64,
+                )?;
+                if ids.is_empty() {
+                    return Ok(0);
+                }
+                let deleted = M::delete_by_ids(connection, &ids)?;
+                let keys: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                RowHashModel::delete_by_keys(connection, M::ENTITY, &keys)?;
+                Ok::<usize, diesel::result::Error>(deleted)
+            });
+            match result {
+                Ok(0) => return (purged, None),
+                Ok(deleted) => purged += deleted,
+                Err(e) => return (purged, Some(e.into())),
+            }
+            debug!("Purged {} {} rows so far", purged, M::ENTITY);
+        }
+    }
+}
+
+impl<M, C> ExecutableUseCase for PurgeUseCase<M, C>
+where
+    M: CanPurgeModel + CanHashModel,
+    C: HasConnection,
+{
+    fn execute(&self) -> Option<Vec<UseCaseError>> {
+        let Some(cutoff) = self.config.older_than.cutoff(self.today) else {
+            return Some(vec![UseCaseError::Domain(DomainError::ValidationError(
+                format!(
+                    "{:?} is out of the supported date range",
+                    self.config.older_than
+                ),
+            ))]);
+        };
+
+        if self.config.dry_run {
+            let mut connection = C::get_pooled_connection();
+            return match M::count_older_than(&mut connection, cutoff) {
+                Ok(count) => {
+                    info!(
+                        "Dry run: {} {} rows older than {} would be purged",
+                        count,
+                        M::ENTITY,
+                        cutoff
+                    );
+                    report::record(M::ENTITY, |r| r.would_purge += count as usize);
+                    None
+                }
+                Err(e) => Some(vec![e.into()]),
+            };
+        }
+
+        let (purged, error) = self.purge(cutoff);
+        info!(
+            "{} {} rows older than {} were purged",
+            purged,
+            M::ENTITY,
+            cutoff
+        );
+        report::record(M::ENTITY, |r| r.purged += purged);
+        error.map(|e| vec![e])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::infrastructure::database::{
+        connection::tests::{get_test_pooled_connection, reset_test_database, HasTestConnection},
+        models::{
+            order::{
+                bench::order_model_fixtures,
+                tests::{insert_order, read_orders},
+            },
+            order_line::tests::{insert_order_line, order_line_model_fixtures, read_order_lines},
+        },
+    };
+
+    fn purge_orders_use_case(dry_run: bool) -> PurgeUseCase<OrderModel, HasTestConnection> {
+        PurgeUseCase {
+            config: PurgeConfig {
+                older_than: RetentionPeriod::Days(1),
+                dry_run,
+                chunk_size: 1,
+            },
+            // Fixture orders are dated from 2023-08-01 to 2023-08-03
+            today: NaiveDate::from_ymd_opt(2023, 8, 3).unwrap(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn arrange() -> crate::infrastructure::database::connection::DbConnection {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+        for order in order_model_fixtures().iter() {
+            insert_order(&mut connection, false, order).expect("Failed to insert order");
+        }
+        for order_line in order_line_model_fixtures().iter() {
+            insert_order_line(&mut connection, false, order_line).expect("Failed to insert line");
+        }
+        connection
+    }
+
+    #[test]
+    fn test_retention_period_from_str() {
+        assert_eq!("90d".parse(), Ok(RetentionPeriod::Days(90)));
+        assert_eq!("12w".parse(), Ok(RetentionPeriod::Weeks(12)));
+        assert_eq!("6m".parse(), Ok(RetentionPeriod::Months(6)));
+        assert_eq!("2y".parse(), Ok(RetentionPeriod::Years(2)));
+        assert!("2".parse::<RetentionPeriod>().is_err());
+        assert!("y".parse::<RetentionPeriod>().is_err());
+        assert!("-2y".parse::<RetentionPeriod>().is_err());
+        assert!("".parse::<RetentionPeriod>().is_err());
+    }
+
+    #[test]
+    fn test_retention_period_cutoff() {
+        let today = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+        assert_eq!(RetentionPeriod::Days(30).cutoff(today), date(2024, 1, 30));
+        assert_eq!(RetentionPeriod::Weeks(1).cutoff(today), date(2024, 2, 22));
+        assert_eq!(RetentionPeriod::Months(1).cutoff(today), date(2024, 1, 29));
+        assert_eq!(RetentionPeriod::Years(1).cutoff(today), date(2023, 2, 28));
+    }
+
+    #[test]
+    #[serial]
+    fn test_dry_run_purges_nothing() {
+        let mut connection = arrange();
+        let before = report::entity_report("order").unwrap_or_default();
+
+        let errors = purge_orders_use_case(true).execute();
+
+        assert!(errors.is_none(), "Failed to purge: {:?}", errors);
+        assert_eq!(read_orders(&mut connection).len(), 3);
+        let after = report::entity_report("order").unwrap_or_default();
+        assert_eq!(after.would_purge - before.would_purge, 1);
+        assert_eq!(after.purged, before.purged);
+    }
+
+    #[test]
+    #[serial]
+    fn test_purge_orders_with_their_lines() {
+        let mut connection = arrange();
+        let before = report::entity_report("order").unwrap_or_default();
+
+        let errors = purge_orders_use_case(false).execute();
+
+        assert!(errors.is_none(), "Failed to purge: {:?}", errors);
+        let after = report::entity_report("order").unwrap_or_default();
+        assert_eq!(after.purged - before.purged, 1);
+        let orders = read_orders(&mut connection);
+        assert_eq!(orders.len(), 2);
+        assert!(orders.iter().all(|order| order.id_order != 1));
+        let order_lines = read_order_lines(&mut connection);
+        assert_eq!(order_lines, vec![order_line_model_fixtures()[2].clone()]);
+    }
+}
@@ -0,0 +1,318 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use diesel::Connection;
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::{
+    domain::DomainError,
+    infrastructure::{
+        csv_reader::CsvDTO,
+        database::{
+            connection::HasConnection,
+            models::{row_hash::RowHashModel, CanHashModel, CanSyncDeletesModel},
+        },
+        report,
+    },
+    use_cases::UseCaseError,
+};
+
+// Keep IN clauses to a reasonable size
+const SYNC_CHUNK_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeletePolicy {
+    Delete,
+    Flag,
+}
+
+impl FromStr for DeletePolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "delete" => Ok(DeletePolicy::Delete),
+            "flag" => Ok(DeletePolicy::Flag),
+            _ => Err(format!(
+                "Unknown delete policy {}, expected delete or flag",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyncDeletesConfig {
+    pub policy: DeletePolicy,
+    // Share of the target rows above which nothing is removed
+    pub max_ratio: f64,
+}
+
+/// Primary keys of the extract read by the import, so the deletes sync does not read it again
+#[derive(Debug, Default)]
+pub struct ExtractIds(RefCell<Option<HashSet<u32>>>);
+
+impl ExtractIds {
+    pub fn keep<I: IntoIterator<Item = u32>>(&self, ids: I) {
+        *self.0.borrow_mut() = Some(ids.into_iter().collect());
+    }
+
+    /// Sorted ids of the extract, none before it is read
+    pub fn to_vec(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.0.borrow().iter().flatten().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+/// Deletes or flags target rows whose primary key is missing from a full snapshot extract
+pub(crate) trait SyncDeletesUseCase<CSV, M>
+where
+    CSV: CsvDTO + for<'a> Deserialize<'a>,
+    M: CanSyncDeletesModel + CanHashModel,
+{
+    type DbConnection: HasConnection;
+
+    fn source_id(csv: &CSV) -> Option<u32>;
+
+    /// Filled when the import reads the extract
+    fn extract_ids(&self) -> &ExtractIds;
+
+    fn sync_deletes(&self, config: &SyncDeletesConfig) -> Option<Vec<UseCaseError>> {
+        let extract_ids = self.extract_ids().0.borrow();
+        let Some(extract_ids) = extract_ids.as_ref() else {
+            return Some(vec![UseCaseError::Domain(DomainError::ValidationError(
+                format!(
+                    "The {} extract was not read, deleted rows are not synchronized",
+                    M::ENTITY
+                ),
+            ))]);
+        };
+
+        let mut connection = Self::DbConnection::get_pooled_connection();
+        let include_flagged = config.policy == DeletePolicy::Delete;
+        let target_ids = match M::select_ids(&mut connection, include_flagged) {
+            Ok(ids) => ids,
+            Err(e) => return Some(vec![e.into()]),
+        };
+        let missing_ids: Vec<u32> = target_ids
+            .iter()
+            .filter(|id| !extract_ids.contains(id))
+            .copied()
+            .collect();
+        if missing_ids.is_empty() {
+            debug!("No {} row is missing from the extract", M::ENTITY);
+            return None;
+        }
+
+        let ratio = missing_ids.len() as f64 / target_ids.len() as f64;
+        if ratio > config.max_ratio {
+            return Some(vec![UseCaseError::Domain(DomainError::ValidationError(
+                format!(
+                    "{} of {} {} rows are missing from the extract ({:.1}%), above the {:.1}% safety limit, nothing was removed",
+                    missing_ids.len(),
+                    target_ids.len(),
+                    M::ENTITY,
+                    ratio * 100.0,
+                    config.max_ratio * 100.0
+                ),
+            ))]);
+        }
+
+        let result = connection.transaction(|connection| {
+            let mut affected = 0;
+            for chunk in missing_ids.chunks(SYNC_CHUNK_SIZE) {
+                affected += match config.policy {
+                    DeletePolicy::Delete => M::delete_by_ids(connection, chunk)?,
+                    DeletePolicy::Flag => M::flag_by_ids(connection, chunk)?,
+                };
+                // Removed or flagged rows must be written again if they come back
+                let keys: Vec<String> = chunk.iter().map(|id| id.to_string()).collect();
+                RowHashModel::delete_by_keys(connection, M::ENTITY, &keys)?;
+            }
+            Ok::<usize, diesel::result::Error>(affected)
+        });
+
+        match result {
+            Ok(affected) => {
+                info!(
+                    "{} {} rows missing from the extract were {}",
+                    affected,
+                    M::ENTITY,
+                    match config.policy {
+                        DeletePolicy::Delete => "deleted",
+                        DeletePolicy::Flag => "flagged",
+                    }
+                );
+                report::record(M::ENTITY, |r| match config.policy {
+                    DeletePolicy::Delete => r.deleted += affected,
+                    DeletePolicy::Flag => r.flagged += affected,
+                });
+                None
+            }
+            Err(e) => Some(vec![e.into()]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::infrastructure::{
+        csv_reader::{order::CsvOrderDTO, CsvSources, CsvType},
+        data_source::CanReadCSVDataSource,
+        database::{
+            connection::{
+                tests::{get_test_pooled_connection, reset_test_database, HasTestConnection},
+                DbConnection,
+            },
+            models::{
+                order::{
+                    bench::order_model_fixtures,
+                    tests::{insert_order, read_flagged_order_ids, read_orders},
+                    OrderModel,
+                },
+                order_line::tests::{
+                    insert_order_line, order_line_model_fixtures, read_order_lines,
+                },
+            },
+        },
+        InfrastructureError,
+    };
+
+    #[derive(Default)]
+    struct SyncOrderDeletesUseCaseTest {
+        extract_ids: ExtractIds,
+    }
+    impl CanReadCSVDataSource<CsvOrderDTO> for SyncOrderDeletesUseCaseTest {
+        fn find_all(&self) -> Result<Vec<CsvOrderDTO>, InfrastructureError> {
+            let root_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            let csv_path = root_path
+                .join("tests")
+                .join("fixtures")
+                .join("order_for_unit_test.csv");
+            let extract = self.read(&CsvSources::default(), CsvType::Test(csv_path))?;
+            self.extract_ids
+                .keep(extract.iter().filter_map(Self::source_id));
+            Ok(extract)
+        }
+    }
+    impl SyncDeletesUseCase<CsvOrderDTO, OrderModel> for SyncOrderDeletesUseCaseTest {
+        type DbConnection = HasTestConnection;
+
+        fn source_id(csv: &CsvOrderDTO) -> Option<u32> {
+            csv.c_order_id.parse().ok()
+        }
+
+        fn extract_ids(&self) -> &ExtractIds {
+            &self.extract_ids
+        }
+    }
+
+    // The import reads the extract before the deletes are synchronized
+    fn read_extract() -> SyncOrderDeletesUseCaseTest {
+        let use_case = SyncOrderDeletesUseCaseTest::default();
+        use_case.find_all().expect("Failed to read the extract");
+        use_case
+    }
+
+    // Orders 1 and 2 are in the extract, order 3 is not
+    fn arrange() -> DbConnection {
+        let mut connection = get_test_pooled_connection();
+        reset_test_database(&mut connection);
+        for order in order_model_fixtures().iter() {
+            insert_order(&mut connection, false, order).expect("Failed to insert order");
+        }
+        let mut order_line = order_line_model_fixtures()[2].clone();
+        order_line.id_order = 3;
+        insert_order_line(&mut connection, false, &order_line).expect("Failed to insert line");
+        connection
+    }
+
+    #[test]
+    #[serial]
+    fn test_flag_missing_orders() {
+        let mut connection = arrange();
+        let config = SyncDeletesConfig {
+            policy: DeletePolicy::Flag,
+            max_ratio: 0.5,
+        };
+
+        let errors = read_extract().sync_deletes(&config);
+
+        assert!(errors.is_none(), "Failed to sync deletes: {:?}", errors);
+        let orders = read_orders(&mut connection);
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0], order_model_fixtures()[0]);
+        assert_eq!(orders[2], order_model_fixtures()[2]);
+        assert_eq!(read_flagged_order_ids(&mut connection), vec![3]);
+        assert_eq!(read_order_lines(&mut connection).len(), 1);
+    }
+
+    #[test]
+    #[serial]
+    fn test_delete_missing_orders_with_their_lines() {
+        let mut connection = arrange();
+        let config = SyncDeletesConfig {
+            policy: DeletePolicy::Delete,
+            max_ratio: 0.5,
+        };
+
+        let errors = read_extract().sync_deletes(&config);
+
+        assert!(errors.is_none(), "Failed to sync deletes: {:?}", errors);
+        let orders = read_orders(&mut connection);
+        assert_eq!(orders.len(), 2);
+        assert!(orders.iter().all(|order| order.id_order != 3));
+        assert!(read_order_lines(&mut connection).is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn test_safety_limit_prevents_removal() {
+        let mut connection = arrange();
+        let config = SyncDeletesConfig {
+            policy: DeletePolicy::Delete,
+            max_ratio: 0.2,
+        };
+
+        let errors = read_extract().sync_deletes(&config);
+
+        assert!(matches!(
+            errors.as_deref(),
+            Some([UseCaseError::Domain(DomainError::ValidationError(_))])
+        ));
+        assert_eq!(read_orders(&mut connection).len(), 3);
+    }
+
+    #[test]
+    #[serial]
+    fn test_extract_must_be_read_first() {
+        let mut connection = arrange();
+        let config = SyncDeletesConfig {
+            policy: DeletePolicy::Delete,
+            max_ratio: 1.0,
+        };
+
+        let errors = SyncOrderDeletesUseCaseTest::default().sync_deletes(&config);
+
+        assert!(matches!(
+            errors.as_deref(),
+            Some([UseCaseError::Domain(DomainError::ValidationError(_))])
+        ));
+        assert_eq!(read_orders(&mut connection).len(), 3);
+    }
+
+    #[test]
+    fn test_delete_policy_from_str() {
+        assert_eq!(DeletePolicy::from_str("delete"), Ok(DeletePolicy::Delete));
+        assert_eq!(DeletePolicy::from_str("flag"), Ok(DeletePolicy::Flag));
+        assert!(DeletePolicy::from_str("purge").is_err());
+    }
+}
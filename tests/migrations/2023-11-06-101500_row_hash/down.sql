@@ -0,0 +1 @@
+DROP TABLE IF EXISTS `fluetl_row_hash`;
@@ -0,0 +1,10 @@
+/** ROW HASH (incremental mode) */
+CREATE TABLE `fluetl_row_hash`
+(
+    `entity`   varchar(64)  NOT NULL,
+    `row_key`  varchar(255) NOT NULL,
+    `row_hash` char(64)     NOT NULL,
+    PRIMARY KEY (`entity`, `row_key`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4
+  COLLATE = utf8mb4_unicode_ci;
@@ -0,0 +1,4 @@
+ALTER TABLE `order`
+    DROP COLUMN `is_flagged`;
+ALTER TABLE `delivery_slip`
+    DROP COLUMN `is_flagged`;
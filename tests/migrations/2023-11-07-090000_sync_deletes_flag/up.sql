@@ -0,0 +1,5 @@
+/** ROWS MISSING FROM A FULL EXTRACT, flagged by --sync-deletes */
+ALTER TABLE `order`
+    ADD COLUMN `is_flagged` tinyint(1) NOT NULL DEFAULT 0;
+ALTER TABLE `delivery_slip`
+    ADD COLUMN `is_flagged` tinyint(1) NOT NULL DEFAULT 0;
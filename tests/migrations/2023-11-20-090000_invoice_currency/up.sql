@@ -0,0 +1,3 @@
+/** INVOICE CURRENCY (ISO 4217) */
+ALTER TABLE `invoice`
+    ADD COLUMN `currency` varchar(3) NOT NULL DEFAULT 'EUR' AFTER `total_tax_incl`;
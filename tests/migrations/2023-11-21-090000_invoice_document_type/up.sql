@@ -0,0 +1,3 @@
+/** INVOICE DOCUMENT TYPE (invoice or credit_note) */
+ALTER TABLE `invoice`
+    ADD COLUMN `document_type` varchar(16) NOT NULL DEFAULT 'invoice' AFTER `currency`;
@@ -0,0 +1,16 @@
+/** INVOICE LINE */
+-- No foreign key: invoices are upserted with REPLACE, which would cascade to their lines
+DROP TABLE IF EXISTS `invoice_line`;
+CREATE TABLE `invoice_line` (
+  `id_invoice_line` int(10) unsigned NOT NULL,
+  `id_invoice` int(10) unsigned NOT NULL,
+  `product_ref` varchar(64) NOT NULL,
+  -- Negative for returned items of credit notes
+  `quantity` decimal(12,3) NOT NULL,
+  `unit_price` decimal(10,2) NOT NULL,
+  `tax_rate` decimal(5,2) NOT NULL,
+  `total_tax_excl` decimal(10,2) NOT NULL,
+  `total_tax_incl` decimal(10,2) NOT NULL,
+  PRIMARY KEY (`id_invoice_line`),
+  KEY `IDX_INVOICE_LINE_ID_INVOICE` (`id_invoice`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
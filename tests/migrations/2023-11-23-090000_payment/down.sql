@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS `invoice_open_amount`;
+DROP TABLE IF EXISTS `payment_allocation`;
+DROP TABLE IF EXISTS `payment`;
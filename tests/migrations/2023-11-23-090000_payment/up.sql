@@ -0,0 +1,35 @@
+/** PAYMENT */
+DROP TABLE IF EXISTS `payment`;
+CREATE TABLE `payment` (
+  `id_payment` int(10) unsigned NOT NULL,
+  `payment_ref` varchar(32) NOT NULL,
+  `date` date NOT NULL,
+  `amount` decimal(10,2) NOT NULL,
+  `currency` varchar(3) NOT NULL DEFAULT 'EUR',
+  PRIMARY KEY (`id_payment`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+/** PAYMENT ALLOCATION */
+-- No foreign key: payments are upserted with REPLACE, which would cascade to their allocations
+DROP TABLE IF EXISTS `payment_allocation`;
+CREATE TABLE `payment_allocation` (
+  `id_allocation_line` int(10) unsigned NOT NULL,
+  `id_payment` int(10) unsigned NOT NULL,
+  `id_invoice` int(10) unsigned NOT NULL,
+  `amount` decimal(10,2) NOT NULL,
+  `discount` decimal(10,2) NOT NULL DEFAULT 0,
+  `write_off` decimal(10,2) NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id_allocation_line`),
+  KEY `IDX_PAYMENT_ALLOCATION_ID_PAYMENT` (`id_payment`),
+  KEY `IDX_PAYMENT_ALLOCATION_ID_INVOICE` (`id_invoice`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+/** INVOICE OPEN AMOUNT */
+-- Derived from the invoice totals and the payment allocations
+DROP TABLE IF EXISTS `invoice_open_amount`;
+CREATE TABLE `invoice_open_amount` (
+  `id_invoice` int(10) unsigned NOT NULL,
+  `open_amount` decimal(10,2) NOT NULL,
+  `is_paid` tinyint(1) NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id_invoice`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
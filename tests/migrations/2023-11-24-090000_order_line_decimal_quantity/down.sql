@@ -0,0 +1,5 @@
+ALTER TABLE `order_line`
+    DROP COLUMN `uom`,
+    MODIFY COLUMN `qty_ordered` int(10) unsigned NOT NULL,
+    MODIFY COLUMN `qty_reserved` int(10) unsigned NOT NULL,
+    MODIFY COLUMN `qty_delivered` int(10) unsigned NOT NULL;
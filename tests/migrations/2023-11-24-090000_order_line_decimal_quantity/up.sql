@@ -0,0 +1,6 @@
+/** ORDER LINE DECIMAL QUANTITIES, negative for returns, and their unit of measure */
+ALTER TABLE `order_line`
+    MODIFY COLUMN `qty_ordered` decimal(12,3) NOT NULL,
+    MODIFY COLUMN `qty_reserved` decimal(12,3) NOT NULL,
+    MODIFY COLUMN `qty_delivered` decimal(12,3) NOT NULL,
+    ADD COLUMN `uom` varchar(16) DEFAULT NULL AFTER `qty_delivered`;
@@ -0,0 +1,4 @@
+/** ORDER CSV COMPLETION, kept when the completion is recomputed from the order lines */
+ALTER TABLE `order`
+    ADD COLUMN `csv_completion` int(10) unsigned DEFAULT NULL AFTER `completion`;
+UPDATE `order` SET `csv_completion` = `completion`;